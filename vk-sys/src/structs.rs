@@ -1410,3 +1410,32 @@ pub struct DebugReportCallbackCreateInfoEXT {
     pub pfnCallback: PFN_vkDebugReportCallbackEXT,
     pub pUserData: *mut c_void,
 }
+
+
+#[repr(C)]
+pub struct DebugMarkerObjectNameInfoEXT {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub objectType: DebugReportObjectTypeEXT,
+    pub object: u64,
+    pub pObjectName: *const c_char,
+}
+
+#[repr(C)]
+pub struct DebugMarkerObjectTagInfoEXT {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub objectType: DebugReportObjectTypeEXT,
+    pub object: u64,
+    pub tagName: u64,
+    pub tagSize: usize,
+    pub pTag: *const c_void,
+}
+
+#[repr(C)]
+pub struct DebugMarkerMarkerInfoEXT {
+    pub sType: StructureType,
+    pub pNext: *const c_void,
+    pub pMarkerName: *const c_char,
+    pub color: [f32; 4],
+}
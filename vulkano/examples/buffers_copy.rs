@@ -44,10 +44,12 @@ fn main() {
         .copy_buffer(&src, &dest)
         .build().unwrap();
 
-    {
+    let submission = {
         let mut queue = queue.lock().unwrap();
-        command_buffer.submit(&mut queue).unwrap();
-    }
+        vulkano::command_buffer::PrimaryCommandBuffer::submit(&command_buffer, &mut queue).unwrap()
+    };
+
+    submission.wait(1000000000).unwrap();
 
     {
         let mut mapping = dest.read(1000000000);
@@ -188,7 +188,8 @@ fn main() {
         };
 
         vulkano::pipeline::GraphicsPipeline::new(&device, &vs.main_entry_point(), &ia, &viewports,
-                                                 &raster, &ms, &blend, &fs.main_entry_point(),
+                                                 &raster, &ms, &vulkano::pipeline::depth_stencil::DepthStencil::simple_depth_test(),
+                                                 &blend, &fs.main_entry_point(),
                                                  &pipeline_layout, &renderpass.subpass(0).unwrap())
                                                  .unwrap()
     };
@@ -201,16 +202,19 @@ fn main() {
     let command_buffers = framebuffers.iter().map(|framebuffer| {
         vulkano::command_buffer::PrimaryCommandBufferBuilder::new(&cb_pool).unwrap()
             .draw_inline(&renderpass, &framebuffer, ([0.0, 0.0, 1.0, 1.0], 1.0))
-            .draw_indexed(&pipeline, (vertex_buffer.clone(), normals_buffer.clone()), &index_buffer, &vulkano::command_buffer::DynamicState::none(), set.clone())
+            .draw_indexed(&pipeline, (vertex_buffer.clone(), normals_buffer.clone()), &index_buffer, 0 .. index_buffer.len() as u32, 0, 0 .. 1, &vulkano::command_buffer::DynamicState::none(), set.clone(), &()).unwrap()
             .draw_end()
             .build().unwrap()
     }).collect::<Vec<_>>();
 
+    let mut previous_submission = None;
+
     loop {
         let image_num = swapchain.acquire_next_image(1000000).unwrap();
         let mut queue = queue.lock().unwrap();
-        command_buffers[image_num].submit(&mut queue).unwrap();
+        let submission = vulkano::command_buffer::PrimaryCommandBuffer::submit(&command_buffers[image_num], &mut queue).unwrap();
         swapchain.present(&mut queue, image_num).unwrap();
+        previous_submission = Some(submission);
         drop(queue);
 
         for ev in window.poll_events() {
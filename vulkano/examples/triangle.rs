@@ -190,7 +190,7 @@ fn main() {
         };
 
         vulkano::pipeline::GraphicsPipeline::new(&device, &vs.main_entry_point(), &ia, &viewports,
-                                                 &raster, &ms, &blend, &fs.main_entry_point(), &vulkano::descriptor_set::PipelineLayout::new(&device, Default::default(), ((), ())).unwrap(),
+                                                 &raster, &ms, &vulkano::pipeline::depth_stencil::DepthStencil::disabled(), &blend, &fs.main_entry_point(), &vulkano::descriptor_set::PipelineLayout::new(&device, Default::default(), ((), ())).unwrap(),
                                                  &renderpass.subpass(0).unwrap()).unwrap()
     };
 
@@ -215,7 +215,7 @@ fn main() {
     let command_buffers = framebuffers.iter().map(|framebuffer| {
         vulkano::command_buffer::PrimaryCommandBufferBuilder::new(&cb_pool).unwrap()
             .draw_inline(&renderpass, &framebuffer, [0.0, 0.0, 1.0, 1.0])
-            .draw(&pipeline, vertex_buffer.clone(), &vulkano::command_buffer::DynamicState::none(), ((), ()))
+            .draw(&pipeline, vertex_buffer.clone(), 0 .. 3, 0 .. 1, &vulkano::command_buffer::DynamicState::none(), ((), ()), &()).unwrap()
             .draw_end()
             .build().unwrap()
     }).collect::<Vec<_>>();
@@ -226,6 +226,12 @@ fn main() {
     // Everything else is kept alive internally with `Arc`s (even the vertex buffer for example),
     // so the only variable that we need is this one.
 
+    // Submitting a command buffer returns a `Submission` object that must be kept alive until
+    // the GPU has finished executing the command buffer (destroying it blocks until then). We
+    // keep the submission of the previous frame here, so that the CPU can prepare a frame while
+    // the GPU is drawing the previous one.
+    let mut previous_submission = None;
+
     loop {
         // Before we can draw on the output, we have to *acquire* an image from the swapchain.
         // This operation returns the index of the image that we are allowed to draw upon..
@@ -235,13 +241,16 @@ fn main() {
         let mut queue = queue.lock().unwrap();
 
         // In order to draw, all we need to do is submit the command buffer to the queue.
-        command_buffers[image_num].submit(&mut queue).unwrap();
+        let submission = vulkano::command_buffer::PrimaryCommandBuffer::submit(&command_buffers[image_num], &mut queue).unwrap();
 
         // The color output should now contain our triangle. But in order to show it on the
         // screen, we have to *present* the image. Depending on the presentation mode, this may
         // be shown immediatly or on the next redraw.
         swapchain.present(&mut queue, image_num).unwrap();
 
+        // Replacing the previous submission waits until it is finished.
+        previous_submission = Some(submission);
+
         // In a real application we want to submit things to the same queue in parallel, so we
         // shouldn't keep it locked too long.
         drop(queue);
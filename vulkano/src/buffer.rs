@@ -17,9 +17,11 @@
 //!
 use std::marker::PhantomData;
 use std::mem;
+use std::ops::Range;
 use std::ptr;
 use std::sync::Arc;
 
+use device::DebugName;
 use device::Device;
 use device::Queue;
 use memory::CpuAccessible;
@@ -29,6 +31,7 @@ use memory::ChunkRange;
 use memory::MemorySource;
 use memory::MemorySourceChunk;
 use sync::Fence;
+use sync::FenceWaitError;
 use sync::Resource;
 use sync::Semaphore;
 use sync::SharingMode;
@@ -247,6 +250,13 @@ impl<T: ?Sized, M> Buffer<T, M> {
     pub fn usage_indirect_buffer(&self) -> bool {
         (self.inner.usage & vk::BUFFER_USAGE_INDIRECT_BUFFER_BIT) != 0
     }
+
+    /// Returns the chunk of memory that the buffer is bound to.
+    #[doc(hidden)]
+    #[inline]
+    pub fn memory(&self) -> &M {
+        &self.inner.memory
+    }
 }
 
 impl<T, M> Buffer<[T], M> {
@@ -293,9 +303,11 @@ unsafe impl<T: ?Sized, M> BufferResource for Buffer<T, M> where M: MemorySourceC
 impl<'a, T: ?Sized, M> Buffer<T, M> where M: CpuAccessible<'a, T> {
     /// Gives a read access to the content of the buffer.
     ///
-    /// If the buffer is in use by the GPU, blocks until it is available.
+    /// If the buffer is in use by the GPU, blocks until it is available. Returns
+    /// `FenceWaitError::Timeout` if it is still in use after the number of nanoseconds of the
+    /// timeout has elapsed.
     #[inline]
-    pub fn read(&'a self, timeout_ns: u64) -> M::Read {
+    pub fn read(&'a self, timeout_ns: u64) -> Result<M::Read, FenceWaitError> {
         self.inner.memory.read(timeout_ns)
     }
 
@@ -311,9 +323,11 @@ impl<'a, T: ?Sized, M> Buffer<T, M> where M: CpuAccessible<'a, T> {
 impl<'a, T: ?Sized, M> Buffer<T, M> where M: CpuWriteAccessible<'a, T> {
     /// Gives a write access to the content of the buffer.
     ///
-    /// If the buffer is in use by the GPU, blocks until it is available.
+    /// If the buffer is in use by the GPU, blocks until it is available. Returns
+    /// `FenceWaitError::Timeout` if it is still in use after the number of nanoseconds of the
+    /// timeout has elapsed.
     #[inline]
-    pub fn write(&'a self, timeout_ns: u64) -> M::Write {
+    pub fn write(&'a self, timeout_ns: u64) -> Result<M::Write, FenceWaitError> {
         self.inner.memory.write(timeout_ns)
     }
 
@@ -332,7 +346,7 @@ unsafe impl<'a, T: ?Sized, M> CpuAccessible<'a, T> for Buffer<T, M>
     type Read = M::Read;
 
     #[inline]
-    fn read(&'a self, timeout_ns: u64) -> M::Read {
+    fn read(&'a self, timeout_ns: u64) -> Result<M::Read, FenceWaitError> {
         self.read(timeout_ns)
     }
 
@@ -348,7 +362,7 @@ unsafe impl<'a, T: ?Sized, M> CpuWriteAccessible<'a, T> for Buffer<T, M>
     type Write = M::Write;
 
     #[inline]
-    fn write(&'a self, timeout_ns: u64) -> M::Write {
+    fn write(&'a self, timeout_ns: u64) -> Result<M::Write, FenceWaitError> {
         self.write(timeout_ns)
     }
 
@@ -367,6 +381,15 @@ unsafe impl<T: ?Sized, M> VulkanObject for Buffer<T, M> {
     }
 }
 
+unsafe impl<T: ?Sized, M> DebugName for Buffer<T, M> {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_BUFFER_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.inner.device
+    }
+}
+
 impl<T: ?Sized, M> Drop for Buffer<T, M> {
     #[inline]
     fn drop(&mut self) {
@@ -410,6 +433,22 @@ impl Usage {
         }
     }
 
+    /// Builds a `Usage` with only `transfer_source` set to true. Useful for staging buffers.
+    #[inline]
+    pub fn transfer_source() -> Usage {
+        Usage {
+            transfer_source: true,
+            transfer_dest: false,
+            uniform_texel_buffer: false,
+            storage_texel_buffer: false,
+            uniform_buffer: false,
+            storage_buffer: false,
+            index_buffer: false,
+            vertex_buffer: false,
+            indirect_buffer: false,
+        }
+    }
+
     #[inline]
     fn to_usage_bits(&self) -> vk::BufferUsageFlagBits {
         let mut result = 0;
@@ -445,6 +484,12 @@ impl<'a, T: ?Sized + 'a, M: 'a> BufferSlice<'a, T, M> {
         &self.resource
     }
 
+    /// Returns the device that owns the buffer.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.inner.device
+    }
+
     /// Returns the offset of that slice within the buffer.
     #[inline]
     pub fn offset(&self) -> usize {
@@ -512,12 +557,63 @@ impl<'a, T: ?Sized + 'a, M: 'a> BufferSlice<'a, T, M> {
     }
 }
 
+impl<'a, T: ?Sized + 'a, M: 'a> BufferSlice<'a, T, M> where M: MemorySourceChunk + 'static {
+    /// Builds a slice of `size` bytes starting at `offset` bytes within `buffer`, interpreting
+    /// its content as a `T`.
+    ///
+    /// # Safety
+    ///
+    /// - The range must be within the buffer.
+    /// - The content of the range must be valid for `T`, and `size` must be suitable for `T`.
+    ///
+    #[doc(hidden)]
+    #[inline]
+    pub unsafe fn from_raw_parts<U: ?Sized + 'static>(buffer: &'a Arc<Buffer<U, M>>,
+                                                      offset: usize, size: usize)
+                                                      -> BufferSlice<'a, T, M>
+    {
+        debug_assert!(offset + size <= buffer.inner.size);
+
+        BufferSlice {
+            marker: PhantomData,
+            resource: buffer.clone(),
+            inner: &buffer.inner,
+            offset: offset,
+            size: size,
+        }
+    }
+}
+
 impl<'a, T: 'a, M: 'a> BufferSlice<'a, [T], M> {
     /// Returns the number of elements in this slice.
     #[inline]
     pub fn len(&self) -> usize {
         self.size() / mem::size_of::<T>()
     }
+
+    /// Builds a slice that contains the elements of this slice within `range`.
+    ///
+    /// Returns `None` if the range is out of range of this slice.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let slice = BufferSlice::from(&buffer).slice(10 .. 20).unwrap();
+    /// ```
+    #[inline]
+    pub fn slice(self, range: Range<usize>) -> Option<BufferSlice<'a, [T], M>> {
+        if range.start > range.end || range.end > self.len() {
+            return None;
+        }
+
+        Some(BufferSlice {
+            marker: PhantomData,
+            resource: self.resource,
+            inner: self.inner,
+            offset: self.offset + range.start * mem::size_of::<T>(),
+            size: (range.end - range.start) * mem::size_of::<T>(),
+        })
+    }
 }
 
 unsafe impl<'a, T: ?Sized, M> VulkanObject for BufferSlice<'a, T, M> {
@@ -571,6 +667,7 @@ mod tests {
 
     use buffer::Usage;
     use buffer::Buffer;
+    use buffer::BufferSlice;
     use memory::DeviceLocal;
 
     #[test]
@@ -580,6 +677,24 @@ mod tests {
         let _ = Buffer::<[i8; 16], _>::new(&device, &Usage::all(), DeviceLocal, &queue).unwrap();
     }
 
+    #[test]
+    fn slice() {
+        let (device, queue) = gfx_dev_and_queue!();
+
+        let b = Buffer::<[i16], _>::array(&device, 12, &Usage::all(),
+                                          DeviceLocal, &queue).unwrap();
+
+        let slice = BufferSlice::from(&b).slice(2 .. 5).unwrap();
+        assert_eq!(slice.offset(), 2 * mem::size_of::<i16>());
+        assert_eq!(slice.len(), 3);
+
+        let slice = slice.slice(1 .. 2).unwrap();
+        assert_eq!(slice.offset(), 3 * mem::size_of::<i16>());
+        assert_eq!(slice.len(), 1);
+
+        assert!(BufferSlice::from(&b).slice(10 .. 13).is_none());
+    }
+
     #[test]
     fn array_len() {
         let (device, queue) = gfx_dev_and_queue!();
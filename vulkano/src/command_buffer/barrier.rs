@@ -0,0 +1,271 @@
+use std::ptr;
+use std::sync::Arc;
+
+use buffer::BufferResource;
+use buffer::BufferSlice;
+use command_buffer::Command;
+use formats::Format;
+use formats::FormatMarker;
+use formats::FormatTy;
+use image::Image;
+use image::ImageResource;
+use image::ImageTypeMarker;
+use image::Layout;
+use memory::MemorySourceChunk;
+use sync::AccessFlagBits;
+use sync::PipelineStages;
+
+use VulkanObject;
+use vk;
+
+/// Prototype for a pipeline barrier that can be added to a command buffer.
+///
+/// A pipeline barrier ensures that the commands that have been added before it are (at least
+/// partially) finished before the commands that are added after it start, and that the memory
+/// writes of the former are visible to the latter.
+///
+/// Command buffers automatically insert the barriers that are needed between the commands that
+/// they know about. Adding a pipeline barrier manually is only required in situations that
+/// can't be tracked, for example when a shader writes to a buffer through a descriptor set and
+/// a later shader reads from it.
+pub struct PipelineBarrierBuilder {
+    src_stage_mask: vk::PipelineStageFlags,
+    dst_stage_mask: vk::PipelineStageFlags,
+    memory_barriers: Vec<vk::MemoryBarrier>,
+    buffer_barriers: Vec<vk::BufferMemoryBarrier>,
+    image_barriers: Vec<vk::ImageMemoryBarrier>,
+
+    // Resources that are referenced by the barriers. They must be kept alive by the command
+    // buffer.
+    buffer_resources: Vec<Arc<BufferResource>>,
+    image_resources: Vec<Arc<ImageResource>>,
+}
+
+impl PipelineBarrierBuilder {
+    /// Builds a new empty pipeline barrier.
+    #[inline]
+    pub fn new() -> PipelineBarrierBuilder {
+        PipelineBarrierBuilder {
+            src_stage_mask: 0,
+            dst_stage_mask: 0,
+            memory_barriers: Vec::new(),
+            buffer_barriers: Vec::new(),
+            image_barriers: Vec::new(),
+            buffer_resources: Vec::new(),
+            image_resources: Vec::new(),
+        }
+    }
+
+    /// Returns true if no dependency has been added to this barrier.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.src_stage_mask == 0 || self.dst_stage_mask == 0
+    }
+
+    /// Adds an execution dependency: the `destination` stages of the commands after the barrier
+    /// can't start before the `source` stages of the commands before the barrier are finished.
+    ///
+    /// No memory barrier is added, therefore this is only enough to handle write-after-read
+    /// hazards.
+    #[inline]
+    pub fn add_execution_dependency(&mut self, source: PipelineStages,
+                                    destination: PipelineStages)
+    {
+        unsafe { self.add_raw_execution_dependency(source.into(), destination.into()) }
+    }
+
+    /// Adds a global memory barrier: all the writes described by `source_access` performed by
+    /// the `source_stage` stages of the commands before the barrier are made visible to the
+    /// accesses described by `destination_access` performed by the `destination_stage` stages of
+    /// the commands after the barrier.
+    #[inline]
+    pub fn add_memory_barrier(&mut self, source_stage: PipelineStages,
+                              source_access: AccessFlagBits, destination_stage: PipelineStages,
+                              destination_access: AccessFlagBits)
+    {
+        unsafe {
+            self.add_raw_memory_barrier(source_stage.into(), source_access.into(),
+                                        destination_stage.into(), destination_access.into())
+        }
+    }
+
+    /// Same as `add_memory_barrier`, but only applies to a slice of a buffer.
+    pub fn add_buffer_memory_barrier<'a, B, T: ?Sized + 'a, M: 'a>(&mut self, buffer: B,
+                                                                 source_stage: PipelineStages,
+                                                                 source_access: AccessFlagBits,
+                                                                 destination_stage: PipelineStages,
+                                                                 destination_access: AccessFlagBits)
+        where B: Into<BufferSlice<'a, T, M>>
+    {
+        let buffer = buffer.into();
+
+        unsafe {
+            self.add_raw_buffer_barrier(buffer.internal_object(),
+                                        buffer.offset() as vk::DeviceSize,
+                                        buffer.size() as vk::DeviceSize, source_stage.into(),
+                                        source_access.into(),
+                                        destination_stage.into(), destination_access.into());
+        }
+
+        self.buffer_resources.push(buffer.buffer().clone());
+    }
+
+    /// Same as `add_memory_barrier`, but only applies to an image.
+    ///
+    /// The layout of the image is not modified.
+    pub fn add_image_memory_barrier<Ty, F, M>(&mut self, image: &Arc<Image<Ty, F, M>>,
+                                              source_stage: PipelineStages,
+                                              source_access: AccessFlagBits,
+                                              destination_stage: PipelineStages,
+                                              destination_access: AccessFlagBits)
+        where Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              M: MemorySourceChunk + 'static
+    {
+        unsafe {
+            let layout = image.default_layout();
+            self.add_raw_image_barrier(image.internal_object(), aspect_mask(F::format()),
+                                       layout, layout, source_stage.into(),
+                                       source_access.into(), destination_stage.into(),
+                                       destination_access.into());
+        }
+
+        self.image_resources.push(image.clone());
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub unsafe fn add_raw_execution_dependency(&mut self, source: vk::PipelineStageFlags,
+                                               destination: vk::PipelineStageFlags)
+    {
+        self.src_stage_mask |= source;
+        self.dst_stage_mask |= destination;
+    }
+
+    #[doc(hidden)]
+    pub unsafe fn add_raw_memory_barrier(&mut self, source_stage: vk::PipelineStageFlags,
+                                         source_access: vk::AccessFlags,
+                                         destination_stage: vk::PipelineStageFlags,
+                                         destination_access: vk::AccessFlags)
+    {
+        self.add_raw_execution_dependency(source_stage, destination_stage);
+
+        self.memory_barriers.push(vk::MemoryBarrier {
+            sType: vk::STRUCTURE_TYPE_MEMORY_BARRIER,
+            pNext: ptr::null(),
+            srcAccessMask: source_access,
+            dstAccessMask: destination_access,
+        });
+    }
+
+    #[doc(hidden)]
+    pub unsafe fn add_raw_buffer_barrier(&mut self, buffer: vk::Buffer, offset: vk::DeviceSize,
+                                         size: vk::DeviceSize,
+                                         source_stage: vk::PipelineStageFlags,
+                                         source_access: vk::AccessFlags,
+                                         destination_stage: vk::PipelineStageFlags,
+                                         destination_access: vk::AccessFlags)
+    {
+        self.add_raw_execution_dependency(source_stage, destination_stage);
+
+        self.buffer_barriers.push(vk::BufferMemoryBarrier {
+            sType: vk::STRUCTURE_TYPE_BUFFER_MEMORY_BARRIER,
+            pNext: ptr::null(),
+            srcAccessMask: source_access,
+            dstAccessMask: destination_access,
+            srcQueueFamilyIndex: vk::QUEUE_FAMILY_IGNORED,
+            dstQueueFamilyIndex: vk::QUEUE_FAMILY_IGNORED,
+            buffer: buffer,
+            offset: offset,
+            size: size,
+        });
+    }
+
+    #[doc(hidden)]
+    pub unsafe fn add_raw_image_barrier(&mut self, image: vk::Image,
+                                        aspect_mask: vk::ImageAspectFlags, old_layout: Layout,
+                                        new_layout: Layout, source_stage: vk::PipelineStageFlags,
+                                        source_access: vk::AccessFlags,
+                                        destination_stage: vk::PipelineStageFlags,
+                                        destination_access: vk::AccessFlags)
+    {
+        // a source stage mask of 0 is forbidden, which can happen when the image hasn't been
+        // used yet and only needs a layout transition
+        let source_stage = if source_stage == 0 {
+            vk::PIPELINE_STAGE_TOP_OF_PIPE_BIT
+        } else {
+            source_stage
+        };
+
+        self.add_raw_execution_dependency(source_stage, destination_stage);
+
+        self.image_barriers.push(vk::ImageMemoryBarrier {
+            sType: vk::STRUCTURE_TYPE_IMAGE_MEMORY_BARRIER,
+            pNext: ptr::null(),
+            srcAccessMask: source_access,
+            dstAccessMask: destination_access,
+            oldLayout: old_layout as u32,
+            newLayout: new_layout as u32,
+            srcQueueFamilyIndex: vk::QUEUE_FAMILY_IGNORED,
+            dstQueueFamilyIndex: vk::QUEUE_FAMILY_IGNORED,
+            image: image,
+            subresourceRange: vk::ImageSubresourceRange {
+                aspectMask: aspect_mask,
+                baseMipLevel: 0,
+                levelCount: vk::REMAINING_MIP_LEVELS,
+                baseArrayLayer: 0,
+                layerCount: vk::REMAINING_ARRAY_LAYERS,
+            },
+        });
+    }
+
+    /// Turns the barrier into a `vkCmdPipelineBarrier` command, and returns the resources that
+    /// are referenced by it. The command is `None` if the barrier is empty.
+    #[doc(hidden)]
+    pub fn into_command(self)
+                        -> (Option<Command>, Vec<Arc<BufferResource>>, Vec<Arc<ImageResource>>)
+    {
+        let command = if !self.is_empty() {
+            Some(Command::PipelineBarrier {
+                src_stage_mask: self.src_stage_mask,
+                dst_stage_mask: self.dst_stage_mask,
+                memory_barriers: self.memory_barriers,
+                buffer_barriers: self.buffer_barriers,
+                image_barriers: self.image_barriers,
+            })
+        } else {
+            None
+        };
+
+        (command, self.buffer_resources, self.image_resources)
+    }
+
+    /// Same as `into_command`, but builds a `vkCmdWaitEvents` command instead. The source stages
+    /// of the barrier must include the stages that were used when signaling the events.
+    ///
+    /// Contrary to `into_command`, a command is always returned, even if the barrier is empty.
+    #[doc(hidden)]
+    pub fn into_wait_events_command(self, events: Vec<vk::Event>)
+                                    -> (Command, Vec<Arc<BufferResource>>, Vec<Arc<ImageResource>>)
+    {
+        let command = Command::WaitEvents {
+            events: events,
+            src_stage_mask: self.src_stage_mask,
+            dst_stage_mask: self.dst_stage_mask,
+            memory_barriers: self.memory_barriers,
+            buffer_barriers: self.buffer_barriers,
+            image_barriers: self.image_barriers,
+        };
+
+        (command, self.buffer_resources, self.image_resources)
+    }
+}
+
+// Returns the aspects that are present in an image of the given format.
+pub fn aspect_mask(format: Format) -> vk::ImageAspectFlags {
+    match format.ty() {
+        FormatTy::Depth => vk::IMAGE_ASPECT_DEPTH_BIT,
+        FormatTy::Stencil => vk::IMAGE_ASPECT_STENCIL_BIT,
+        FormatTy::DepthStencil => vk::IMAGE_ASPECT_DEPTH_BIT | vk::IMAGE_ASPECT_STENCIL_BIT,
+        _ => vk::IMAGE_ASPECT_COLOR_BIT,
+    }
+}
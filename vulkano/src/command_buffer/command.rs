@@ -0,0 +1,1443 @@
+use std::error;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::fmt;
+use std::mem;
+use std::ptr;
+
+use device::Device;
+
+use VulkanPointers;
+use vk;
+
+/// A command that has been recorded by a command buffer builder, but not written yet to the
+/// Vulkan command buffer.
+///
+/// Builders that were switched to recording mode with `record_commands` store their commands
+/// in a `CommandList`, which can be inspected with `commands()`. The list is written to the
+/// Vulkan command buffer when the builder is built, after redundant binds are removed.
+///
+/// The content of a command is raw Vulkan handles and parameters. The objects they refer to
+/// are kept alive by the command buffer.
+pub enum Command {
+    /// `vkCmdPipelineBarrier`.
+    PipelineBarrier {
+        src_stage_mask: vk::PipelineStageFlags,
+        dst_stage_mask: vk::PipelineStageFlags,
+        memory_barriers: Vec<vk::MemoryBarrier>,
+        buffer_barriers: Vec<vk::BufferMemoryBarrier>,
+        image_barriers: Vec<vk::ImageMemoryBarrier>,
+    },
+
+    /// `vkCmdSetEvent`.
+    SetEvent {
+        event: vk::Event,
+        stages: vk::PipelineStageFlags,
+    },
+
+    /// `vkCmdResetEvent`.
+    ResetEvent {
+        event: vk::Event,
+        stages: vk::PipelineStageFlags,
+    },
+
+    /// `vkCmdWaitEvents`.
+    WaitEvents {
+        events: Vec<vk::Event>,
+        src_stage_mask: vk::PipelineStageFlags,
+        dst_stage_mask: vk::PipelineStageFlags,
+        memory_barriers: Vec<vk::MemoryBarrier>,
+        buffer_barriers: Vec<vk::BufferMemoryBarrier>,
+        image_barriers: Vec<vk::ImageMemoryBarrier>,
+    },
+
+    /// `vkCmdResetQueryPool`.
+    ResetQueryPool {
+        pool: vk::QueryPool,
+        first_query: u32,
+        query_count: u32,
+    },
+
+    /// `vkCmdBeginQuery`.
+    BeginQuery {
+        pool: vk::QueryPool,
+        query: u32,
+        flags: vk::QueryControlFlags,
+    },
+
+    /// `vkCmdEndQuery`.
+    EndQuery {
+        pool: vk::QueryPool,
+        query: u32,
+    },
+
+    /// `vkCmdWriteTimestamp`.
+    WriteTimestamp {
+        stage: vk::PipelineStageFlagBits,
+        pool: vk::QueryPool,
+        query: u32,
+    },
+
+    /// `vkCmdCopyQueryPoolResults`.
+    CopyQueryPoolResults {
+        pool: vk::QueryPool,
+        first_query: u32,
+        query_count: u32,
+        destination: vk::Buffer,
+        offset: vk::DeviceSize,
+        stride: vk::DeviceSize,
+        flags: vk::QueryResultFlags,
+    },
+
+    /// `vkCmdExecuteCommands`.
+    ExecuteCommands {
+        command_buffers: Vec<vk::CommandBuffer>,
+    },
+
+    /// `vkCmdUpdateBuffer`.
+    UpdateBuffer {
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        data: Vec<u8>,
+    },
+
+    /// `vkCmdFillBuffer`.
+    FillBuffer {
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+        data: u32,
+    },
+
+    /// `vkCmdCopyBuffer`.
+    CopyBuffer {
+        source: vk::Buffer,
+        destination: vk::Buffer,
+        regions: Vec<vk::BufferCopy>,
+    },
+
+    /// `vkCmdCopyImage`.
+    CopyImage {
+        source: vk::Image,
+        source_layout: vk::ImageLayout,
+        destination: vk::Image,
+        destination_layout: vk::ImageLayout,
+        regions: Vec<vk::ImageCopy>,
+    },
+
+    /// `vkCmdBlitImage`.
+    BlitImage {
+        source: vk::Image,
+        source_layout: vk::ImageLayout,
+        destination: vk::Image,
+        destination_layout: vk::ImageLayout,
+        regions: Vec<vk::ImageBlit>,
+        filter: vk::Filter,
+    },
+
+    /// `vkCmdResolveImage`.
+    ResolveImage {
+        source: vk::Image,
+        source_layout: vk::ImageLayout,
+        destination: vk::Image,
+        destination_layout: vk::ImageLayout,
+        regions: Vec<vk::ImageResolve>,
+    },
+
+    /// `vkCmdClearColorImage`.
+    ClearColorImage {
+        image: vk::Image,
+        layout: vk::ImageLayout,
+        color: vk::ClearColorValue,
+        ranges: Vec<vk::ImageSubresourceRange>,
+    },
+
+    /// `vkCmdClearDepthStencilImage`.
+    ClearDepthStencilImage {
+        image: vk::Image,
+        layout: vk::ImageLayout,
+        value: vk::ClearDepthStencilValue,
+        ranges: Vec<vk::ImageSubresourceRange>,
+    },
+
+    /// `vkCmdClearAttachments`.
+    ClearAttachments {
+        attachments: Vec<vk::ClearAttachment>,
+        rects: Vec<vk::ClearRect>,
+    },
+
+    /// `vkCmdCopyBufferToImage`.
+    CopyBufferToImage {
+        source: vk::Buffer,
+        destination: vk::Image,
+        destination_layout: vk::ImageLayout,
+        regions: Vec<vk::BufferImageCopy>,
+    },
+
+    /// `vkCmdCopyImageToBuffer`.
+    CopyImageToBuffer {
+        source: vk::Image,
+        source_layout: vk::ImageLayout,
+        destination: vk::Buffer,
+        regions: Vec<vk::BufferImageCopy>,
+    },
+
+    /// `vkCmdBindPipeline`.
+    BindPipeline {
+        bind_point: vk::PipelineBindPoint,
+        pipeline: vk::Pipeline,
+    },
+
+    /// `vkCmdBindDescriptorSets`.
+    BindDescriptorSets {
+        bind_point: vk::PipelineBindPoint,
+        layout: vk::PipelineLayout,
+        first_set: u32,
+        sets: Vec<vk::DescriptorSet>,
+        dynamic_offsets: Vec<u32>,
+    },
+
+    /// `vkCmdBindVertexBuffers`.
+    BindVertexBuffers {
+        first_binding: u32,
+        buffers: Vec<vk::Buffer>,
+        offsets: Vec<vk::DeviceSize>,
+    },
+
+    /// `vkCmdBindIndexBuffer`.
+    BindIndexBuffer {
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        index_type: vk::IndexType,
+    },
+
+    /// `vkCmdSetLineWidth`.
+    SetLineWidth(f32),
+
+    /// `vkCmdSetViewport`.
+    SetViewport {
+        first_viewport: u32,
+        viewports: Vec<vk::Viewport>,
+    },
+
+    /// `vkCmdSetScissor`.
+    SetScissor {
+        first_scissor: u32,
+        scissors: Vec<vk::Rect2D>,
+    },
+
+    /// `vkCmdSetDepthBias`.
+    SetDepthBias {
+        constant_factor: f32,
+        clamp: f32,
+        slope_factor: f32,
+    },
+
+    /// `vkCmdSetBlendConstants`.
+    SetBlendConstants([f32; 4]),
+
+    /// `vkCmdSetDepthBounds`.
+    SetDepthBounds {
+        min: f32,
+        max: f32,
+    },
+
+    /// `vkCmdSetStencilCompareMask`.
+    SetStencilCompareMask {
+        face_mask: vk::StencilFaceFlags,
+        value: u32,
+    },
+
+    /// `vkCmdSetStencilWriteMask`.
+    SetStencilWriteMask {
+        face_mask: vk::StencilFaceFlags,
+        value: u32,
+    },
+
+    /// `vkCmdSetStencilReference`.
+    SetStencilReference {
+        face_mask: vk::StencilFaceFlags,
+        value: u32,
+    },
+
+    /// `vkCmdPushConstants`.
+    PushConstants {
+        layout: vk::PipelineLayout,
+        stages: vk::ShaderStageFlags,
+        offset: u32,
+        data: Vec<u8>,
+    },
+
+    /// `vkCmdDraw`.
+    Draw {
+        vertex_count: u32,
+        instance_count: u32,
+        first_vertex: u32,
+        first_instance: u32,
+    },
+
+    /// `vkCmdDrawIndexed`.
+    DrawIndexed {
+        index_count: u32,
+        instance_count: u32,
+        first_index: u32,
+        vertex_offset: i32,
+        first_instance: u32,
+    },
+
+    /// `vkCmdDrawIndirect`.
+    DrawIndirect {
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        draw_count: u32,
+        stride: u32,
+    },
+
+    /// `vkCmdDrawIndexedIndirect`.
+    DrawIndexedIndirect {
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        draw_count: u32,
+        stride: u32,
+    },
+
+    /// `vkCmdDispatch`.
+    Dispatch([u32; 3]),
+
+    /// `vkCmdDispatchIndirect`.
+    DispatchIndirect {
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+    },
+
+    /// `vkCmdBeginRenderPass`.
+    BeginRenderPass {
+        render_pass: vk::RenderPass,
+        framebuffer: vk::Framebuffer,
+        render_area: vk::Rect2D,
+        clear_values: Vec<vk::ClearValue>,
+        contents: vk::SubpassContents,
+    },
+
+    /// `vkCmdNextSubpass`.
+    NextSubpass {
+        contents: vk::SubpassContents,
+    },
+
+    /// `vkCmdEndRenderPass`.
+    EndRenderPass,
+
+    /// `vkCmdDebugMarkerBeginEXT`.
+    BeginDebugLabel {
+        name: CString,
+        color: [f32; 4],
+    },
+
+    /// `vkCmdDebugMarkerEndEXT`.
+    EndDebugLabel,
+
+    /// `vkCmdDebugMarkerInsertEXT`.
+    InsertDebugLabel {
+        name: CString,
+        color: [f32; 4],
+    },
+}
+
+impl Command {
+    /// Writes the command to a Vulkan command buffer.
+    ///
+    /// # Safety
+    ///
+    /// - The command buffer must be in the recording state and must belong to the device whose
+    ///   function pointers are passed.
+    /// - The objects that the command refers to must still be alive, and the command must be
+    ///   valid at this point of the command buffer.
+    ///
+    pub unsafe fn replay(&self, vk: &vk::DevicePointers, cmd: vk::CommandBuffer) {
+        match *self {
+            Command::PipelineBarrier { src_stage_mask, dst_stage_mask, ref memory_barriers,
+                                       ref buffer_barriers, ref image_barriers } =>
+            {
+                vk.CmdPipelineBarrier(cmd, src_stage_mask, dst_stage_mask, 0,
+                                      memory_barriers.len() as u32, memory_barriers.as_ptr(),
+                                      buffer_barriers.len() as u32, buffer_barriers.as_ptr(),
+                                      image_barriers.len() as u32, image_barriers.as_ptr());
+            },
+
+            Command::SetEvent { event, stages } => {
+                vk.CmdSetEvent(cmd, event, stages);
+            },
+
+            Command::ResetEvent { event, stages } => {
+                vk.CmdResetEvent(cmd, event, stages);
+            },
+
+            Command::WaitEvents { ref events, src_stage_mask, dst_stage_mask,
+                                  ref memory_barriers, ref buffer_barriers,
+                                  ref image_barriers } =>
+            {
+                vk.CmdWaitEvents(cmd, events.len() as u32, events.as_ptr(),
+                                 src_stage_mask, dst_stage_mask,
+                                 memory_barriers.len() as u32, memory_barriers.as_ptr(),
+                                 buffer_barriers.len() as u32, buffer_barriers.as_ptr(),
+                                 image_barriers.len() as u32, image_barriers.as_ptr());
+            },
+
+            Command::ResetQueryPool { pool, first_query, query_count } => {
+                vk.CmdResetQueryPool(cmd, pool, first_query, query_count);
+            },
+
+            Command::BeginQuery { pool, query, flags } => {
+                vk.CmdBeginQuery(cmd, pool, query, flags);
+            },
+
+            Command::EndQuery { pool, query } => {
+                vk.CmdEndQuery(cmd, pool, query);
+            },
+
+            Command::WriteTimestamp { stage, pool, query } => {
+                vk.CmdWriteTimestamp(cmd, stage, pool, query);
+            },
+
+            Command::CopyQueryPoolResults { pool, first_query, query_count, destination, offset,
+                                            stride, flags } =>
+            {
+                vk.CmdCopyQueryPoolResults(cmd, pool, first_query, query_count, destination,
+                                           offset, stride, flags);
+            },
+
+            Command::ExecuteCommands { ref command_buffers } => {
+                vk.CmdExecuteCommands(cmd, command_buffers.len() as u32,
+                                      command_buffers.as_ptr());
+            },
+
+            Command::UpdateBuffer { buffer, offset, ref data } => {
+                vk.CmdUpdateBuffer(cmd, buffer, offset, data.len() as vk::DeviceSize,
+                                   data.as_ptr() as *const _);
+            },
+
+            Command::FillBuffer { buffer, offset, size, data } => {
+                vk.CmdFillBuffer(cmd, buffer, offset, size, data);
+            },
+
+            Command::CopyBuffer { source, destination, ref regions } => {
+                vk.CmdCopyBuffer(cmd, source, destination, regions.len() as u32,
+                                 regions.as_ptr());
+            },
+
+            Command::CopyImage { source, source_layout, destination, destination_layout,
+                                 ref regions } =>
+            {
+                vk.CmdCopyImage(cmd, source, source_layout, destination, destination_layout,
+                                regions.len() as u32, regions.as_ptr());
+            },
+
+            Command::BlitImage { source, source_layout, destination, destination_layout,
+                                 ref regions, filter } =>
+            {
+                vk.CmdBlitImage(cmd, source, source_layout, destination, destination_layout,
+                                regions.len() as u32, regions.as_ptr(), filter);
+            },
+
+            Command::ResolveImage { source, source_layout, destination, destination_layout,
+                                    ref regions } =>
+            {
+                vk.CmdResolveImage(cmd, source, source_layout, destination, destination_layout,
+                                   regions.len() as u32, regions.as_ptr());
+            },
+
+            Command::ClearColorImage { image, layout, ref color, ref ranges } => {
+                vk.CmdClearColorImage(cmd, image, layout, color, ranges.len() as u32,
+                                      ranges.as_ptr());
+            },
+
+            Command::ClearDepthStencilImage { image, layout, ref value, ref ranges } => {
+                vk.CmdClearDepthStencilImage(cmd, image, layout, value, ranges.len() as u32,
+                                             ranges.as_ptr());
+            },
+
+            Command::ClearAttachments { ref attachments, ref rects } => {
+                vk.CmdClearAttachments(cmd, attachments.len() as u32, attachments.as_ptr(),
+                                       rects.len() as u32, rects.as_ptr());
+            },
+
+            Command::CopyBufferToImage { source, destination, destination_layout,
+                                         ref regions } =>
+            {
+                vk.CmdCopyBufferToImage(cmd, source, destination, destination_layout,
+                                        regions.len() as u32, regions.as_ptr());
+            },
+
+            Command::CopyImageToBuffer { source, source_layout, destination, ref regions } => {
+                vk.CmdCopyImageToBuffer(cmd, source, source_layout, destination,
+                                        regions.len() as u32, regions.as_ptr());
+            },
+
+            Command::BindPipeline { bind_point, pipeline } => {
+                vk.CmdBindPipeline(cmd, bind_point, pipeline);
+            },
+
+            Command::BindDescriptorSets { bind_point, layout, first_set, ref sets,
+                                          ref dynamic_offsets } =>
+            {
+                vk.CmdBindDescriptorSets(cmd, bind_point, layout, first_set, sets.len() as u32,
+                                         sets.as_ptr(), dynamic_offsets.len() as u32,
+                                         dynamic_offsets.as_ptr());
+            },
+
+            Command::BindVertexBuffers { first_binding, ref buffers, ref offsets } => {
+                debug_assert_eq!(buffers.len(), offsets.len());
+                vk.CmdBindVertexBuffers(cmd, first_binding, buffers.len() as u32,
+                                        buffers.as_ptr(), offsets.as_ptr());
+            },
+
+            Command::BindIndexBuffer { buffer, offset, index_type } => {
+                vk.CmdBindIndexBuffer(cmd, buffer, offset, index_type);
+            },
+
+            Command::SetLineWidth(width) => {
+                vk.CmdSetLineWidth(cmd, width);
+            },
+
+            Command::SetViewport { first_viewport, ref viewports } => {
+                vk.CmdSetViewport(cmd, first_viewport, viewports.len() as u32,
+                                  viewports.as_ptr());
+            },
+
+            Command::SetScissor { first_scissor, ref scissors } => {
+                vk.CmdSetScissor(cmd, first_scissor, scissors.len() as u32, scissors.as_ptr());
+            },
+
+            Command::SetDepthBias { constant_factor, clamp, slope_factor } => {
+                vk.CmdSetDepthBias(cmd, constant_factor, clamp, slope_factor);
+            },
+
+            Command::SetBlendConstants(ref constants) => {
+                vk.CmdSetBlendConstants(cmd, constants.as_ptr());
+            },
+
+            Command::SetDepthBounds { min, max } => {
+                vk.CmdSetDepthBounds(cmd, min, max);
+            },
+
+            Command::SetStencilCompareMask { face_mask, value } => {
+                vk.CmdSetStencilCompareMask(cmd, face_mask, value);
+            },
+
+            Command::SetStencilWriteMask { face_mask, value } => {
+                vk.CmdSetStencilWriteMask(cmd, face_mask, value);
+            },
+
+            Command::SetStencilReference { face_mask, value } => {
+                vk.CmdSetStencilReference(cmd, face_mask, value);
+            },
+
+            Command::PushConstants { layout, stages, offset, ref data } => {
+                vk.CmdPushConstants(cmd, layout, stages, offset, data.len() as u32,
+                                    data.as_ptr() as *const _);
+            },
+
+            Command::Draw { vertex_count, instance_count, first_vertex, first_instance } => {
+                vk.CmdDraw(cmd, vertex_count, instance_count, first_vertex, first_instance);
+            },
+
+            Command::DrawIndexed { index_count, instance_count, first_index, vertex_offset,
+                                   first_instance } =>
+            {
+                vk.CmdDrawIndexed(cmd, index_count, instance_count, first_index, vertex_offset,
+                                  first_instance);
+            },
+
+            Command::DrawIndirect { buffer, offset, draw_count, stride } => {
+                vk.CmdDrawIndirect(cmd, buffer, offset, draw_count, stride);
+            },
+
+            Command::DrawIndexedIndirect { buffer, offset, draw_count, stride } => {
+                vk.CmdDrawIndexedIndirect(cmd, buffer, offset, draw_count, stride);
+            },
+
+            Command::Dispatch(dimensions) => {
+                vk.CmdDispatch(cmd, dimensions[0], dimensions[1], dimensions[2]);
+            },
+
+            Command::DispatchIndirect { buffer, offset } => {
+                vk.CmdDispatchIndirect(cmd, buffer, offset);
+            },
+
+            Command::BeginRenderPass { render_pass, framebuffer, ref render_area,
+                                       ref clear_values, contents } =>
+            {
+                let infos = vk::RenderPassBeginInfo {
+                    sType: vk::STRUCTURE_TYPE_RENDER_PASS_BEGIN_INFO,
+                    pNext: ptr::null(),
+                    renderPass: render_pass,
+                    framebuffer: framebuffer,
+                    renderArea: vk::Rect2D {
+                        offset: vk::Offset2D {
+                            x: render_area.offset.x,
+                            y: render_area.offset.y,
+                        },
+                        extent: vk::Extent2D {
+                            width: render_area.extent.width,
+                            height: render_area.extent.height,
+                        },
+                    },
+                    clearValueCount: clear_values.len() as u32,
+                    pClearValues: clear_values.as_ptr(),
+                };
+
+                vk.CmdBeginRenderPass(cmd, &infos, contents);
+            },
+
+            Command::NextSubpass { contents } => {
+                vk.CmdNextSubpass(cmd, contents);
+            },
+
+            Command::EndRenderPass => {
+                vk.CmdEndRenderPass(cmd);
+            },
+
+            Command::BeginDebugLabel { ref name, color } => {
+                let infos = debug_marker_info(name, color);
+                vk.CmdDebugMarkerBeginEXT(cmd, &infos);
+            },
+
+            Command::EndDebugLabel => {
+                vk.CmdDebugMarkerEndEXT(cmd);
+            },
+
+            Command::InsertDebugLabel { ref name, color } => {
+                let infos = debug_marker_info(name, color);
+                vk.CmdDebugMarkerInsertEXT(cmd, &infos);
+            },
+        }
+    }
+}
+
+// The Vulkan structs don't implement `Debug`, so the lists of regions and barriers are printed
+// as their number of elements.
+impl fmt::Debug for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Command::PipelineBarrier { src_stage_mask, dst_stage_mask, ref memory_barriers,
+                                       ref buffer_barriers, ref image_barriers } =>
+            {
+                f.debug_struct("PipelineBarrier")
+                 .field("src_stage_mask", &src_stage_mask)
+                 .field("dst_stage_mask", &dst_stage_mask)
+                 .field("memory_barriers", &memory_barriers.len())
+                 .field("buffer_barriers", &buffer_barriers.len())
+                 .field("image_barriers", &image_barriers.len())
+                 .finish()
+            },
+            Command::SetEvent { event, stages } => {
+                f.debug_struct("SetEvent").field("event", &event).field("stages", &stages)
+                 .finish()
+            },
+            Command::ResetEvent { event, stages } => {
+                f.debug_struct("ResetEvent").field("event", &event).field("stages", &stages)
+                 .finish()
+            },
+            Command::WaitEvents { ref events, src_stage_mask, dst_stage_mask,
+                                  ref memory_barriers, ref buffer_barriers,
+                                  ref image_barriers } =>
+            {
+                f.debug_struct("WaitEvents")
+                 .field("events", events)
+                 .field("src_stage_mask", &src_stage_mask)
+                 .field("dst_stage_mask", &dst_stage_mask)
+                 .field("memory_barriers", &memory_barriers.len())
+                 .field("buffer_barriers", &buffer_barriers.len())
+                 .field("image_barriers", &image_barriers.len())
+                 .finish()
+            },
+            Command::ResetQueryPool { pool, first_query, query_count } => {
+                f.debug_struct("ResetQueryPool").field("pool", &pool)
+                 .field("first_query", &first_query).field("query_count", &query_count)
+                 .finish()
+            },
+            Command::BeginQuery { pool, query, flags } => {
+                f.debug_struct("BeginQuery").field("pool", &pool).field("query", &query)
+                 .field("flags", &flags).finish()
+            },
+            Command::EndQuery { pool, query } => {
+                f.debug_struct("EndQuery").field("pool", &pool).field("query", &query).finish()
+            },
+            Command::WriteTimestamp { stage, pool, query } => {
+                f.debug_struct("WriteTimestamp").field("stage", &stage).field("pool", &pool)
+                 .field("query", &query).finish()
+            },
+            Command::CopyQueryPoolResults { pool, first_query, query_count, destination, offset,
+                                            stride, flags } =>
+            {
+                f.debug_struct("CopyQueryPoolResults")
+                 .field("pool", &pool)
+                 .field("first_query", &first_query)
+                 .field("query_count", &query_count)
+                 .field("destination", &destination)
+                 .field("offset", &offset)
+                 .field("stride", &stride)
+                 .field("flags", &flags)
+                 .finish()
+            },
+            Command::ExecuteCommands { ref command_buffers } => {
+                f.debug_struct("ExecuteCommands").field("command_buffers", command_buffers)
+                 .finish()
+            },
+            Command::UpdateBuffer { buffer, offset, ref data } => {
+                f.debug_struct("UpdateBuffer").field("buffer", &buffer).field("offset", &offset)
+                 .field("size", &data.len()).finish()
+            },
+            Command::FillBuffer { buffer, offset, size, data } => {
+                f.debug_struct("FillBuffer").field("buffer", &buffer).field("offset", &offset)
+                 .field("size", &size).field("data", &data).finish()
+            },
+            Command::CopyBuffer { source, destination, ref regions } => {
+                f.debug_struct("CopyBuffer").field("source", &source)
+                 .field("destination", &destination).field("regions", &regions.len()).finish()
+            },
+            Command::CopyImage { source, source_layout, destination, destination_layout,
+                                 ref regions } =>
+            {
+                f.debug_struct("CopyImage")
+                 .field("source", &source)
+                 .field("source_layout", &source_layout)
+                 .field("destination", &destination)
+                 .field("destination_layout", &destination_layout)
+                 .field("regions", &regions.len())
+                 .finish()
+            },
+            Command::BlitImage { source, source_layout, destination, destination_layout,
+                                 ref regions, filter } =>
+            {
+                f.debug_struct("BlitImage")
+                 .field("source", &source)
+                 .field("source_layout", &source_layout)
+                 .field("destination", &destination)
+                 .field("destination_layout", &destination_layout)
+                 .field("regions", &regions.len())
+                 .field("filter", &filter)
+                 .finish()
+            },
+            Command::ResolveImage { source, source_layout, destination, destination_layout,
+                                    ref regions } =>
+            {
+                f.debug_struct("ResolveImage")
+                 .field("source", &source)
+                 .field("source_layout", &source_layout)
+                 .field("destination", &destination)
+                 .field("destination_layout", &destination_layout)
+                 .field("regions", &regions.len())
+                 .finish()
+            },
+            Command::ClearColorImage { image, layout, ref color, ref ranges } => {
+                f.debug_struct("ClearColorImage").field("image", &image)
+                 .field("layout", &layout).field("color", color.as_uint32())
+                 .field("ranges", &ranges.len()).finish()
+            },
+            Command::ClearDepthStencilImage { image, layout, ref value, ref ranges } => {
+                f.debug_struct("ClearDepthStencilImage").field("image", &image)
+                 .field("layout", &layout).field("depth", &value.depth)
+                 .field("stencil", &value.stencil).field("ranges", &ranges.len()).finish()
+            },
+            Command::ClearAttachments { ref attachments, ref rects } => {
+                f.debug_struct("ClearAttachments").field("attachments", &attachments.len())
+                 .field("rects", &rects.len()).finish()
+            },
+            Command::CopyBufferToImage { source, destination, destination_layout,
+                                         ref regions } =>
+            {
+                f.debug_struct("CopyBufferToImage")
+                 .field("source", &source)
+                 .field("destination", &destination)
+                 .field("destination_layout", &destination_layout)
+                 .field("regions", &regions.len())
+                 .finish()
+            },
+            Command::CopyImageToBuffer { source, source_layout, destination, ref regions } => {
+                f.debug_struct("CopyImageToBuffer")
+                 .field("source", &source)
+                 .field("source_layout", &source_layout)
+                 .field("destination", &destination)
+                 .field("regions", &regions.len())
+                 .finish()
+            },
+            Command::BindPipeline { bind_point, pipeline } => {
+                f.debug_struct("BindPipeline").field("bind_point", &bind_point)
+                 .field("pipeline", &pipeline).finish()
+            },
+            Command::BindDescriptorSets { bind_point, layout, first_set, ref sets,
+                                          ref dynamic_offsets } =>
+            {
+                f.debug_struct("BindDescriptorSets")
+                 .field("bind_point", &bind_point)
+                 .field("layout", &layout)
+                 .field("first_set", &first_set)
+                 .field("sets", sets)
+                 .field("dynamic_offsets", dynamic_offsets)
+                 .finish()
+            },
+            Command::BindVertexBuffers { first_binding, ref buffers, ref offsets } => {
+                f.debug_struct("BindVertexBuffers").field("first_binding", &first_binding)
+                 .field("buffers", buffers).field("offsets", offsets).finish()
+            },
+            Command::BindIndexBuffer { buffer, offset, index_type } => {
+                f.debug_struct("BindIndexBuffer").field("buffer", &buffer)
+                 .field("offset", &offset).field("index_type", &index_type).finish()
+            },
+            Command::SetLineWidth(width) => {
+                f.debug_tuple("SetLineWidth").field(&width).finish()
+            },
+            Command::SetViewport { first_viewport, ref viewports } => {
+                f.debug_struct("SetViewport").field("first_viewport", &first_viewport)
+                 .field("viewports", &viewports.len()).finish()
+            },
+            Command::SetScissor { first_scissor, ref scissors } => {
+                f.debug_struct("SetScissor").field("first_scissor", &first_scissor)
+                 .field("scissors", &scissors.len()).finish()
+            },
+            Command::SetDepthBias { constant_factor, clamp, slope_factor } => {
+                f.debug_struct("SetDepthBias").field("constant_factor", &constant_factor)
+                 .field("clamp", &clamp).field("slope_factor", &slope_factor).finish()
+            },
+            Command::SetBlendConstants(ref constants) => {
+                f.debug_tuple("SetBlendConstants").field(constants).finish()
+            },
+            Command::SetDepthBounds { min, max } => {
+                f.debug_struct("SetDepthBounds").field("min", &min).field("max", &max).finish()
+            },
+            Command::SetStencilCompareMask { face_mask, value } => {
+                f.debug_struct("SetStencilCompareMask").field("face_mask", &face_mask)
+                 .field("value", &value).finish()
+            },
+            Command::SetStencilWriteMask { face_mask, value } => {
+                f.debug_struct("SetStencilWriteMask").field("face_mask", &face_mask)
+                 .field("value", &value).finish()
+            },
+            Command::SetStencilReference { face_mask, value } => {
+                f.debug_struct("SetStencilReference").field("face_mask", &face_mask)
+                 .field("value", &value).finish()
+            },
+            Command::PushConstants { layout, stages, offset, ref data } => {
+                f.debug_struct("PushConstants").field("layout", &layout)
+                 .field("stages", &stages).field("offset", &offset).field("size", &data.len())
+                 .finish()
+            },
+            Command::Draw { vertex_count, instance_count, first_vertex, first_instance } => {
+                f.debug_struct("Draw")
+                 .field("vertex_count", &vertex_count)
+                 .field("instance_count", &instance_count)
+                 .field("first_vertex", &first_vertex)
+                 .field("first_instance", &first_instance)
+                 .finish()
+            },
+            Command::DrawIndexed { index_count, instance_count, first_index, vertex_offset,
+                                   first_instance } =>
+            {
+                f.debug_struct("DrawIndexed")
+                 .field("index_count", &index_count)
+                 .field("instance_count", &instance_count)
+                 .field("first_index", &first_index)
+                 .field("vertex_offset", &vertex_offset)
+                 .field("first_instance", &first_instance)
+                 .finish()
+            },
+            Command::DrawIndirect { buffer, offset, draw_count, stride } => {
+                f.debug_struct("DrawIndirect").field("buffer", &buffer).field("offset", &offset)
+                 .field("draw_count", &draw_count).field("stride", &stride).finish()
+            },
+            Command::DrawIndexedIndirect { buffer, offset, draw_count, stride } => {
+                f.debug_struct("DrawIndexedIndirect").field("buffer", &buffer)
+                 .field("offset", &offset).field("draw_count", &draw_count)
+                 .field("stride", &stride).finish()
+            },
+            Command::Dispatch(ref dimensions) => {
+                f.debug_tuple("Dispatch").field(dimensions).finish()
+            },
+            Command::DispatchIndirect { buffer, offset } => {
+                f.debug_struct("DispatchIndirect").field("buffer", &buffer)
+                 .field("offset", &offset).finish()
+            },
+            Command::BeginRenderPass { render_pass, framebuffer, ref render_area,
+                                       ref clear_values, contents } =>
+            {
+                f.debug_struct("BeginRenderPass")
+                 .field("render_pass", &render_pass)
+                 .field("framebuffer", &framebuffer)
+                 .field("render_area", &((render_area.offset.x, render_area.offset.y),
+                                         (render_area.extent.width, render_area.extent.height)))
+                 .field("clear_values", &clear_values.len())
+                 .field("contents", &contents)
+                 .finish()
+            },
+            Command::NextSubpass { contents } => {
+                f.debug_struct("NextSubpass").field("contents", &contents).finish()
+            },
+            Command::EndRenderPass => {
+                f.debug_tuple("EndRenderPass").finish()
+            },
+            Command::BeginDebugLabel { ref name, color } => {
+                f.debug_struct("BeginDebugLabel").field("name", name).field("color", &color)
+                 .finish()
+            },
+            Command::EndDebugLabel => {
+                f.debug_tuple("EndDebugLabel").finish()
+            },
+            Command::InsertDebugLabel { ref name, color } => {
+                f.debug_struct("InsertDebugLabel").field("name", name).field("color", &color)
+                 .finish()
+            },
+        }
+    }
+}
+
+// Builds the description of a debug label. The name must outlive the returned struct.
+fn debug_marker_info(name: &CStr, color: [f32; 4]) -> vk::DebugMarkerMarkerInfoEXT {
+    vk::DebugMarkerMarkerInfoEXT {
+        sType: vk::STRUCTURE_TYPE_DEBUG_MARKER_MARKER_INFO_EXT,
+        pNext: ptr::null(),
+        pMarkerName: name.as_ptr(),
+        color: color,
+    }
+}
+
+/// List of commands that can be inspected, validated and optimized before being written to a
+/// Vulkan command buffer.
+///
+/// A list that is built manually with `push` doesn't require a device, which means that it can
+/// be validated and optimized without a GPU. Command buffer builders that were switched to
+/// recording mode with `record_commands` store their commands in a `CommandList`, but these
+/// builders still allocate a Vulkan command buffer and therefore always require a device.
+///
+/// # Example
+///
+/// ```
+/// use vulkano::command_buffer::Command;
+/// use vulkano::command_buffer::CommandList;
+///
+/// let mut list = CommandList::new();
+/// list.push(Command::Dispatch([8, 8, 1]));
+/// assert!(list.validate(false).is_err());     // no compute pipeline is bound
+/// ```
+pub struct CommandList {
+    commands: Vec<Command>,
+}
+
+impl CommandList {
+    /// Builds a new empty list.
+    #[inline]
+    pub fn new() -> CommandList {
+        CommandList {
+            commands: Vec::new(),
+        }
+    }
+
+    /// Adds a command at the end of the list.
+    #[inline]
+    pub fn push(&mut self, command: Command) {
+        self.commands.push(command);
+    }
+
+    /// Returns the commands of the list.
+    #[inline]
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    /// Returns the number of commands in the list.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns true if the list doesn't contain any command.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Checks that the commands are in a valid order.
+    ///
+    /// This checks that render passes, queries and debug labels are properly begun and ended,
+    /// that commands are recorded inside or outside of a render pass as required, and that a
+    /// pipeline is bound before each draw or dispatch. The parameters of the commands themselves
+    /// are not checked.
+    ///
+    /// `inside_render_pass` must be true if the list is executed inside of a render pass, which
+    /// is the case of secondary graphics command buffers.
+    pub fn validate(&self, inside_render_pass: bool) -> Result<(), CommandListError> {
+        let mut in_render_pass = inside_render_pass;
+        let mut pipelines = [false, false];
+        let mut active_queries: Vec<(vk::QueryPool, u32)> = Vec::new();
+        let mut labels = 0;
+
+        for (index, command) in self.commands.iter().enumerate() {
+            match *command {
+                Command::BeginRenderPass { .. } => {
+                    if in_render_pass {
+                        return Err(CommandListError::NestedRenderPass(index));
+                    }
+                    in_render_pass = true;
+                },
+
+                // Secondary command buffers can't change the subpass of the render pass they
+                // are executed in.
+                Command::NextSubpass { .. } => {
+                    if inside_render_pass {
+                        return Err(CommandListError::ForbiddenInsideRenderPass(index));
+                    }
+                    if !in_render_pass {
+                        return Err(CommandListError::NotInsideRenderPass(index));
+                    }
+                },
+
+                Command::EndRenderPass => {
+                    if inside_render_pass {
+                        return Err(CommandListError::ForbiddenInsideRenderPass(index));
+                    }
+                    if !in_render_pass {
+                        return Err(CommandListError::NotInsideRenderPass(index));
+                    }
+                    in_render_pass = false;
+                },
+
+                Command::BindPipeline { bind_point, .. } => {
+                    pipelines[bind_point_index(bind_point)] = true;
+                },
+
+                Command::Draw { .. } | Command::DrawIndexed { .. } |
+                Command::DrawIndirect { .. } | Command::DrawIndexedIndirect { .. } => {
+                    if !in_render_pass {
+                        return Err(CommandListError::NotInsideRenderPass(index));
+                    }
+                    if !pipelines[0] {
+                        return Err(CommandListError::NoPipelineBound(index));
+                    }
+                },
+
+                Command::ClearAttachments { .. } => {
+                    if !in_render_pass {
+                        return Err(CommandListError::NotInsideRenderPass(index));
+                    }
+                },
+
+                Command::Dispatch(_) | Command::DispatchIndirect { .. } => {
+                    if in_render_pass {
+                        return Err(CommandListError::ForbiddenInsideRenderPass(index));
+                    }
+                    if !pipelines[1] {
+                        return Err(CommandListError::NoPipelineBound(index));
+                    }
+                },
+
+                Command::SetEvent { .. } | Command::ResetEvent { .. } |
+                Command::ResetQueryPool { .. } | Command::CopyQueryPoolResults { .. } |
+                Command::UpdateBuffer { .. } | Command::FillBuffer { .. } |
+                Command::CopyBuffer { .. } | Command::CopyImage { .. } |
+                Command::BlitImage { .. } | Command::ResolveImage { .. } |
+                Command::ClearColorImage { .. } | Command::ClearDepthStencilImage { .. } |
+                Command::CopyBufferToImage { .. } | Command::CopyImageToBuffer { .. } => {
+                    if in_render_pass {
+                        return Err(CommandListError::ForbiddenInsideRenderPass(index));
+                    }
+                },
+
+                Command::BeginQuery { pool, query, .. } => {
+                    if active_queries.contains(&(pool, query)) {
+                        return Err(CommandListError::QueryAlreadyActive(index));
+                    }
+                    active_queries.push((pool, query));
+                },
+
+                Command::EndQuery { pool, query } => {
+                    match active_queries.iter().position(|&q| q == (pool, query)) {
+                        Some(pos) => { active_queries.remove(pos); },
+                        None => return Err(CommandListError::QueryNotActive(index)),
+                    }
+                },
+
+                Command::BeginDebugLabel { .. } => {
+                    labels += 1;
+                },
+
+                Command::EndDebugLabel => {
+                    if labels == 0 {
+                        return Err(CommandListError::DebugLabelNotBegun(index));
+                    }
+                    labels -= 1;
+                },
+
+                // the state of the command buffer is undefined after executing secondary command
+                // buffers
+                Command::ExecuteCommands { .. } => {
+                    pipelines = [false, false];
+                },
+
+                _ => ()
+            }
+        }
+
+        let end = self.commands.len();
+        if in_render_pass && !inside_render_pass {
+            return Err(CommandListError::RenderPassNotEnded(end));
+        }
+        if !active_queries.is_empty() {
+            return Err(CommandListError::QueryNotEnded(end));
+        }
+        if labels != 0 {
+            return Err(CommandListError::DebugLabelNotEnded(end));
+        }
+
+        Ok(())
+    }
+
+    /// Removes the binds that don't change the state of the command buffer.
+    ///
+    /// The state before the first command is considered unknown, and so is the state after
+    /// executing secondary command buffers.
+    pub fn remove_redundant_binds(&mut self) {
+        // Bound state, indexed by the bind point for pipelines and descriptor sets.
+        let mut pipelines: [Option<vk::Pipeline>; 2] = [None, None];
+        let mut sets: [Option<(vk::PipelineLayout, u32, Vec<vk::DescriptorSet>, Vec<u32>)>; 2] =
+                                                                                    [None, None];
+        let mut vertex_buffers: Option<(u32, Vec<vk::Buffer>, Vec<vk::DeviceSize>)> = None;
+        let mut index_buffer: Option<(vk::Buffer, vk::DeviceSize, vk::IndexType)> = None;
+
+        let commands = mem::replace(&mut self.commands, Vec::new());
+
+        for command in commands.into_iter() {
+            match command {
+                Command::BindPipeline { bind_point, pipeline } => {
+                    let point = bind_point_index(bind_point);
+                    if pipelines[point] == Some(pipeline) {
+                        continue;
+                    }
+
+                    // binding a pipeline may disturb the descriptor sets if the layouts are not
+                    // compatible
+                    pipelines[point] = Some(pipeline);
+                    sets[point] = None;
+                },
+
+                Command::BindDescriptorSets { bind_point, layout, first_set, sets: ref list,
+                                              ref dynamic_offsets } =>
+                {
+                    let point = bind_point_index(bind_point);
+                    let new_state = (layout, first_set, list.clone(), dynamic_offsets.clone());
+                    if sets[point].as_ref() == Some(&new_state) {
+                        continue;
+                    }
+                    sets[point] = Some(new_state);
+                },
+
+                Command::BindVertexBuffers { first_binding, ref buffers, ref offsets } => {
+                    let new_state = (first_binding, buffers.clone(), offsets.clone());
+                    if vertex_buffers.as_ref() == Some(&new_state) {
+                        continue;
+                    }
+                    vertex_buffers = Some(new_state);
+                },
+
+                Command::BindIndexBuffer { buffer, offset, index_type } => {
+                    let new_state = (buffer, offset, index_type);
+                    if index_buffer == Some(new_state) {
+                        continue;
+                    }
+                    index_buffer = Some(new_state);
+                },
+
+                // the state of the command buffer is undefined after executing secondary command
+                // buffers
+                Command::ExecuteCommands { .. } => {
+                    pipelines = [None, None];
+                    sets = [None, None];
+                    vertex_buffers = None;
+                    index_buffer = None;
+                },
+
+                _ => ()
+            }
+
+            self.commands.push(command);
+        }
+    }
+
+    /// Writes the commands to a Vulkan command buffer.
+    ///
+    /// # Safety
+    ///
+    /// - The command buffer must be in the recording state and must belong to `device`.
+    /// - The objects that the commands refer to must still be alive, and the commands must be
+    ///   valid at this point of the command buffer.
+    ///
+    pub unsafe fn replay(&self, device: &Device, cmd: vk::CommandBuffer) {
+        let vk = device.pointers();
+        for command in self.commands.iter() {
+            command.replay(vk, cmd);
+        }
+    }
+}
+
+impl fmt::Debug for CommandList {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_list().entries(self.commands.iter()).finish()
+    }
+}
+
+/// Error that can be returned when validating a `CommandList`.
+///
+/// The value is the index of the command that caused the error, or the number of commands if
+/// the error was detected at the end of the list.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CommandListError {
+    /// A render pass was begun inside of a render pass.
+    NestedRenderPass(usize),
+    /// A command that must be inside of a render pass was used outside of one.
+    NotInsideRenderPass(usize),
+    /// A command that must be outside of a render pass was used inside of one.
+    ForbiddenInsideRenderPass(usize),
+    /// The list ends inside of a render pass.
+    RenderPassNotEnded(usize),
+    /// A draw or dispatch command was used without a pipeline bound.
+    NoPipelineBound(usize),
+    /// A query was begun while it was already active.
+    QueryAlreadyActive(usize),
+    /// A query was ended while it wasn't active.
+    QueryNotActive(usize),
+    /// The list ends while a query is still active.
+    QueryNotEnded(usize),
+    /// A debug label was ended without having been begun.
+    DebugLabelNotBegun(usize),
+    /// The list ends while a debug label is still active.
+    DebugLabelNotEnded(usize),
+}
+
+impl error::Error for CommandListError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            CommandListError::NestedRenderPass(_) => "a render pass was begun inside of a render \
+                                                      pass",
+            CommandListError::NotInsideRenderPass(_) => "a command that must be inside of a \
+                                                         render pass was used outside of one",
+            CommandListError::ForbiddenInsideRenderPass(_) => "a command that must be outside of \
+                                                               a render pass was used inside of \
+                                                               one",
+            CommandListError::RenderPassNotEnded(_) => "the list ends inside of a render pass",
+            CommandListError::NoPipelineBound(_) => "a draw or dispatch command was used without \
+                                                     a pipeline bound",
+            CommandListError::QueryAlreadyActive(_) => "a query was begun while it was already \
+                                                        active",
+            CommandListError::QueryNotActive(_) => "a query was ended while it wasn't active",
+            CommandListError::QueryNotEnded(_) => "the list ends while a query is still active",
+            CommandListError::DebugLabelNotBegun(_) => "a debug label was ended without having \
+                                                        been begun",
+            CommandListError::DebugLabelNotEnded(_) => "the list ends while a debug label is \
+                                                        still active",
+        }
+    }
+}
+
+impl fmt::Display for CommandListError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+#[inline]
+fn bind_point_index(bind_point: vk::PipelineBindPoint) -> usize {
+    if bind_point == vk::PIPELINE_BIND_POINT_COMPUTE { 1 } else { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::Command;
+    use command_buffer::CommandList;
+    use command_buffer::CommandListError;
+    use vk;
+
+    fn command_list(commands: Vec<Command>) -> CommandList {
+        let mut list = CommandList::new();
+        for command in commands.into_iter() {
+            list.push(command);
+        }
+        list
+    }
+
+    fn draw() -> Command {
+        Command::Draw { vertex_count: 3, instance_count: 1, first_vertex: 0, first_instance: 0 }
+    }
+
+    fn bind_pipeline(bind_point: vk::PipelineBindPoint) -> Command {
+        Command::BindPipeline { bind_point: bind_point, pipeline: 1 }
+    }
+
+    fn bind_sets(sets: Vec<vk::DescriptorSet>) -> Command {
+        Command::BindDescriptorSets {
+            bind_point: vk::PIPELINE_BIND_POINT_GRAPHICS,
+            layout: 7,
+            first_set: 0,
+            sets: sets,
+            dynamic_offsets: Vec::new(),
+        }
+    }
+
+    fn begin_render_pass() -> Command {
+        Command::BeginRenderPass {
+            render_pass: 1,
+            framebuffer: 2,
+            render_area: vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent: vk::Extent2D { width: 64, height: 64 },
+            },
+            clear_values: Vec::new(),
+            contents: vk::SUBPASS_CONTENTS_INLINE,
+        }
+    }
+
+    #[test]
+    fn redundant_pipeline() {
+        let mut list = command_list(vec![
+            bind_pipeline(vk::PIPELINE_BIND_POINT_GRAPHICS),
+            draw(),
+            bind_pipeline(vk::PIPELINE_BIND_POINT_GRAPHICS),
+            draw(),
+            bind_pipeline(vk::PIPELINE_BIND_POINT_COMPUTE),
+        ]);
+
+        list.remove_redundant_binds();
+        assert_eq!(list.len(), 4);
+    }
+
+    #[test]
+    fn redundant_descriptor_sets() {
+        let mut list = command_list(vec![
+            bind_sets(vec![1, 2]),
+            draw(),
+            bind_sets(vec![1, 2]),
+            draw(),
+            bind_sets(vec![1, 3]),
+            draw(),
+        ]);
+
+        list.remove_redundant_binds();
+        assert_eq!(list.len(), 5);
+    }
+
+    #[test]
+    fn pipeline_invalidates_sets() {
+        let mut list = command_list(vec![
+            bind_sets(vec![1]),
+            bind_pipeline(vk::PIPELINE_BIND_POINT_GRAPHICS),
+            bind_sets(vec![1]),
+            draw(),
+        ]);
+
+        list.remove_redundant_binds();
+        assert_eq!(list.len(), 4);
+    }
+
+    #[test]
+    fn execute_commands_resets_state() {
+        let mut list = command_list(vec![
+            Command::BindIndexBuffer { buffer: 1, offset: 0, index_type: 0 },
+            Command::ExecuteCommands { command_buffers: vec![5] },
+            Command::BindIndexBuffer { buffer: 1, offset: 0, index_type: 0 },
+            Command::BindIndexBuffer { buffer: 1, offset: 0, index_type: 0 },
+        ]);
+
+        list.remove_redundant_binds();
+        assert_eq!(list.len(), 3);
+    }
+
+    #[test]
+    fn validate_render_pass() {
+        let list = command_list(vec![
+            begin_render_pass(),
+            bind_pipeline(vk::PIPELINE_BIND_POINT_GRAPHICS),
+            draw(),
+            Command::NextSubpass { contents: vk::SUBPASS_CONTENTS_INLINE },
+            draw(),
+            Command::EndRenderPass,
+        ]);
+
+        assert_eq!(list.validate(false), Ok(()));
+        assert_eq!(list.validate(true), Err(CommandListError::NestedRenderPass(0)));
+    }
+
+    #[test]
+    fn validate_render_pass_not_ended() {
+        let list = command_list(vec![begin_render_pass()]);
+        assert_eq!(list.validate(false), Err(CommandListError::RenderPassNotEnded(1)));
+    }
+
+    #[test]
+    fn validate_end_render_pass() {
+        let list = command_list(vec![Command::EndRenderPass]);
+        assert_eq!(list.validate(false), Err(CommandListError::NotInsideRenderPass(0)));
+        assert_eq!(list.validate(true), Err(CommandListError::ForbiddenInsideRenderPass(0)));
+
+        let next_subpass = Command::NextSubpass { contents: vk::SUBPASS_CONTENTS_INLINE };
+        let list = command_list(vec![next_subpass]);
+        assert_eq!(list.validate(false), Err(CommandListError::NotInsideRenderPass(0)));
+        assert_eq!(list.validate(true), Err(CommandListError::ForbiddenInsideRenderPass(0)));
+    }
+
+    #[test]
+    fn validate_draw_outside_render_pass() {
+        let list = command_list(vec![bind_pipeline(vk::PIPELINE_BIND_POINT_GRAPHICS), draw()]);
+        assert_eq!(list.validate(false), Err(CommandListError::NotInsideRenderPass(1)));
+        assert_eq!(list.validate(true), Ok(()));
+    }
+
+    #[test]
+    fn validate_dispatch_inside_render_pass() {
+        let list = command_list(vec![bind_pipeline(vk::PIPELINE_BIND_POINT_COMPUTE),
+                             Command::Dispatch([1, 1, 1])]);
+        assert_eq!(list.validate(false), Ok(()));
+        assert_eq!(list.validate(true), Err(CommandListError::ForbiddenInsideRenderPass(1)));
+    }
+
+    #[test]
+    fn validate_pipeline_bound() {
+        let list = command_list(vec![
+            bind_pipeline(vk::PIPELINE_BIND_POINT_COMPUTE),
+            draw(),
+        ]);
+        assert_eq!(list.validate(true), Err(CommandListError::NoPipelineBound(1)));
+
+        let list = command_list(vec![
+            bind_pipeline(vk::PIPELINE_BIND_POINT_GRAPHICS),
+            Command::ExecuteCommands { command_buffers: vec![5] },
+            draw(),
+        ]);
+        assert_eq!(list.validate(true), Err(CommandListError::NoPipelineBound(2)));
+    }
+
+    #[test]
+    fn validate_queries() {
+        let list = command_list(vec![
+            Command::BeginQuery { pool: 1, query: 0, flags: 0 },
+            Command::BeginQuery { pool: 1, query: 1, flags: 0 },
+            Command::EndQuery { pool: 1, query: 0 },
+        ]);
+        assert_eq!(list.validate(false), Err(CommandListError::QueryNotEnded(3)));
+
+        let list = command_list(vec![Command::EndQuery { pool: 1, query: 0 }]);
+        assert_eq!(list.validate(false), Err(CommandListError::QueryNotActive(0)));
+    }
+
+    #[test]
+    fn validate_debug_labels() {
+        let list = command_list(vec![Command::EndDebugLabel]);
+        assert_eq!(list.validate(false), Err(CommandListError::DebugLabelNotBegun(0)));
+    }
+
+    #[test]
+    fn debug_output() {
+        let list = command_list(vec![Command::Dispatch([1, 2, 3]), Command::EndRenderPass]);
+        assert_eq!(format!("{:?}", list), "[Dispatch([1, 2, 3]), EndRenderPass]");
+    }
+}
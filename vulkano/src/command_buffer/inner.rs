@@ -1,29 +1,83 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use std::mem;
+use std::ops::Range;
 use std::ptr;
+use std::slice;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::u64;
+use std::usize;
 
 use buffer::Buffer;
 use buffer::BufferSlice;
 use buffer::BufferResource;
+use buffer::Usage as BufferUsage;
+use command_buffer::BufferCopyRegion;
+use command_buffer::BufferImageLayout;
+use command_buffer::ClearAttachment;
+use command_buffer::ClearRect;
+use command_buffer::Command;
+use command_buffer::CommandList;
 use command_buffer::CommandBufferPool;
+use command_buffer::DispatchIndirectCommand;
+use command_buffer::DrawError;
+use command_buffer::DrawIndexedIndirectCommand;
+use command_buffer::DrawIndirectCommand;
 use command_buffer::DynamicState;
+use command_buffer::DynamicStencilValue;
+use command_buffer::ImageRegion;
+use command_buffer::PipelineBarrierBuilder;
+use command_buffer::RenderArea;
+use command_buffer::ResetError;
+use command_buffer::SubmitError;
+use command_buffer::Usage;
+use command_buffer::barrier::aspect_mask;
+use descriptor_set::AbstractDescriptorSet;
+use descriptor_set::PipelineLayout;
 use descriptor_set::PipelineLayoutDesc;
 use descriptor_set::DescriptorSetsCollection;
+use descriptor_set::DescriptorType;
 use device::Queue;
+use formats::Data;
+use formats::Format;
+use formats::FormatMarker;
+use formats::FormatTy;
+use framebuffer::AttachmentDescription;
 use framebuffer::ClearValue;
 use framebuffer::Framebuffer;
+use framebuffer::LoadOp;
 use framebuffer::RenderPass;
 use framebuffer::RenderPassLayout;
+use image::Image;
 use image::ImageResource;
+use image::ImageTypeMarker;
+use image::Layout;
+use memory::HostVisible;
 use memory::MemorySourceChunk;
+use pipeline::ComputePipeline;
 use pipeline::GenericPipeline;
 use pipeline::GraphicsPipeline;
 use pipeline::input_assembly::Index;
 use pipeline::vertex::MultiVertex;
+use pipeline::vertex::VertexDefinition;
+use query::QueryPool;
+use query::QueryResultElement;
+use query::QueryResultFlags;
+use query::QueryType;
+use sampler::Filter;
+use sync::Event;
 use sync::Fence;
+use sync::FenceWaitError;
+use sync::PipelineStages;
 use sync::Resource;
 use sync::Semaphore;
+use sync::SharingMode;
 
+use device;
+use device::DebugName;
 use device::Device;
 use OomError;
 use VulkanObject;
@@ -50,6 +104,12 @@ pub struct InnerCommandBufferBuilder {
     //
     // These are stored just so that they don't get destroyed.
     pipelines: Vec<Arc<GenericPipeline>>,
+    descriptor_sets: Vec<Arc<AbstractDescriptorSet>>,
+    events: Vec<Arc<Event>>,
+    query_pools: Vec<Arc<QueryPool>>,
+
+    // Secondary command buffers that are executed by this command buffer.
+    secondaries: Vec<Arc<AbstractSecondaryCommandBuffer>>,
 
     // Current pipeline object binded to the graphics bind point.
     graphics_pipeline: Option<vk::Pipeline>,
@@ -57,49 +117,124 @@ pub struct InnerCommandBufferBuilder {
     // Current pipeline object binded to the compute bind point.
     compute_pipeline: Option<vk::Pipeline>,
 
+    // Descriptor sets binded to the graphics bind point, with the pipeline layout that was used
+    // to bind them and their dynamic offsets.
+    graphics_descriptor_sets: Option<BoundDescriptorSets>,
+
+    // Same as `graphics_descriptor_sets`, but for the compute bind point.
+    compute_descriptor_sets: Option<BoundDescriptorSets>,
+
     // Current state of the dynamic state within the command buffer.
     dynamic_state: DynamicState,
+
+    // Barrier that is going to be added before the next command. Built from the accesses that
+    // the next command declares.
+    pending_barrier: PipelineBarrierBuilder,
+
+    // Stages and accesses of the next command. Filled by `access_buffer` and `access_image`.
+    next_accesses: (vk::PipelineStageFlags, vk::AccessFlags),
+
+    // State of the buffers that have been accessed by the commands recorded so far, indexed by
+    // their Vulkan handle. An entry is removed whenever a barrier covers all of its accesses.
+    buffers_state: HashMap<vk::Buffer, Access>,
+
+    // Same as `buffers_state`, but for images.
+    images_state: HashMap<vk::Image, ImageState>,
+
+    // Stages and accesses of the writes to the resources of `buffers_state` and `images_state`
+    // that haven't been made visible yet to accesses through descriptor sets.
+    pending_writes: (vk::PipelineStageFlags, vk::AccessFlags),
+
+    // Stages that may have accessed resources through descriptor sets (or through any other way
+    // that can't be tracked, like secondary command buffers or render passes) since the last
+    // barrier.
+    descriptor_stages: vk::PipelineStageFlags,
+
+    // True if the commands are recorded inside a render pass. No barrier can be added then.
+    inside_render_pass: bool,
+
+    // Render pass that is being recorded, or that is inherited by a secondary command buffer.
+    render_pass: Option<RenderPassState>,
+
+    // True if the command buffer is a secondary command buffer.
+    secondary: bool,
+
+    // Queries that have been started with `begin_query` but not ended yet, and whether they were
+    // started inside a render pass.
+    active_queries: Vec<(vk::QueryPool, u32, QueryType, bool)>,
+
+    // If `Some`, the commands are stored in this list instead of being written to the command
+    // buffer, and are only written when the command buffer is built.
+    recorded: Option<CommandList>,
+
+    // How the command buffer is going to be used once it is built.
+    usage: Usage,
+
+    // Generation of the pool when the recording started.
+    generation: u64,
 }
 
 impl InnerCommandBufferBuilder {
     /// Creates a new builder.
-    pub fn new(pool: &Arc<CommandBufferPool>, secondary: bool)
-               -> Result<InnerCommandBufferBuilder, OomError>
+    ///
+    /// `render_pass` must be `Some` for secondary command buffers that are going to be
+    /// executed inside a render pass, and `None` otherwise.
+    pub fn new(pool: &Arc<CommandBufferPool>, secondary: bool, usage: Usage,
+               render_pass: Option<RenderPassState>) -> Result<InnerCommandBufferBuilder, OomError>
+    {
+        assert!(secondary || render_pass.is_none());
+        let cmd = try!(pool.alloc_command_buffer(secondary));
+        InnerCommandBufferBuilder::begin(pool, cmd, secondary, usage, render_pass)
+    }
+
+    // Starts recording a command buffer that has just been allocated or reset. The command
+    // buffer is freed if an error happens.
+    fn begin(pool: &Arc<CommandBufferPool>, cmd: vk::CommandBuffer, secondary: bool,
+             usage: Usage, render_pass: Option<RenderPassState>)
+             -> Result<InnerCommandBufferBuilder, OomError>
     {
         let device = pool.device();
         let vk = device.pointers();
 
-        let cmd = unsafe {
-            let infos = vk::CommandBufferAllocateInfo {
-                sType: vk::STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO,
-                pNext: ptr::null(),
-                commandPool: pool.internal_object(),
-                level: if secondary {
-                    vk::COMMAND_BUFFER_LEVEL_SECONDARY
-                } else {
-                    vk::COMMAND_BUFFER_LEVEL_PRIMARY
-                },
-                // vulkan can allocate multiple command buffers at once, hence the 1
-                commandBufferCount: 1,
-            };
+        let mut flags = match usage {
+            Usage::OneTime => vk::COMMAND_BUFFER_USAGE_ONE_TIME_SUBMIT_BIT,
+            Usage::MultipleSubmits => 0,
+            Usage::SimultaneousUse => vk::COMMAND_BUFFER_USAGE_SIMULTANEOUS_USE_BIT,
+        };
+
+        if render_pass.is_some() {
+            flags |= vk::COMMAND_BUFFER_USAGE_RENDER_PASS_CONTINUE_BIT;
+        }
 
-            let mut output = mem::uninitialized();
-            try!(check_errors(vk.AllocateCommandBuffers(device.internal_object(), &infos,
-                                                        &mut output)));
-            output
+        // secondary command buffers must always provide an inheritance info, even if they are
+        // not executed inside a render pass
+        let inheritance = vk::CommandBufferInheritanceInfo {
+            sType: vk::STRUCTURE_TYPE_COMMAND_BUFFER_INHERITANCE_INFO,
+            pNext: ptr::null(),
+            renderPass: render_pass.as_ref().map(|rp| rp.renderpass).unwrap_or(0),
+            subpass: render_pass.as_ref().map(|rp| rp.current_subpass as u32).unwrap_or(0),
+            framebuffer: render_pass.as_ref().and_then(|rp| rp.framebuffer).unwrap_or(0),
+            occlusionQueryEnable: vk::FALSE,
+            queryFlags: 0,
+            pipelineStatistics: 0,
         };
 
         unsafe {
             let infos = vk::CommandBufferBeginInfo {
                 sType: vk::STRUCTURE_TYPE_COMMAND_BUFFER_BEGIN_INFO,
                 pNext: ptr::null(),
-                flags: vk::COMMAND_BUFFER_USAGE_SIMULTANEOUS_USE_BIT,       // TODO:
-                pInheritanceInfo: ptr::null(),     // TODO: 
+                flags: flags,
+                pInheritanceInfo: if secondary { &inheritance } else { ptr::null() },
             };
 
-            try!(check_errors(vk.BeginCommandBuffer(cmd, &infos)));
+            if let Err(err) = check_errors(vk.BeginCommandBuffer(cmd, &infos)) {
+                pool.free_command_buffer(cmd);
+                return Err(From::from(err));
+            }
         }
 
+        let generation = pool.begin_recording();
+
         Ok(InnerCommandBufferBuilder {
             device: device.clone(),
             pool: pool.clone(),
@@ -107,406 +242,2350 @@ impl InnerCommandBufferBuilder {
             buffer_resources: Vec::new(),
             image_resources: Vec::new(),
             pipelines: Vec::new(),
+            descriptor_sets: Vec::new(),
+            events: Vec::new(),
+            query_pools: Vec::new(),
+            secondaries: Vec::new(),
+            active_queries: Vec::new(),
             graphics_pipeline: None,
             compute_pipeline: None,
+            graphics_descriptor_sets: None,
+            compute_descriptor_sets: None,
             dynamic_state: DynamicState::none(),
+            pending_barrier: PipelineBarrierBuilder::new(),
+            next_accesses: (0, 0),
+            buffers_state: HashMap::new(),
+            images_state: HashMap::new(),
+            pending_writes: (0, 0),
+            descriptor_stages: 0,
+            inside_render_pass: render_pass.is_some(),
+            render_pass: render_pass,
+            secondary: secondary,
+            recorded: None,
+            usage: usage,
+            generation: generation,
         })
     }
 
-    /// Executes the content of another command buffer.
-    ///
-    /// # Safety
+    /// Switches the builder to a mode where the commands are stored in a `CommandList` instead
+    /// of being written directly to the command buffer. The list is written to the
+    /// command buffer when `build` is called. The Vulkan command buffer is allocated anyway.
     ///
-    /// Care must be taken to respect the rules about secondary command buffers.
-    pub unsafe fn execute_commands<'a, I>(mut self, iter: I)
-                                          -> InnerCommandBufferBuilder
-        where I: Iterator<Item = &'a InnerCommandBuffer>
-    {
-        {
-            // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
-            let mut command_buffers = Vec::with_capacity(iter.size_hint().0);
+    /// The commands that have been added before calling this function are not part of the list.
+    /// Does nothing if the builder is already in this mode.
+    #[inline]
+    pub fn record_commands(mut self) -> InnerCommandBufferBuilder {
+        if self.recorded.is_none() {
+            self.recorded = Some(CommandList::new());
+        }
 
-            for cb in iter {
-                command_buffers.push(cb.cmd);
-                for p in cb.pipelines.iter() { self.pipelines.push(p.clone()); }
-                for r in cb.buffer_resources.iter() { self.buffer_resources.push(r.clone()); }
-                for r in cb.image_resources.iter() { self.image_resources.push(r.clone()); }
-            }
+        self
+    }
 
-            let vk = self.device.pointers();
-            vk.CmdExecuteCommands(self.cmd.unwrap(), command_buffers.len() as u32,
-                                  command_buffers.as_ptr());
+    /// Returns the commands that have been recorded so far, or `None` if `record_commands`
+    /// hasn't been called.
+    #[inline]
+    pub fn commands(&self) -> Option<&CommandList> {
+        self.recorded.as_ref()
+    }
+
+    // Adds a command to the list of recorded commands, or writes it directly to the command
+    // buffer if the builder isn't in recording mode.
+    unsafe fn write(&mut self, command: Command) {
+        if let Some(ref mut list) = self.recorded {
+            list.push(command);
+            return;
         }
 
-        self
+        command.replay(self.device.pointers(), self.cmd.unwrap());
     }
 
-    /// Writes data to a buffer.
+    /// Adds a pipeline barrier to the command buffer.
+    ///
+    /// The barriers that are required by the commands that the builder knows about are added
+    /// automatically. This function is only useful for accesses that can't be tracked.
     ///
     /// # Panic
     ///
-    /// - Panicks if the size of `data` is not the same as the size of the buffer slice.
-    /// - Panicks if the size of `data` is superior to 65536 bytes.
-    /// - Panicks if the offset or size is not a multiple of 4.
-    /// - Panicks if the buffer wasn't created with the right usage.
-    /// - Panicks if the queue family doesn't support transfer operations.
+    /// - Panicks if called inside a render pass.
     ///
     /// # Safety
     ///
     /// - Care must be taken to respect the rules about secondary command buffers.
     ///
-    pub unsafe fn update_buffer<'a, B, T: 'a, M: 'a>(self, buffer: B, data: &T)
-                                                 -> InnerCommandBufferBuilder
-        where B: Into<BufferSlice<'a, T, M>>
+    pub unsafe fn pipeline_barrier(mut self, barrier: PipelineBarrierBuilder)
+                                   -> InnerCommandBufferBuilder
     {
-        {
-            let vk = self.device.pointers();
-            let buffer = buffer.into();
-
-            assert!(self.pool.queue_family().supports_transfers());
-            assert_eq!(buffer.size(), mem::size_of_val(data));
-            assert!(buffer.size() <= 65536);
-            assert!(buffer.offset() % 4 == 0);
-            assert!(buffer.size() % 4 == 0);
-            assert!(buffer.usage_transfer_dest());
+        assert!(!self.inside_render_pass);
 
-            // FIXME: check that the queue family supports transfers
-            // FIXME: add the buffer to the list of resources
-            // FIXME: check queue family of the buffer
+        // the image barriers of `barrier` expect the images to be in their default layout
+        self.restore_default_layouts();
+        self.flush_barrier();
 
-            vk.CmdUpdateBuffer(self.cmd.unwrap(), buffer.internal_object(),
-                               buffer.offset() as vk::DeviceSize,
-                               buffer.size() as vk::DeviceSize, data as *const T as *const _);
+        {
+            let (command, buffers, images) = barrier.into_command();
+            if let Some(command) = command {
+                self.write(command);
+            }
+            self.buffer_resources.extend(buffers.into_iter());
+            self.image_resources.extend(images.into_iter());
         }
 
         self
     }
 
-    /// Fills a buffer with data.
+    /// Calls `vkCmdSetEvent`. The event is signaled once the given stages of all the previous
+    /// commands are finished.
     ///
     /// # Panic
     ///
-    /// - Panicks if `offset + data` is superior to the size of the buffer.
-    /// - Panicks if the offset or size is not a multiple of 4.
-    /// - Panicks if the buffer wasn't created with the right usage.
-    /// - Panicks if the queue family doesn't support transfer operations.
+    /// - Panicks if called inside a render pass.
+    /// - Panicks if the event doesn't belong to the same device.
+    /// - Panicks if `stages` is empty.
     ///
-    /// # Safety
+    pub unsafe fn set_event(mut self, event: &Arc<Event>, stages: PipelineStages)
+                            -> InnerCommandBufferBuilder
+    {
+        assert!(!self.inside_render_pass);
+        assert_eq!(event.device().internal_object(), self.device.internal_object());
+
+        let stages: vk::PipelineStageFlags = stages.into();
+        assert!(stages != 0);
+
+        self.flush_barrier();
+
+        self.write(Command::SetEvent { event: event.internal_object(), stages: stages });
+
+        self.events.push(event.clone());
+        self
+    }
+
+    /// Calls `vkCmdResetEvent`. The event is unsignaled once the given stages of all the
+    /// previous commands are finished.
     ///
-    /// - Type safety is not enforced by the API.
-    /// - Care must be taken to respect the rules about secondary command buffers.
+    /// # Panic
     ///
-    pub unsafe fn fill_buffer<T: 'static, M>(mut self, buffer: &Arc<Buffer<T, M>>, offset: usize,
-                                             size: usize, data: u32) -> InnerCommandBufferBuilder
-        where M: MemorySourceChunk + 'static
+    /// - Panicks if called inside a render pass.
+    /// - Panicks if the event doesn't belong to the same device.
+    /// - Panicks if `stages` is empty.
+    ///
+    pub unsafe fn reset_event(mut self, event: &Arc<Event>, stages: PipelineStages)
+                              -> InnerCommandBufferBuilder
     {
-        {
-            let vk = self.device.pointers();
-
-            assert!(self.pool.queue_family().supports_transfers());
-            assert!(offset + size <= buffer.size());
-            assert!(offset % 4 == 0);
-            assert!(size % 4 == 0);
-            assert!(buffer.usage_transfer_dest());
+        assert!(!self.inside_render_pass);
+        assert_eq!(event.device().internal_object(), self.device.internal_object());
 
-            self.buffer_resources.push(buffer.clone());
+        let stages: vk::PipelineStageFlags = stages.into();
+        assert!(stages != 0);
 
-            // FIXME: check that the queue family supports transfers
-            // FIXME: check queue family of the buffer
+        self.flush_barrier();
 
-            vk.CmdFillBuffer(self.cmd.unwrap(), buffer.internal_object(),
-                             offset as vk::DeviceSize, size as vk::DeviceSize, data);
-        }
+        self.write(Command::ResetEvent { event: event.internal_object(), stages: stages });
 
+        self.events.push(event.clone());
         self
     }
 
-    /// Copies data between buffers.
+    /// Calls `vkCmdWaitEvents`. The destination stages of the barrier of the next commands can't
+    /// start before all the events are signaled, and the memory barriers of `barrier` are
+    /// applied.
     ///
     /// # Panic
     ///
-    /// - Panicks if the buffers don't belong to the same device.
-    /// - Panicks if one of the buffers wasn't created with the right usage.
-    /// - Panicks if the queue family doesn't support transfer operations.
+    /// - Panicks if called inside a render pass.
+    /// - Panicks if `events` is empty.
+    /// - Panicks if one of the events doesn't belong to the same device.
+    /// - Panicks if the barrier doesn't have any source or destination stage.
     ///
     /// # Safety
     ///
-    /// - Type safety is not enforced by the API.
+    /// - The source stages of the barrier must include the stages that were used to signal the
+    ///   events.
     /// - Care must be taken to respect the rules about secondary command buffers.
     ///
-    // TODO: doesn't support slices
-    pub unsafe fn copy_buffer<T: ?Sized + 'static, Ms, Md>(mut self, source: &Arc<Buffer<T, Ms>>,
-                                                           destination: &Arc<Buffer<T, Md>>)
-                                                           -> InnerCommandBufferBuilder
-        where Ms: MemorySourceChunk + 'static, Md: MemorySourceChunk + 'static
+    pub unsafe fn wait_events(mut self, events: &[Arc<Event>], barrier: PipelineBarrierBuilder)
+                              -> InnerCommandBufferBuilder
     {
-        {
-            let vk = self.device.pointers();
+        assert!(!self.inside_render_pass);
+        assert!(!events.is_empty());
+        assert!(!barrier.is_empty());
 
-            assert_eq!(&**source.device() as *const _, &**destination.device() as *const _);
-            assert!(self.pool.queue_family().supports_transfers());
-            assert!(source.usage_transfer_src());
-            assert!(destination.usage_transfer_dest());
+        for event in events.iter() {
+            assert_eq!(event.device().internal_object(), self.device.internal_object());
+        }
 
-            let copy = vk::BufferCopy {
-                srcOffset: 0,
-                dstOffset: 0,
-                size: source.size() as u64,
-            };
+        // the image barriers of `barrier` expect the images to be in their default layout
+        self.restore_default_layouts();
+        self.flush_barrier();
 
-            vk.CmdCopyBuffer(self.cmd.unwrap(), source.internal_object(),
-                             destination.internal_object(), 1, &copy);
+        {
+            let raw_events = events.iter().map(|e| e.internal_object()).collect::<Vec<_>>();
+            let (command, buffers, images) = barrier.into_wait_events_command(raw_events);
+            self.write(command);
+            self.buffer_resources.extend(buffers.into_iter());
+            self.image_resources.extend(images.into_iter());
+        }
 
-            self.buffer_resources.push(source.clone());
-            self.buffer_resources.push(destination.clone());
+        for event in events.iter() {
+            self.events.push(event.clone());
         }
 
         self
     }
 
-    /// Calls `vkCmdDraw`.
-    // FIXME: push constants
-    pub unsafe fn draw<V, Pl, L>(mut self, pipeline: &Arc<GraphicsPipeline<V, Pl>>,
-                             vertices: V, dynamic: &DynamicState,
-                             sets: L) -> InnerCommandBufferBuilder
-        where V: 'static + MultiVertex, L: 'static + DescriptorSetsCollection,
-              Pl: 'static + PipelineLayoutDesc
+    /// Calls `vkCmdResetQueryPool`. The queries must be reset before they can be used.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if called inside a render pass.
+    /// - Panicks if the pool doesn't belong to the same device.
+    /// - Panicks if the range is out of range of the pool.
+    ///
+    pub unsafe fn reset_query_pool<P>(mut self, pool: &Arc<P>, range: Range<u32>)
+                                      -> InnerCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
     {
+        assert!(!self.inside_render_pass);
+        assert_eq!(pool.device().internal_object(), self.device.internal_object());
+        assert!(range.start <= range.end);
+        assert!(range.end <= pool.num_slots());
 
-        // FIXME: add buffers to the resources
-
-        {
-            self.bind_gfx_pipeline_state(pipeline, dynamic, sets);
-
-            let vk = self.device.pointers();
+        self.flush_barrier();
 
-            let buffers = vertices.buffers();
-            // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
-            let offsets = (0 .. buffers.len()).map(|_| 0).collect::<Vec<_>>();
-            let ids = buffers.map(|b| b.internal_object()).collect::<Vec<_>>();
-            vk.CmdBindVertexBuffers(self.cmd.unwrap(), 0, ids.len() as u32, ids.as_ptr(),
-                                    offsets.as_ptr());
-            vk.CmdDraw(self.cmd.unwrap(), 3, 1, 0, 0);  // FIXME: params
-        }
+        self.write(Command::ResetQueryPool {
+            pool: pool.internal_object(),
+            first_query: range.start,
+            query_count: range.end - range.start,
+        });
 
+        self.query_pools.push(pool.clone());
         self
     }
 
-    /// Calls `vkCmdDrawIndexed`.
-    // FIXME: push constants
-    pub unsafe fn draw_indexed<'a, V, Pl, L, I, Ib, IbM>(mut self, pipeline: &Arc<GraphicsPipeline<V, Pl>>,
-                                                 vertices: V, indices: Ib, dynamic: &DynamicState,
-                                                 sets: L) -> InnerCommandBufferBuilder
-        where V: 'static + MultiVertex, L: 'static + DescriptorSetsCollection,
-              Pl: 'static + PipelineLayoutDesc,
-              Ib: Into<BufferSlice<'a, [I], IbM>>, I: 'static + Index, IbM: 'static
+    /// Calls `vkCmdBeginQuery`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the pool doesn't belong to the same device.
+    /// - Panicks if the slot is out of range of the pool.
+    /// - Panicks if the pool is a timestamp pool.
+    /// - Panicks if `precise` is true and the pool is not an occlusion pool, or the
+    ///   `occlusion_query_precise` feature is not enabled.
+    /// - Panicks if a query of the same type is already active.
+    ///
+    pub unsafe fn begin_query<P>(mut self, pool: &Arc<P>, slot: u32, precise: bool)
+                                 -> InnerCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
     {
+        assert_eq!(pool.device().internal_object(), self.device.internal_object());
+        assert!(slot < pool.num_slots());
 
-        // FIXME: add buffers to the resources
-
-        {
-            self.bind_gfx_pipeline_state(pipeline, dynamic, sets);
-
-            let vk = self.device.pointers();
-
-            let indices = indices.into();
+        let ty = pool.query_type();
+        assert!(ty != QueryType::Timestamp);
+        assert!(!precise || (ty == QueryType::Occlusion &&
+                             self.device.enabled_features().occlusion_query_precise));
+        assert!(self.active_queries.iter().all(|&(_, _, t, _)| t != ty));
 
-            let buffers = vertices.buffers();
-            // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
-            let offsets = (0 .. buffers.len()).map(|_| 0).collect::<Vec<_>>();
-            let ids = buffers.map(|b| b.internal_object()).collect::<Vec<_>>();
+        self.flush_barrier();
 
-            vk.CmdBindIndexBuffer(self.cmd.unwrap(), indices.buffer().internal_object(),
-                                  indices.offset() as u64, I::ty() as u32);
-            vk.CmdBindVertexBuffers(self.cmd.unwrap(), 0, ids.len() as u32, ids.as_ptr(),
-                                    offsets.as_ptr());
-            vk.CmdDrawIndexed(self.cmd.unwrap(), indices.len() as u32, 1, 0, 0, 0);  // FIXME: params
-        }
+        self.write(Command::BeginQuery {
+            pool: pool.internal_object(),
+            query: slot,
+            flags: if precise { vk::QUERY_CONTROL_PRECISE_BIT } else { 0 },
+        });
 
+        self.active_queries.push((pool.internal_object(), slot, ty, self.inside_render_pass));
+        self.query_pools.push(pool.clone());
         self
     }
 
-    fn bind_gfx_pipeline_state<V, Pl, L>(&mut self, pipeline: &Arc<GraphicsPipeline<V, Pl>>,
-                                         dynamic: &DynamicState, sets: L)
-        where V: 'static + MultiVertex, L: 'static + DescriptorSetsCollection,
-              Pl: 'static + PipelineLayoutDesc
+    /// Calls `vkCmdEndQuery`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the query hasn't been started with `begin_query`.
+    /// - Panicks if the query was started inside a render pass and this is called outside of
+    ///   it, or the other way around.
+    ///
+    pub unsafe fn end_query<P>(mut self, pool: &Arc<P>, slot: u32) -> InnerCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
     {
-        unsafe {
-            let vk = self.device.pointers();
-
-            assert!(sets.is_compatible_with(pipeline.layout()));
-
-            if self.graphics_pipeline != Some(pipeline.internal_object()) {
-                vk.CmdBindPipeline(self.cmd.unwrap(), vk::PIPELINE_BIND_POINT_GRAPHICS,
-                                   pipeline.internal_object());
-                self.pipelines.push(pipeline.clone());
-                self.graphics_pipeline = Some(pipeline.internal_object());
-            }
+        let position = self.active_queries.iter().position(|&(p, s, _, _)| {
+            p == pool.internal_object() && s == slot
+        });
+        let position = position.expect("The query is not active");
+        let (_, _, _, inside) = self.active_queries.remove(position);
+        assert_eq!(inside, self.inside_render_pass);
 
-            if let Some(line_width) = dynamic.line_width {
-                assert!(pipeline.has_dynamic_line_width());
-                // TODO: check limits
-                if self.dynamic_state.line_width != Some(line_width) {
-                    vk.CmdSetLineWidth(self.cmd.unwrap(), line_width);
-                    self.dynamic_state.line_width = Some(line_width);
-                }
-            } else {
-                assert!(!pipeline.has_dynamic_line_width());
-            }
-
-            // FIXME: keep these alive
-            // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
-            let descriptor_sets = sets.list().collect::<Vec<_>>();
-            // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
-            let descriptor_sets = descriptor_sets.into_iter().map(|set| set.internal_object()).collect::<Vec<_>>();
+        self.write(Command::EndQuery { pool: pool.internal_object(), query: slot });
 
-            // TODO: shouldn't rebind everything every time
-            if !descriptor_sets.is_empty() {
-                vk.CmdBindDescriptorSets(self.cmd.unwrap(), vk::PIPELINE_BIND_POINT_GRAPHICS,
-                                         pipeline.layout().internal_object(), 0,
-                                         descriptor_sets.len() as u32, descriptor_sets.as_ptr(),
-                                         0, ptr::null());   // FIXME: dynamic offsets
-            }
-        }
+        self
     }
 
-    /// Calls `vkCmdBeginRenderPass`.
+    /// Calls `vkCmdWriteTimestamp`. The timestamp is written once the given stage of all the
+    /// previous commands is finished.
     ///
     /// # Panic
     ///
-    /// - Panicks if the framebuffer is not compatible with the renderpass.
-    ///
-    /// # Safety
-    ///
-    /// - Care must be taken to respect the rules about secondary command buffers.
+    /// - Panicks if the pool doesn't belong to the same device.
+    /// - Panicks if the slot is out of range of the pool.
+    /// - Panicks if the pool is not a timestamp pool.
+    /// - Panicks if `stage` doesn't contain exactly one stage.
     ///
-    #[inline]
-    pub unsafe fn begin_renderpass<R, F>(mut self, renderpass: &Arc<RenderPass<R>>,
-                                         framebuffer: &Arc<Framebuffer<F>>,
-                                         secondary_cmd_buffers: bool,
-                                         clear_values: &[ClearValue]) -> InnerCommandBufferBuilder
-        where R: RenderPassLayout, F: RenderPassLayout
+    pub unsafe fn write_timestamp<P>(mut self, pool: &Arc<P>, slot: u32, stage: PipelineStages)
+                                     -> InnerCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
     {
-        assert!(framebuffer.is_compatible_with(renderpass));
+        assert_eq!(pool.device().internal_object(), self.device.internal_object());
+        assert!(slot < pool.num_slots());
+        assert!(pool.query_type() == QueryType::Timestamp);
 
-        // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
-        let clear_values = clear_values.iter().map(|value| {
-            match *value {
-                ClearValue::None => vk::ClearValue::color({
-                    vk::ClearColorValue::float32([0.0, 0.0, 0.0, 0.0])
-                }),
-                ClearValue::Float(data) => vk::ClearValue::color(vk::ClearColorValue::float32(data)),
-                ClearValue::Int(data) => vk::ClearValue::color(vk::ClearColorValue::int32(data)),
-                ClearValue::Uint(data) => vk::ClearValue::color(vk::ClearColorValue::uint32(data)),
-                ClearValue::Depth(d) => vk::ClearValue::depth_stencil({
-                    vk::ClearDepthStencilValue { depth: d, stencil: 0 }
-                }),
-                ClearValue::Stencil(s) => vk::ClearValue::depth_stencil({
-                    vk::ClearDepthStencilValue { depth: 0.0, stencil: s }
-                }),
-                ClearValue::DepthStencil((d, s)) => vk::ClearValue::depth_stencil({
-                    vk::ClearDepthStencilValue { depth: d, stencil: s }
-                }),
-            }
-        }).collect::<Vec<_>>();
+        let stage: vk::PipelineStageFlagBits = stage.into();
+        assert_eq!(stage.count_ones(), 1);
 
-        // FIXME: change attachment image layouts if necessary, for both initial and final
-        /*for attachment in R::attachments() {
+        self.flush_barrier();
 
-        }*/
+        self.write(Command::WriteTimestamp {
+            stage: stage,
+            pool: pool.internal_object(),
+            query: slot,
+        });
 
-        for attachment in framebuffer.attachments() {
-            self.image_resources.push(attachment.clone());
+        self.query_pools.push(pool.clone());
+        self
+    }
+
+    /// Calls `vkCmdDebugMarkerBeginEXT`. Does nothing if the debug markers aren't enabled on
+    /// the device.
+    ///
+    /// No barrier is flushed, so that the barriers of the next commands are inside the label.
+    pub unsafe fn begin_label(mut self, name: &str, color: [f32; 4]) -> InnerCommandBufferBuilder {
+        if self.device.debug_markers_enabled() {
+            self.write(Command::BeginDebugLabel {
+                name: device::debug_name(name),
+                color: color,
+            });
         }
 
-        {
-            let vk = self.device.pointers();
+        self
+    }
 
-            let infos = vk::RenderPassBeginInfo {
-                sType: vk::STRUCTURE_TYPE_RENDER_PASS_BEGIN_INFO,
-                pNext: ptr::null(),
-                renderPass: renderpass.internal_object(),
-                framebuffer: framebuffer.internal_object(),
-                renderArea: vk::Rect2D {                // TODO: let user customize
-                    offset: vk::Offset2D { x: 0, y: 0 },
-                    extent: vk::Extent2D {
-                        width: framebuffer.width(),
-                        height: framebuffer.height(),
-                    },
-                },
-                clearValueCount: clear_values.len() as u32,
-                pClearValues: clear_values.as_ptr(),
-            };
+    /// Calls `vkCmdDebugMarkerEndEXT`. Does nothing if the debug markers aren't enabled on
+    /// the device.
+    pub unsafe fn end_label(mut self) -> InnerCommandBufferBuilder {
+        if self.device.debug_markers_enabled() {
+            self.write(Command::EndDebugLabel);
+        }
 
-            let content = if secondary_cmd_buffers {
-                vk::SUBPASS_CONTENTS_SECONDARY_COMMAND_BUFFERS
-            } else {
-                vk::SUBPASS_CONTENTS_INLINE
-            };
+        self
+    }
 
-            vk.CmdBeginRenderPass(self.cmd.unwrap(), &infos, content);
+    /// Calls `vkCmdDebugMarkerInsertEXT`. Does nothing if the debug markers aren't enabled on
+    /// the device.
+    pub unsafe fn insert_label(mut self, name: &str, color: [f32; 4])
+                               -> InnerCommandBufferBuilder
+    {
+        if self.device.debug_markers_enabled() {
+            self.write(Command::InsertDebugLabel {
+                name: device::debug_name(name),
+                color: color,
+            });
         }
 
         self
     }
 
-    #[inline]
-    pub unsafe fn next_subpass(self, secondary_cmd_buffers: bool) -> InnerCommandBufferBuilder {
-        {
-            let vk = self.device.pointers();
+    /// Calls `vkCmdCopyQueryPoolResults`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if called inside a render pass.
+    /// - Panicks if the pool doesn't belong to the same device.
+    /// - Panicks if the range is out of range of the pool.
+    /// - Panicks if the buffer doesn't have the right length.
+    /// - Panicks if the buffer wasn't created with the right usage.
+    /// - Panicks if the offset of the buffer is not a multiple of the size of `T`.
+    /// - Panicks if `flags.partial` is true and the pool is a timestamp pool.
+    ///
+    pub unsafe fn copy_query_pool_results<'a, P, B, T, M>(mut self, pool: &Arc<P>,
+                                                          range: Range<u32>, destination: B,
+                                                          flags: QueryResultFlags)
+                                                          -> InnerCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static,
+              B: Into<BufferSlice<'a, [T], M>>, T: QueryResultElement + 'static, M: 'static
+    {
+        let destination = destination.into();
 
-            let content = if secondary_cmd_buffers {
-                vk::SUBPASS_CONTENTS_SECONDARY_COMMAND_BUFFERS
-            } else {
-                vk::SUBPASS_CONTENTS_INLINE
-            };
+        assert!(!self.inside_render_pass);
+        assert_eq!(pool.device().internal_object(), self.device.internal_object());
+        assert!(range.start <= range.end);
+        assert!(range.end <= pool.num_slots());
+        assert_eq!(destination.len(), (range.end - range.start) as usize *
+                                      pool.num_values_per_query());
+        assert!(destination.usage_transfer_dest());
+        assert!(destination.offset() % mem::size_of::<T>() == 0);
+        assert!(!flags.partial || pool.query_type() != QueryType::Timestamp);
 
-            vk.CmdNextSubpass(self.cmd.unwrap(), content);
+        self.access_buffer(destination.internal_object(), TRANSFER_WRITE);
+        self.flush_barrier();
+
+        {
+            let flags: vk::QueryResultFlags = flags.into();
+            let stride = mem::size_of::<T>() * pool.num_values_per_query();
+            self.write(Command::CopyQueryPoolResults {
+                pool: pool.internal_object(),
+                first_query: range.start,
+                query_count: range.end - range.start,
+                destination: destination.internal_object(),
+                offset: destination.offset() as vk::DeviceSize,
+                stride: stride as vk::DeviceSize,
+                flags: flags | T::flags(),
+            });
         }
 
+        self.buffer_resources.push(destination.buffer().clone());
+        self.query_pools.push(pool.clone());
         self
     }
 
-    #[inline]
-    pub unsafe fn end_renderpass(self) -> InnerCommandBufferBuilder {
+    /// Executes the content of another command buffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if a command buffer that inherits a render pass is executed outside of a render
+    ///   pass, or the other way around.
+    /// - Panicks if a command buffer inherits a render pass that is not compatible with the
+    ///   current render pass, a different subpass or a different framebuffer.
+    /// - Panicks if a query is active, as the secondary command buffers don't inherit queries.
+    ///
+    /// # Safety
+    ///
+    /// Care must be taken to respect the rules about secondary command buffers.
+    pub unsafe fn execute_commands<I>(mut self, iter: I) -> InnerCommandBufferBuilder
+        where I: Iterator<Item = Arc<AbstractSecondaryCommandBuffer>>
+    {
+        // the secondary command buffers are begun without any inherited query
+        assert!(self.active_queries.is_empty());
+
+        // the secondary command buffers can access any resource
+        self.full_barrier();
+
         {
-            let vk = self.device.pointers();
-            vk.CmdEndRenderPass(self.cmd.unwrap());
-        }
+            let mut command_buffers = Vec::with_capacity(iter.size_hint().0);
 
-        self
-    }
+            for secondary in iter {
+                {
+                    let cb = secondary.inner();
+                    match (&self.render_pass, &cb.render_pass) {
+                        (&Some(ref current), &Some(ref inherited)) => {
+                            assert!(inherited.can_be_executed_in(current));
+                        },
+                        (&None, &None) => (),
+                        _ => panic!("The render pass of a secondary command buffer doesn't match")
+                    }
+
+                    // the resources are synchronized when the primary command buffer is
+                    // submitted ; the other objects are kept alive by the secondary command
+                    // buffer itself
+                    command_buffers.push(cb.cmd);
+                    for r in cb.buffer_resources.iter() { self.buffer_resources.push(r.clone()); }
+                    for r in cb.image_resources.iter() { self.image_resources.push(r.clone()); }
+                }
 
-    /// Finishes building the command buffer.
-    pub fn build(mut self) -> Result<InnerCommandBuffer, OomError> {
-        unsafe {
-            let vk = self.device.pointers();
-            let cmd = self.cmd.take().unwrap();
+                self.secondaries.push(secondary);
+            }
 
-            // ending the commands recording
-            try!(check_errors(vk.EndCommandBuffer(cmd)));
+            self.write(Command::ExecuteCommands { command_buffers: command_buffers });
+        }
 
-            Ok(InnerCommandBuffer {
-                device: self.device.clone(),
-                pool: self.pool.clone(),
-                cmd: cmd,
-                buffer_resources: mem::replace(&mut self.buffer_resources, Vec::new()),
-                image_resources: mem::replace(&mut self.image_resources, Vec::new()),
-                pipelines: mem::replace(&mut self.pipelines, Vec::new()),
-            })
+        if !self.inside_render_pass {
+            self.descriptor_stages = vk::PIPELINE_STAGE_ALL_COMMANDS_BIT;
         }
+
+        // the state of the command buffer is undefined after executing secondary command buffers
+        self.graphics_pipeline = None;
+        self.compute_pipeline = None;
+        self.graphics_descriptor_sets = None;
+        self.compute_descriptor_sets = None;
+        self.dynamic_state = DynamicState::none();
+
+        self
     }
-}
 
-impl Drop for InnerCommandBufferBuilder {
-    #[inline]
+    /// Writes data to a buffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the size of `data` is not the same as the size of the buffer slice.
+    /// - Panicks if `data` is empty.
+    /// - Panicks if the size of `data` is superior to 65536 bytes.
+    /// - Panicks if the offset or size is not a multiple of 4.
+    /// - Panicks if the buffer wasn't created with the right usage.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn update_buffer<'a, B, T: ?Sized + 'a, M: 'a>(mut self, buffer: B, data: &T)
+                                                          -> InnerCommandBufferBuilder
+        where B: Into<BufferSlice<'a, T, M>>
+    {
+        {
+            let buffer = buffer.into();
+
+            assert!(self.pool.queue_family().supports_transfers());
+            assert_eq!(buffer.size(), mem::size_of_val(data));
+            assert!(buffer.size() != 0);
+            assert!(buffer.size() <= 65536);
+            assert!(buffer.offset() % 4 == 0);
+            assert!(buffer.size() % 4 == 0);
+            assert!(buffer.usage_transfer_dest());
+
+            // FIXME: check queue family of the buffer
+
+            self.access_buffer(buffer.internal_object(), TRANSFER_WRITE);
+            self.flush_barrier();
+
+            let data = slice::from_raw_parts(data as *const T as *const u8, buffer.size());
+            self.write(Command::UpdateBuffer {
+                buffer: buffer.internal_object(),
+                offset: buffer.offset() as vk::DeviceSize,
+                data: data.to_vec(),
+            });
+
+            self.buffer_resources.push(buffer.buffer().clone());
+        }
+
+        self
+    }
+
+    /// Writes data to a buffer, whatever its size.
+    ///
+    /// Small amounts of data are written with `update_buffer`. Otherwise the data is written to a
+    /// host-visible staging buffer which is then copied to the destination. The staging buffer
+    /// is kept alive by the command buffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the size of `data` is not the same as the size of the buffer slice.
+    /// - Panicks if `data` is empty.
+    /// - Panicks if the buffer wasn't created with the right usage.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn upload<'a, B, T: ?Sized + 'a, M: 'a>(self, buffer: B, data: &T)
+                                                       -> Result<InnerCommandBufferBuilder, OomError>
+        where B: Into<BufferSlice<'a, T, M>>
+    {
+        let buffer = buffer.into();
+        let size = mem::size_of_val(data);
+        assert_eq!(buffer.size(), size);
+        assert!(size != 0);
+
+        if size <= 65536 && buffer.offset() % 4 == 0 && size % 4 == 0 {
+            return Ok(self.update_buffer(buffer, data));
+        }
+
+        let sharing = SharingMode::Exclusive(self.pool.queue_family().id());
+        let staging = try!(Buffer::<[u8], _>::array(&self.device, size,
+                                                    &BufferUsage::transfer_source(), HostVisible,
+                                                    sharing));
+
+        {
+            // the staging buffer has just been created, so it can't be in use by the GPU
+            let mut mapping = staging.write(0).unwrap();
+            ptr::copy_nonoverlapping(data as *const T as *const u8, mapping.as_mut_ptr(), size);
+        }
+
+        let copy = vk::BufferCopy {
+            srcOffset: 0,
+            dstOffset: buffer.offset() as vk::DeviceSize,
+            size: size as vk::DeviceSize,
+        };
+
+        Ok(self.copy_buffer_raw(BufferSlice::from(&staging), buffer, vec![copy]))
+    }
+
+    /// Fills a buffer with data.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `offset + data` is superior to the size of the buffer.
+    /// - Panicks if the offset or size is not a multiple of 4.
+    /// - Panicks if the buffer wasn't created with the right usage.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    /// # Safety
+    ///
+    /// - Type safety is not enforced by the API.
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn fill_buffer<T: 'static, M>(mut self, buffer: &Arc<Buffer<T, M>>, offset: usize,
+                                             size: usize, data: u32) -> InnerCommandBufferBuilder
+        where M: MemorySourceChunk + 'static
+    {
+        {
+            assert!(self.pool.queue_family().supports_transfers());
+            assert!(offset + size <= buffer.size());
+            assert!(offset % 4 == 0);
+            assert!(size % 4 == 0);
+            assert!(buffer.usage_transfer_dest());
+
+            self.buffer_resources.push(buffer.clone());
+
+            // FIXME: check that the queue family supports transfers
+            // FIXME: check queue family of the buffer
+
+            self.access_buffer(buffer.internal_object(), TRANSFER_WRITE);
+            self.flush_barrier();
+
+            self.write(Command::FillBuffer {
+                buffer: buffer.internal_object(),
+                offset: offset as vk::DeviceSize,
+                size: size as vk::DeviceSize,
+                data: data,
+            });
+        }
+
+        self
+    }
+
+    /// Copies the content of a buffer slice to another buffer slice.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the buffers don't belong to the same device.
+    /// - Panicks if one of the buffers wasn't created with the right usage.
+    /// - Panicks if the slices don't have the same size.
+    /// - Panicks if the slices are empty.
+    /// - Panicks if the slices overlap.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn copy_buffer<'a, 'b, S, D, T: ?Sized + 'static, Ms, Md>(self, source: S,
+                                                                         destination: D)
+                                                                         -> InnerCommandBufferBuilder
+        where S: Into<BufferSlice<'a, T, Ms>>, D: Into<BufferSlice<'b, T, Md>>,
+              Ms: 'static, Md: 'static
+    {
+        let source = source.into();
+        let destination = destination.into();
+        assert_eq!(source.size(), destination.size());
+        assert!(source.size() != 0);
+
+        let copy = vk::BufferCopy {
+            srcOffset: source.offset() as vk::DeviceSize,
+            dstOffset: destination.offset() as vk::DeviceSize,
+            size: source.size() as vk::DeviceSize,
+        };
+
+        self.copy_buffer_raw(source, destination, vec![copy])
+    }
+
+    /// Copies regions of a buffer slice to regions of another buffer slice with one command.
+    ///
+    /// The offsets and lengths of the regions are in number of elements and are relative to the
+    /// start of the slices.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the buffers don't belong to the same device.
+    /// - Panicks if one of the buffers wasn't created with the right usage.
+    /// - Panicks if one of the regions is empty or out of range of its slice.
+    /// - Panicks if one of the source regions overlaps one of the destination regions.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn copy_buffer_regions<'a, 'b, S, D, T: 'static, Ms, Md>(self, source: S,
+                                                                        destination: D,
+                                                                        regions: &[BufferCopyRegion])
+                                                                        -> InnerCommandBufferBuilder
+        where S: Into<BufferSlice<'a, [T], Ms>>, D: Into<BufferSlice<'b, [T], Md>>,
+              Ms: 'static, Md: 'static
+    {
+        let source = source.into();
+        let destination = destination.into();
+        let element_size = mem::size_of::<T>();
+
+        let copies = regions.iter().map(|region| {
+            assert!(region.len != 0);
+            let source_end = region.source_offset.checked_add(region.len);
+            assert!(source_end.map_or(false, |end| end <= source.len()));
+            let destination_end = region.destination_offset.checked_add(region.len);
+            assert!(destination_end.map_or(false, |end| end <= destination.len()));
+
+            // the regions are within the slices, so their offsets and sizes in bytes can't
+            // overflow
+            vk::BufferCopy {
+                srcOffset: (source.offset() + region.source_offset * element_size)
+                                                                        as vk::DeviceSize,
+                dstOffset: (destination.offset() + region.destination_offset * element_size)
+                                                                        as vk::DeviceSize,
+                size: (region.len * element_size) as vk::DeviceSize,
+            }
+        }).collect::<Vec<_>>();
+
+        self.copy_buffer_raw(source, destination, copies)
+    }
+
+    // Common implementation of `copy_buffer` and `copy_buffer_regions`. The offsets of the
+    // regions are relative to the start of the buffers.
+    unsafe fn copy_buffer_raw<'a, 'b, Ts: ?Sized, Td: ?Sized, Ms, Md>(mut self,
+                                                                      source: BufferSlice<'a, Ts, Ms>,
+                                                                      destination: BufferSlice<'b, Td, Md>,
+                                                                      copies: Vec<vk::BufferCopy>)
+                                                                      -> InnerCommandBufferBuilder
+    {
+        assert_eq!(source.device().internal_object(), destination.device().internal_object());
+        assert!(self.pool.queue_family().supports_transfers());
+        assert!(source.usage_transfer_src());
+        assert!(destination.usage_transfer_dest());
+
+        // the source regions and the destination regions mustn't overlap in memory
+        if source.internal_object() == destination.internal_object() {
+            for src in copies.iter() {
+                for dst in copies.iter() {
+                    assert!(src.srcOffset + src.size <= dst.dstOffset ||
+                            dst.dstOffset + dst.size <= src.srcOffset);
+                }
+            }
+        }
+
+        if copies.is_empty() {
+            return self;
+        }
+
+        self.access_buffer(source.internal_object(), TRANSFER_READ);
+        self.access_buffer(destination.internal_object(), TRANSFER_WRITE);
+        self.flush_barrier();
+
+        self.write(Command::CopyBuffer {
+            source: source.internal_object(),
+            destination: destination.internal_object(),
+            regions: copies,
+        });
+
+        self.buffer_resources.push(source.buffer().clone());
+        self.buffer_resources.push(destination.buffer().clone());
+        self
+    }
+
+    /// Copies a region of an image to another image.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the images don't belong to the same device.
+    /// - Panicks if one of the images wasn't created with the right usage.
+    /// - Panicks if one of the regions is empty or out of range of its image.
+    /// - Panicks if the extents or the number of layers of the regions are not the same.
+    /// - Panicks if the formats of the images are not compatible.
+    /// - Panicks if the images don't have the same number of samples.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn copy_image<Ts, Fs, Ms, Td, Fd, Md>(mut self, source: &Arc<Image<Ts, Fs, Ms>>,
+                                                     source_region: &ImageRegion,
+                                                     destination: &Arc<Image<Td, Fd, Md>>,
+                                                     destination_region: &ImageRegion)
+                                                     -> InnerCommandBufferBuilder
+        where Ts: ImageTypeMarker + 'static, Fs: FormatMarker + 'static,
+              Ms: MemorySourceChunk + 'static, Td: ImageTypeMarker + 'static,
+              Fd: FormatMarker + 'static, Md: MemorySourceChunk + 'static
+    {
+        assert!(self.pool.queue_family().supports_transfers());
+        self.check_image_to_image(source, source_region, destination, destination_region);
+        assert_eq!(source_region.extent, destination_region.extent);
+        assert_eq!(source.num_samples(), destination.num_samples());
+
+        {
+            let (src_format, dest_format) = (Fs::format(), Fd::format());
+            assert_eq!(src_format.size(), dest_format.size());
+            if src_format.ty() == FormatTy::Depth || src_format.ty() == FormatTy::Stencil ||
+               src_format.ty() == FormatTy::DepthStencil || dest_format.ty() == FormatTy::Depth ||
+               dest_format.ty() == FormatTy::Stencil ||
+               dest_format.ty() == FormatTy::DepthStencil
+            {
+                assert_eq!(src_format, dest_format);
+            }
+        }
+
+        let (src_layout, dest_layout) = self.access_transfer_images(source, destination);
+        self.flush_barrier();
+
+        {
+            let region = vk::ImageCopy {
+                srcSubresource: subresource_layers(Fs::format(), source_region),
+                srcOffset: offset(source_region.offset),
+                dstSubresource: subresource_layers(Fd::format(), destination_region),
+                dstOffset: offset(destination_region.offset),
+                extent: vk::Extent3D {
+                    width: source_region.extent[0],
+                    height: source_region.extent[1],
+                    depth: source_region.extent[2],
+                },
+            };
+
+            self.write(Command::CopyImage {
+                source: source.internal_object(),
+                source_layout: src_layout as u32,
+                destination: destination.internal_object(),
+                destination_layout: dest_layout as u32,
+                regions: vec![region],
+            });
+        }
+
+        self.image_resources.push(source.clone());
+        self.image_resources.push(destination.clone());
+        self
+    }
+
+    /// Copies a region of an image to another image, with scaling and format conversion.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the images don't belong to the same device.
+    /// - Panicks if one of the images wasn't created with the right usage.
+    /// - Panicks if one of the regions is empty or out of range of its image.
+    /// - Panicks if the number of layers of the regions are not the same.
+    /// - Panicks if one of the images is multisampled.
+    /// - Panicks if one of the images has a compressed format.
+    /// - Panicks if one of the images has a depth or stencil format and the formats are not
+    ///   identical, or if `filter` is `Linear`.
+    /// - Panicks if one of the images has an integer format and the other doesn't have an
+    ///   integer format of the same signedness.
+    /// - Panicks if the queue family doesn't support graphics operations.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn blit_image<Ts, Fs, Ms, Td, Fd, Md>(mut self, source: &Arc<Image<Ts, Fs, Ms>>,
+                                                     source_region: &ImageRegion,
+                                                     destination: &Arc<Image<Td, Fd, Md>>,
+                                                     destination_region: &ImageRegion,
+                                                     filter: Filter)
+                                                     -> InnerCommandBufferBuilder
+        where Ts: ImageTypeMarker + 'static, Fs: FormatMarker + 'static,
+              Ms: MemorySourceChunk + 'static, Td: ImageTypeMarker + 'static,
+              Fd: FormatMarker + 'static, Md: MemorySourceChunk + 'static
+    {
+        assert!(self.pool.queue_family().supports_graphics());
+        self.check_image_to_image(source, source_region, destination, destination_region);
+        assert_eq!(source.num_samples(), 1);
+        assert_eq!(destination.num_samples(), 1);
+
+        {
+            let (src_format, dest_format) = (Fs::format(), Fd::format());
+            assert!(src_format.ty() != FormatTy::Compressed);
+            assert!(dest_format.ty() != FormatTy::Compressed);
+
+            match (src_format.ty(), dest_format.ty()) {
+                (FormatTy::Depth, _) | (FormatTy::Stencil, _) | (FormatTy::DepthStencil, _) |
+                (_, FormatTy::Depth) | (_, FormatTy::Stencil) | (_, FormatTy::DepthStencil) => {
+                    assert_eq!(src_format, dest_format);
+                    assert_eq!(filter, Filter::Nearest);
+                },
+                (FormatTy::Uint, d) | (d, FormatTy::Uint) => assert_eq!(d, FormatTy::Uint),
+                (FormatTy::Sint, d) | (d, FormatTy::Sint) => assert_eq!(d, FormatTy::Sint),
+                _ => ()
+            }
+        }
+
+        let (src_layout, dest_layout) = self.access_transfer_images(source, destination);
+        self.flush_barrier();
+
+        {
+            let region = vk::ImageBlit {
+                srcSubresource: subresource_layers(Fs::format(), source_region),
+                srcOffsets: [offset(source_region.offset), end_offset(source_region)],
+                dstSubresource: subresource_layers(Fd::format(), destination_region),
+                dstOffsets: [offset(destination_region.offset), end_offset(destination_region)],
+            };
+
+            self.write(Command::BlitImage {
+                source: source.internal_object(),
+                source_layout: src_layout as u32,
+                destination: destination.internal_object(),
+                destination_layout: dest_layout as u32,
+                regions: vec![region],
+                filter: filter as u32,
+            });
+        }
+
+        self.image_resources.push(source.clone());
+        self.image_resources.push(destination.clone());
+        self
+    }
+
+    /// Resolves a region of a multisampled image into a non-multisampled image.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the images don't belong to the same device.
+    /// - Panicks if one of the images wasn't created with the right usage.
+    /// - Panicks if one of the regions is empty or out of range of its image.
+    /// - Panicks if the extents or the number of layers of the regions are not the same.
+    /// - Panicks if the source is not multisampled or if the destination is multisampled.
+    /// - Panicks if the images don't have the same format.
+    /// - Panicks if the queue family doesn't support graphics operations.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn resolve_image<Ts, Fs, Ms, Td, Fd, Md>(mut self, source: &Arc<Image<Ts, Fs, Ms>>,
+                                                        source_region: &ImageRegion,
+                                                        destination: &Arc<Image<Td, Fd, Md>>,
+                                                        destination_region: &ImageRegion)
+                                                        -> InnerCommandBufferBuilder
+        where Ts: ImageTypeMarker + 'static, Fs: FormatMarker + 'static,
+              Ms: MemorySourceChunk + 'static, Td: ImageTypeMarker + 'static,
+              Fd: FormatMarker + 'static, Md: MemorySourceChunk + 'static
+    {
+        assert!(self.pool.queue_family().supports_graphics());
+        self.check_image_to_image(source, source_region, destination, destination_region);
+        assert_eq!(source_region.extent, destination_region.extent);
+        assert!(source.num_samples() > 1);
+        assert_eq!(destination.num_samples(), 1);
+        assert_eq!(Fs::format(), Fd::format());
+
+        let (src_layout, dest_layout) = self.access_transfer_images(source, destination);
+        self.flush_barrier();
+
+        {
+            let region = vk::ImageResolve {
+                srcSubresource: subresource_layers(Fs::format(), source_region),
+                srcOffset: offset(source_region.offset),
+                dstSubresource: subresource_layers(Fd::format(), destination_region),
+                dstOffset: offset(destination_region.offset),
+                extent: vk::Extent3D {
+                    width: source_region.extent[0],
+                    height: source_region.extent[1],
+                    depth: source_region.extent[2],
+                },
+            };
+
+            self.write(Command::ResolveImage {
+                source: source.internal_object(),
+                source_layout: src_layout as u32,
+                destination: destination.internal_object(),
+                destination_layout: dest_layout as u32,
+                regions: vec![region],
+            });
+        }
+
+        self.image_resources.push(source.clone());
+        self.image_resources.push(destination.clone());
+        self
+    }
+
+    // Checks the rules that are common to `copy_image`, `blit_image` and `resolve_image`.
+    fn check_image_to_image<Ts, Fs, Ms, Td, Fd, Md>(&self, source: &Image<Ts, Fs, Ms>,
+                                                    source_region: &ImageRegion,
+                                                    destination: &Image<Td, Fd, Md>,
+                                                    destination_region: &ImageRegion)
+        where Ts: ImageTypeMarker, Fs: FormatMarker, Ms: MemorySourceChunk,
+              Td: ImageTypeMarker, Fd: FormatMarker, Md: MemorySourceChunk
+    {
+        assert_eq!(&**source.device() as *const _, &*self.device as *const _);
+        assert_eq!(&**destination.device() as *const _, &*self.device as *const _);
+        assert!(source.usage_transfer_src());
+        assert!(destination.usage_transfer_dest());
+        assert!(source_region.is_within(source));
+        assert!(destination_region.is_within(destination));
+        assert_eq!(source_region.num_layers, destination_region.num_layers);
+    }
+
+    // Declares the accesses of `copy_image`, `blit_image` and `resolve_image`, and returns the
+    // layouts that the source and the destination must be used with.
+    fn access_transfer_images<Ts, Fs, Ms, Td, Fd, Md>(&mut self, source: &Image<Ts, Fs, Ms>,
+                                                      destination: &Image<Td, Fd, Md>)
+                                                      -> (Layout, Layout)
+        where Ts: ImageTypeMarker, Fs: FormatMarker, Ms: MemorySourceChunk,
+              Td: ImageTypeMarker, Fd: FormatMarker, Md: MemorySourceChunk
+    {
+        if source.internal_object() == destination.internal_object() {
+            let access = Access {
+                stages: vk::PIPELINE_STAGE_TRANSFER_BIT,
+                access: vk::ACCESS_TRANSFER_READ_BIT | vk::ACCESS_TRANSFER_WRITE_BIT,
+                write: true,
+            };
+
+            self.access_image(source, Layout::General, access);
+            (Layout::General, Layout::General)
+
+        } else {
+            self.access_image(source, Layout::TransferSrcOptimal, TRANSFER_READ);
+            self.access_image(destination, Layout::TransferDstOptimal, TRANSFER_WRITE);
+            (Layout::TransferSrcOptimal, Layout::TransferDstOptimal)
+        }
+    }
+
+    /// Clears all the mipmap levels and array layers of a color image with a specific value.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the image doesn't belong to the same device.
+    /// - Panicks if the image wasn't created with the right usage.
+    /// - Panicks if the image has a depth, stencil or compressed format.
+    /// - Panicks if the clear value doesn't correspond to the format of the image.
+    /// - Panicks if the queue family doesn't support graphics or compute operations.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn clear_color_image<Ty, F, M>(mut self, image: &Arc<Image<Ty, F, M>>,
+                                              color: ClearValue) -> InnerCommandBufferBuilder
+        where Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              M: MemorySourceChunk + 'static
+    {
+        assert!(self.pool.queue_family().supports_graphics() ||
+                self.pool.queue_family().supports_compute());
+        assert_eq!(&**image.device() as *const _, &*self.device as *const _);
+        assert!(image.usage_transfer_dest());
+
+        let color = color_clear_value(F::format(), color);
+
+        self.access_image(&**image, Layout::TransferDstOptimal, TRANSFER_WRITE);
+        self.flush_barrier();
+
+        self.write(Command::ClearColorImage {
+            image: image.internal_object(),
+            layout: Layout::TransferDstOptimal as u32,
+            color: color,
+            ranges: vec![whole_subresource_range(vk::IMAGE_ASPECT_COLOR_BIT)],
+        });
+
+        self.image_resources.push(image.clone());
+        self
+    }
+
+    /// Clears all the mipmap levels and array layers of a depth and/or stencil image with a
+    /// specific value.
+    ///
+    /// If the image has both a depth and a stencil component, passing `ClearValue::Depth` or
+    /// `ClearValue::Stencil` only clears the corresponding component.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the image doesn't belong to the same device.
+    /// - Panicks if the image wasn't created with the right usage.
+    /// - Panicks if the image doesn't have a depth or stencil format.
+    /// - Panicks if the clear value doesn't correspond to the format of the image.
+    /// - Panicks if the depth value is not between 0.0 and 1.0.
+    /// - Panicks if the queue family doesn't support graphics operations.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn clear_depth_stencil_image<Ty, F, M>(mut self, image: &Arc<Image<Ty, F, M>>,
+                                                      value: ClearValue)
+                                                      -> InnerCommandBufferBuilder
+        where Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              M: MemorySourceChunk + 'static
+    {
+        assert!(self.pool.queue_family().supports_graphics());
+        assert_eq!(&**image.device() as *const _, &*self.device as *const _);
+        assert!(image.usage_transfer_dest());
+
+        let (aspect_mask, value) = depth_stencil_clear_value(F::format(), value);
+
+        self.access_image(&**image, Layout::TransferDstOptimal, TRANSFER_WRITE);
+        self.flush_barrier();
+
+        self.write(Command::ClearDepthStencilImage {
+            image: image.internal_object(),
+            layout: Layout::TransferDstOptimal as u32,
+            value: value,
+            ranges: vec![whole_subresource_range(aspect_mask)],
+        });
+
+        self.image_resources.push(image.clone());
+        self
+    }
+
+    /// Clears regions of attachments of the current subpass.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if not called inside a render pass whose attachments are known.
+    /// - Panicks if one of the attachments doesn't exist in the current subpass.
+    /// - Panicks if one of the clear values doesn't correspond to the format of its attachment.
+    /// - Panicks if one of the depth values is not between 0.0 and 1.0.
+    /// - Panicks if one of the rectangles is empty or out of range of the framebuffer.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn clear_attachments(mut self, attachments: &[ClearAttachment],
+                                    rects: &[ClearRect]) -> InnerCommandBufferBuilder
+    {
+        assert!(self.inside_render_pass);
+
+        let (attachments, rects) = {
+            let render_pass = self.render_pass.as_ref().unwrap();
+            let subpass = &render_pass.subpasses[render_pass.current_subpass];
+
+            let attachments = attachments.iter().map(|attachment| {
+                match *attachment {
+                    ClearAttachment::Color(index, value) => {
+                        let format = render_pass.format(subpass.colors[index as usize]);
+                        vk::ClearAttachment {
+                            aspectMask: vk::IMAGE_ASPECT_COLOR_BIT,
+                            colorAttachment: index,
+                            clearValue: vk::ClearValue::color(color_clear_value(format, value)),
+                        }
+                    },
+                    ClearAttachment::DepthStencil(value) => {
+                        let format = render_pass.format(subpass.depth_stencil.unwrap());
+                        let (aspect_mask, value) = depth_stencil_clear_value(format, value);
+                        vk::ClearAttachment {
+                            aspectMask: aspect_mask,
+                            colorAttachment: 0,
+                            clearValue: vk::ClearValue::depth_stencil(value),
+                        }
+                    },
+                }
+            }).collect::<Vec<_>>();
+
+            let rects = rects.iter().map(|rect| {
+                assert!(rect.extent[0] != 0 && rect.extent[1] != 0 && rect.num_layers != 0);
+
+                // the dimensions are unknown in secondary command buffers that don't specify
+                // their framebuffer
+                if let Some(dimensions) = render_pass.dimensions {
+                    assert!(rect.offset[0] + rect.extent[0] <= dimensions[0]);
+                    assert!(rect.offset[1] + rect.extent[1] <= dimensions[1]);
+                    assert!(rect.first_layer + rect.num_layers <= dimensions[2]);
+                }
+
+                vk::ClearRect {
+                    rect: vk::Rect2D {
+                        offset: vk::Offset2D {
+                            x: rect.offset[0] as i32,
+                            y: rect.offset[1] as i32,
+                        },
+                        extent: vk::Extent2D {
+                            width: rect.extent[0],
+                            height: rect.extent[1],
+                        },
+                    },
+                    baseArrayLayer: rect.first_layer,
+                    layerCount: rect.num_layers,
+                }
+            }).collect::<Vec<_>>();
+
+            (attachments, rects)
+        };
+
+        if !attachments.is_empty() && !rects.is_empty() {
+            self.write(Command::ClearAttachments { attachments: attachments, rects: rects });
+        }
+
+        self
+    }
+
+    /// Copies data from a buffer to a region of an image.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the buffer and the image don't belong to the same device.
+    /// - Panicks if the buffer or the image wasn't created with the right usage.
+    /// - Panicks if the size of the element type of the buffer is not the size of a texel of the
+    ///   image. The formats don't have to match, for example `[u8; 4]` can be used with any
+    ///   format of four 8-bit components.
+    /// - Panicks if the image has a depth-stencil or a compressed format.
+    /// - Panicks if the region is empty or out of range of the image.
+    /// - Panicks if the layout is invalid or if the buffer is too small for the region.
+    /// - Panicks if the offset of the buffer is not a multiple of 4.
+    /// - Panicks if the image is multisampled.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn copy_buffer_to_image<'a, B, T, Mb, Ty, F, Mi>(mut self, source: B,
+                                                               destination: &Arc<Image<Ty, F, Mi>>,
+                                                               layout: &BufferImageLayout,
+                                                               region: &ImageRegion)
+                                                               -> InnerCommandBufferBuilder
+        where B: Into<BufferSlice<'a, [T], Mb>>, T: Data + 'static, Mb: 'static,
+              Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              Mi: MemorySourceChunk + 'static
+    {
+        let source = source.into();
+
+        assert!(source.usage_transfer_src());
+        assert!(destination.usage_transfer_dest());
+        let copy = self.buffer_image_copy(&source, destination, layout, region);
+
+        self.access_buffer(source.internal_object(), TRANSFER_READ);
+        self.access_image(destination, Layout::TransferDstOptimal, TRANSFER_WRITE);
+        self.flush_barrier();
+
+        self.write(Command::CopyBufferToImage {
+            source: source.internal_object(),
+            destination: destination.internal_object(),
+            destination_layout: Layout::TransferDstOptimal as u32,
+            regions: vec![copy],
+        });
+
+        self.buffer_resources.push(source.buffer().clone());
+        self.image_resources.push(destination.clone());
+        self
+    }
+
+    /// Copies data from a region of an image to a buffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the buffer and the image don't belong to the same device.
+    /// - Panicks if the buffer or the image wasn't created with the right usage.
+    /// - Panicks if the size of the element type of the buffer is not the size of a texel of the
+    ///   image. The formats don't have to match, for example `[u8; 4]` can be used with any
+    ///   format of four 8-bit components.
+    /// - Panicks if the image has a depth-stencil or a compressed format.
+    /// - Panicks if the region is empty or out of range of the image.
+    /// - Panicks if the layout is invalid or if the buffer is too small for the region.
+    /// - Panicks if the offset of the buffer is not a multiple of 4.
+    /// - Panicks if the image is multisampled.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn copy_image_to_buffer<'a, B, T, Mb, Ty, F, Mi>(mut self,
+                                                               source: &Arc<Image<Ty, F, Mi>>,
+                                                               region: &ImageRegion,
+                                                               destination: B,
+                                                               layout: &BufferImageLayout)
+                                                               -> InnerCommandBufferBuilder
+        where B: Into<BufferSlice<'a, [T], Mb>>, T: Data + 'static, Mb: 'static,
+              Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              Mi: MemorySourceChunk + 'static
+    {
+        let destination = destination.into();
+
+        assert!(source.usage_transfer_src());
+        assert!(destination.usage_transfer_dest());
+        let copy = self.buffer_image_copy(&destination, source, layout, region);
+
+        self.access_image(source, Layout::TransferSrcOptimal, TRANSFER_READ);
+        self.access_buffer(destination.internal_object(), TRANSFER_WRITE);
+        self.flush_barrier();
+
+        self.write(Command::CopyImageToBuffer {
+            source: source.internal_object(),
+            source_layout: Layout::TransferSrcOptimal as u32,
+            destination: destination.internal_object(),
+            regions: vec![copy],
+        });
+
+        self.buffer_resources.push(destination.buffer().clone());
+        self.image_resources.push(source.clone());
+        self
+    }
+
+    // Checks the rules that are common to `copy_buffer_to_image` and `copy_image_to_buffer`, and
+    // builds the corresponding `vk::BufferImageCopy`.
+    fn buffer_image_copy<T, Mb, Ty, F, Mi>(&self, buffer: &BufferSlice<[T], Mb>,
+                                           image: &Image<Ty, F, Mi>,
+                                           layout: &BufferImageLayout, region: &ImageRegion)
+                                           -> vk::BufferImageCopy
+        where T: Data, Ty: ImageTypeMarker, F: FormatMarker, Mi: MemorySourceChunk
+    {
+        assert!(self.pool.queue_family().supports_transfers());
+        assert_eq!(&**image.device() as *const _, &*self.device as *const _);
+        // FIXME: check the device of the buffer
+
+        // the texels are copied as raw bytes, so the element type only needs to be in the same
+        // compatibility class as the format of the image, which means having the same size
+        assert!(F::format().ty() != FormatTy::DepthStencil);        // TODO: not supported yet
+        assert!(F::format().ty() != FormatTy::Compressed);          // TODO: not supported yet
+        assert_eq!(T::ty().size(), F::format().size());
+        assert_eq!(image.num_samples(), 1);
+        assert!(region.is_within(image));
+
+        // the offset must be a multiple of 4 and of the size of a texel
+        assert!(buffer.offset() % 4 == 0);
+        assert!(buffer.offset() % mem::size_of::<T>() == 0);
+
+        assert!(layout.row_length == 0 || layout.row_length >= region.extent[0]);
+        assert!(layout.image_height == 0 || layout.image_height >= region.extent[1]);
+        assert!(buffer.len() >= layout.required_len(region));
+
+        vk::BufferImageCopy {
+            bufferOffset: buffer.offset() as vk::DeviceSize,
+            bufferRowLength: layout.row_length,
+            bufferImageHeight: layout.image_height,
+            imageSubresource: subresource_layers(F::format(), region),
+            imageOffset: offset(region.offset),
+            imageExtent: vk::Extent3D {
+                width: region.extent[0],
+                height: region.extent[1],
+                depth: region.extent[2],
+            },
+        }
+    }
+
+    /// Calls `vkCmdDraw`.
+    ///
+    /// Returns an error if `vertices` or `instances` are out of range of the vertex buffers.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn draw<V, Pl, L>(mut self, pipeline: &Arc<GraphicsPipeline<V::Definition, Pl>>,
+                                 vertices: V, vertex_range: Range<u32>, instances: Range<u32>,
+                                 dynamic: &DynamicState, sets: L,
+                                 push_constants: &Pl::PushConstants)
+                                 -> Result<InnerCommandBufferBuilder, DrawError>
+        where V: MultiVertex, L: 'static + DescriptorSetsCollection,
+              Pl: 'static + PipelineLayoutDesc
+    {
+        if vertex_range.start > vertex_range.end ||
+           vertex_range.end as usize > vertices.num_vertices().unwrap_or(usize::MAX)
+        {
+            return Err(DrawError::VertexRangeOutOfBounds);
+        }
+
+        try!(check_instance_range(&vertices, &instances));
+
+        {
+            self.bind_gfx_pipeline_state(pipeline, dynamic, sets, push_constants);
+
+            self.bind_vertex_buffers(&vertices);
+
+            self.write(Command::Draw {
+                vertex_count: vertex_range.end - vertex_range.start,
+                instance_count: instances.end - instances.start,
+                first_vertex: vertex_range.start,
+                first_instance: instances.start,
+            });
+        }
+
+        Ok(self)
+    }
+
+    /// Calls `vkCmdDrawIndexed`.
+    ///
+    /// Returns an error if `indices_range` is out of range of the index buffer slice, or if
+    /// `instances` is out of range of the per-instance vertex buffers.
+    ///
+    /// Note that the values of the indices themselves (plus `vertex_offset`) can't be checked
+    /// against the length of the vertex buffers, as this would require reading the index buffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn draw_indexed<'a, V, Pl, L, I, Ib, IbM>(mut self, pipeline: &Arc<GraphicsPipeline<V::Definition, Pl>>,
+                                                 vertices: V, indices: Ib,
+                                                 indices_range: Range<u32>, vertex_offset: i32,
+                                                 instances: Range<u32>, dynamic: &DynamicState,
+                                                 sets: L, push_constants: &Pl::PushConstants)
+                                                 -> Result<InnerCommandBufferBuilder, DrawError>
+        where V: MultiVertex, L: 'static + DescriptorSetsCollection,
+              Pl: 'static + PipelineLayoutDesc,
+              Ib: Into<BufferSlice<'a, [I], IbM>>, I: 'static + Index, IbM: 'static
+    {
+        let indices = indices.into();
+
+        if indices_range.start > indices_range.end ||
+           indices_range.end as usize > indices.len()
+        {
+            return Err(DrawError::IndexRangeOutOfBounds);
+        }
+
+        try!(check_instance_range(&vertices, &instances));
+
+        {
+            self.bind_gfx_pipeline_state(pipeline, dynamic, sets, push_constants);
+
+            self.bind_vertex_buffers(&vertices);
+            self.bind_index_buffer(&indices);
+
+            self.write(Command::DrawIndexed {
+                index_count: indices_range.end - indices_range.start,
+                instance_count: instances.end - instances.start,
+                first_index: indices_range.start,
+                vertex_offset: vertex_offset,
+                first_instance: instances.start,
+            });
+        }
+
+        Ok(self)
+    }
+
+    /// Calls `vkCmdDispatch`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if one of the dimensions is superior to the device's
+    ///   `max_compute_work_group_count` limit.
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the queue family doesn't support compute operations.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn dispatch<Pl, L>(mut self, dimensions: [u32; 3],
+                                  pipeline: &Arc<ComputePipeline<Pl>>, sets: L,
+                                  push_constants: &Pl::PushConstants)
+                                  -> InnerCommandBufferBuilder
+        where L: 'static + DescriptorSetsCollection, Pl: 'static + PipelineLayoutDesc
+    {
+        assert!(self.pool.queue_family().supports_compute());
+
+        {
+            let limits = self.device.physical_device().limits().max_compute_work_group_count();
+            assert!(dimensions[0] <= limits[0]);
+            assert!(dimensions[1] <= limits[1]);
+            assert!(dimensions[2] <= limits[2]);
+        }
+
+        {
+            self.bind_compute_pipeline_state(pipeline, sets, push_constants);
+
+            self.access_descriptors(vk::PIPELINE_STAGE_COMPUTE_SHADER_BIT);
+            self.flush_barrier();
+
+            self.write(Command::Dispatch(dimensions));
+        }
+
+        self
+    }
+
+    /// Calls `vkCmdDrawIndirect`.
+    ///
+    /// One draw is performed for each element of `indirect`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the indirect buffer wasn't created with the right usage.
+    /// - Panicks if the offset of the indirect buffer slice is not a multiple of 4.
+    /// - Panicks if `indirect` contains more than one element and the `multi_draw_indirect`
+    ///   feature is not enabled.
+    /// - Panicks if `indirect` contains more elements than the `max_draw_indirect_count` limit.
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    ///
+    /// # Safety
+    ///
+    /// - The content of the indirect buffer is not checked.
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn draw_indirect<'a, V, Pl, L, B, M>(mut self, pipeline: &Arc<GraphicsPipeline<V::Definition, Pl>>,
+                                                    vertices: V, indirect: B,
+                                                    dynamic: &DynamicState, sets: L,
+                                                    push_constants: &Pl::PushConstants)
+                                                    -> InnerCommandBufferBuilder
+        where V: MultiVertex, L: 'static + DescriptorSetsCollection,
+              Pl: 'static + PipelineLayoutDesc,
+              B: Into<BufferSlice<'a, [DrawIndirectCommand], M>>, M: 'static
+    {
+        let indirect = indirect.into();
+        self.check_indirect_buffer(&indirect);
+
+        {
+            self.bind_gfx_pipeline_state(pipeline, dynamic, sets, push_constants);
+            self.bind_vertex_buffers(&vertices);
+
+            self.write(Command::DrawIndirect {
+                buffer: indirect.internal_object(),
+                offset: indirect.offset() as vk::DeviceSize,
+                draw_count: indirect.len() as u32,
+                stride: mem::size_of::<DrawIndirectCommand>() as u32,
+            });
+        }
+
+        self.buffer_resources.push(indirect.buffer().clone());
+        self
+    }
+
+    /// Calls `vkCmdDrawIndexedIndirect`.
+    ///
+    /// One draw is performed for each element of `indirect`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the indirect buffer wasn't created with the right usage.
+    /// - Panicks if the offset of the indirect buffer slice is not a multiple of 4.
+    /// - Panicks if `indirect` contains more than one element and the `multi_draw_indirect`
+    ///   feature is not enabled.
+    /// - Panicks if `indirect` contains more elements than the `max_draw_indirect_count` limit.
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    ///
+    /// # Safety
+    ///
+    /// - The content of the indirect buffer is not checked.
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn draw_indexed_indirect<'a, V, Pl, L, I, Ib, IbM, B, M>(mut self,
+                                        pipeline: &Arc<GraphicsPipeline<V::Definition, Pl>>, vertices: V,
+                                        indices: Ib, indirect: B, dynamic: &DynamicState,
+                                        sets: L, push_constants: &Pl::PushConstants)
+                                        -> InnerCommandBufferBuilder
+        where V: MultiVertex, L: 'static + DescriptorSetsCollection,
+              Pl: 'static + PipelineLayoutDesc,
+              Ib: Into<BufferSlice<'a, [I], IbM>>, I: 'static + Index, IbM: 'static,
+              B: Into<BufferSlice<'a, [DrawIndexedIndirectCommand], M>>, M: 'static
+    {
+        let indices = indices.into();
+        let indirect = indirect.into();
+        self.check_indirect_buffer(&indirect);
+
+        {
+            self.bind_gfx_pipeline_state(pipeline, dynamic, sets, push_constants);
+            self.bind_vertex_buffers(&vertices);
+            self.bind_index_buffer(&indices);
+
+            self.write(Command::DrawIndexedIndirect {
+                buffer: indirect.internal_object(),
+                offset: indirect.offset() as vk::DeviceSize,
+                draw_count: indirect.len() as u32,
+                stride: mem::size_of::<DrawIndexedIndirectCommand>() as u32,
+            });
+        }
+
+        self.buffer_resources.push(indirect.buffer().clone());
+        self
+    }
+
+    /// Calls `vkCmdDispatchIndirect`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the indirect buffer wasn't created with the right usage.
+    /// - Panicks if the offset of the indirect buffer slice is not a multiple of 4.
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the queue family doesn't support compute operations.
+    ///
+    /// # Safety
+    ///
+    /// - The content of the indirect buffer is not checked against the device's limits.
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn dispatch_indirect<'a, Pl, L, B, M>(mut self, indirect: B,
+                                                     pipeline: &Arc<ComputePipeline<Pl>>, sets: L,
+                                                     push_constants: &Pl::PushConstants)
+                                                     -> InnerCommandBufferBuilder
+        where L: 'static + DescriptorSetsCollection, Pl: 'static + PipelineLayoutDesc,
+              B: Into<BufferSlice<'a, DispatchIndirectCommand, M>>, M: 'static
+    {
+        let indirect = indirect.into();
+
+        assert!(self.pool.queue_family().supports_compute());
+        assert!(indirect.usage_indirect_buffer());
+        assert!(indirect.offset() % 4 == 0);
+
+        {
+            self.bind_compute_pipeline_state(pipeline, sets, push_constants);
+
+            self.access_buffer(indirect.internal_object(), INDIRECT_READ);
+            self.access_descriptors(vk::PIPELINE_STAGE_COMPUTE_SHADER_BIT);
+            self.flush_barrier();
+
+            self.write(Command::DispatchIndirect {
+                buffer: indirect.internal_object(),
+                offset: indirect.offset() as vk::DeviceSize,
+            });
+        }
+
+        self.buffer_resources.push(indirect.buffer().clone());
+        self
+    }
+
+    // Checks the rules that apply to the indirect buffer of `draw_indirect` and
+    // `draw_indexed_indirect`.
+    fn check_indirect_buffer<T, M>(&self, indirect: &BufferSlice<[T], M>) {
+        assert!(indirect.usage_indirect_buffer());
+        assert!(indirect.offset() % 4 == 0);
+
+        if indirect.len() > 1 {
+            assert!(self.device.enabled_features().multi_draw_indirect);
+        }
+
+        let limit = self.device.physical_device().limits().max_draw_indirect_count();
+        assert!(indirect.len() <= limit as usize);
+    }
+
+    // Binds the buffers of `vertices` to the vertex input bindings.
+    unsafe fn bind_vertex_buffers<V>(&mut self, vertices: &V) where V: MultiVertex {
+        let buffers = vertices.buffers();
+        let mut ids = Vec::with_capacity(buffers.len());
+        let mut offsets = Vec::with_capacity(buffers.len());
+
+        for (buffer, offset) in buffers {
+            ids.push(buffer.internal_object());
+            offsets.push(offset as vk::DeviceSize);
+            self.buffer_resources.push(buffer);
+        }
+
+        self.write(Command::BindVertexBuffers { first_binding: 0, buffers: ids, offsets: offsets });
+    }
+
+    // Binds `indices` as the index buffer.
+    unsafe fn bind_index_buffer<I, M>(&mut self, indices: &BufferSlice<[I], M>) where I: Index {
+        self.write(Command::BindIndexBuffer {
+            buffer: indices.internal_object(),
+            offset: indices.offset() as vk::DeviceSize,
+            index_type: I::ty() as u32,
+        });
+
+        self.buffer_resources.push(indices.buffer().clone());
+    }
+
+    fn bind_compute_pipeline_state<Pl, L>(&mut self, pipeline: &Arc<ComputePipeline<Pl>>, sets: L,
+                                          push_constants: &Pl::PushConstants)
+        where L: 'static + DescriptorSetsCollection, Pl: 'static + PipelineLayoutDesc
+    {
+        unsafe {
+            assert!(sets.is_compatible_with(pipeline.layout()));
+
+            if self.compute_pipeline != Some(pipeline.internal_object()) {
+                self.write(Command::BindPipeline {
+                    bind_point: vk::PIPELINE_BIND_POINT_COMPUTE,
+                    pipeline: pipeline.internal_object(),
+                });
+                self.pipelines.push(pipeline.clone());
+                self.compute_pipeline = Some(pipeline.internal_object());
+            }
+
+            self.bind_descriptor_sets(vk::PIPELINE_BIND_POINT_COMPUTE, pipeline.layout(), &sets);
+
+            self.push_constants(pipeline.layout(), push_constants);
+        }
+    }
+
+    fn bind_gfx_pipeline_state<V, Pl, L>(&mut self, pipeline: &Arc<GraphicsPipeline<V, Pl>>,
+                                         dynamic: &DynamicState, sets: L,
+                                         push_constants: &Pl::PushConstants)
+        where V: 'static + VertexDefinition, L: 'static + DescriptorSetsCollection,
+              Pl: 'static + PipelineLayoutDesc
+    {
+        unsafe {
+            assert!(sets.is_compatible_with(pipeline.layout()));
+
+            if self.graphics_pipeline != Some(pipeline.internal_object()) {
+                self.write(Command::BindPipeline {
+                    bind_point: vk::PIPELINE_BIND_POINT_GRAPHICS,
+                    pipeline: pipeline.internal_object(),
+                });
+                self.pipelines.push(pipeline.clone());
+                self.graphics_pipeline = Some(pipeline.internal_object());
+            }
+
+            self.bind_descriptor_sets(vk::PIPELINE_BIND_POINT_GRAPHICS, pipeline.layout(), &sets);
+
+            self.set_dynamic_state(pipeline, dynamic);
+            self.push_constants(pipeline.layout(), push_constants);
+        }
+    }
+
+    // Binds descriptor sets and their dynamic offsets to a bind point.
+    //
+    // Binding sets with a pipeline layout doesn't disturb the sets that were previously bound
+    // with a layout that is compatible for these sets, ie. that has the same push constant
+    // ranges and the same descriptor set layouts up to these sets. Therefore the leading sets
+    // that are already bound with a compatible layout and with the same dynamic offsets are
+    // skipped.
+    unsafe fn bind_descriptor_sets<L, Pl>(&mut self, bind_point: vk::PipelineBindPoint,
+                                          layout: &Arc<PipelineLayout<Pl>>, sets: &L)
+        where L: DescriptorSetsCollection
+    {
+        let list = sets.list().collect::<Vec<_>>();
+        if list.is_empty() {
+            return;
+        }
+
+        let (uniform_alignment, storage_alignment) = {
+            let limits = self.device.physical_device().limits();
+            (limits.min_uniform_buffer_offset_alignment(),
+             limits.min_storage_buffer_offset_alignment())
+        };
+
+        let mut offsets = sets.dynamic_offsets().into_iter();
+        let new_sets = list.iter().map(|set| {
+            let set_offsets = set.dynamic_descriptors().iter().map(|ty| {
+                let offset = offsets.next().expect("Not enough dynamic offsets were provided");
+                let alignment = match *ty {
+                    DescriptorType::StorageBufferDynamic => storage_alignment,
+                    _ => uniform_alignment,
+                };
+                assert!(offset as u64 % alignment == 0,
+                        "Dynamic offset {} is not a multiple of the required alignment {}",
+                        offset, alignment);
+                offset
+            }).collect::<Vec<_>>();
+
+            (set.internal_object(), set_offsets)
+        }).collect::<Vec<_>>();
+        assert!(offsets.next().is_none(), "Too many dynamic offsets were provided");
+
+        let set_layouts = layout.descriptor_set_layouts().iter().map(|l| {
+            ::VulkanObjectU64::internal_object(&**l)
+        }).collect::<Vec<_>>();
+
+        let push_constants = layout.push_constant_ranges().iter().map(|range| {
+            (range.stages.into(), range.offset, range.size)
+        }).collect::<Vec<_>>();
+
+        let layout = layout.internal_object();
+
+        let first_set = {
+            let bound = if bind_point == vk::PIPELINE_BIND_POINT_COMPUTE {
+                &self.compute_descriptor_sets
+            } else {
+                &self.graphics_descriptor_sets
+            };
+
+            match *bound {
+                Some(ref bound) if bound.push_constants == push_constants => {
+                    let compatible = bound.set_layouts.iter().zip(set_layouts.iter())
+                                                      .take_while(|&(a, b)| a == b).count();
+                    bound.sets.iter().zip(new_sets.iter()).take(compatible)
+                              .take_while(|&(a, b)| a == b).count()
+                },
+                _ => 0,
+            }
+        };
+
+        if first_set == new_sets.len() {
+            return;
+        }
+
+        self.write(Command::BindDescriptorSets {
+            bind_point: bind_point,
+            layout: layout,
+            first_set: first_set as u32,
+            sets: new_sets[first_set ..].iter().map(|&(set, _)| set).collect(),
+            dynamic_offsets: new_sets[first_set ..].iter()
+                                                  .flat_map(|&(_, ref o)| o.iter().cloned())
+                                                  .collect(),
+        });
+
+        for set in list.into_iter().skip(first_set) {
+            self.descriptor_sets.push(set);
+        }
+
+        let new_state = Some(BoundDescriptorSets {
+            set_layouts: set_layouts,
+            push_constants: push_constants,
+            sets: new_sets,
+        });
+        if bind_point == vk::PIPELINE_BIND_POINT_COMPUTE {
+            self.compute_descriptor_sets = new_state;
+        } else {
+            self.graphics_descriptor_sets = new_state;
+        }
+    }
+
+    // Sets the dynamic state of the pipeline, skipping the values that are already set.
+    //
+    // Note that binding a pipeline overwrites the states that are not dynamic in this pipeline,
+    // which is why the current value is forgotten in this situation.
+    unsafe fn set_dynamic_state<V, Pl>(&mut self, pipeline: &GraphicsPipeline<V, Pl>,
+                                       dynamic: &DynamicState)
+    {
+        if let Some(line_width) = dynamic.line_width {
+            assert!(pipeline.has_dynamic_line_width());
+            // TODO: check limits
+            if self.dynamic_state.line_width != Some(line_width) {
+                self.write(Command::SetLineWidth(line_width));
+                self.dynamic_state.line_width = Some(line_width);
+            }
+        } else {
+            assert!(!pipeline.has_dynamic_line_width());
+            self.dynamic_state.line_width = None;
+        }
+
+        if let Some(ref viewports) = dynamic.viewports {
+            assert!(pipeline.has_dynamic_viewports());
+            assert_eq!(viewports.len() as u32, pipeline.num_viewports());
+            // TODO: check limits
+            if self.dynamic_state.viewports.as_ref() != Some(viewports) {
+                let raw = viewports.iter().map(|v| v.clone().into())
+                                   .collect::<Vec<vk::Viewport>>();
+                self.write(Command::SetViewport { first_viewport: 0, viewports: raw });
+                self.dynamic_state.viewports = Some(viewports.clone());
+            }
+        } else {
+            assert!(!pipeline.has_dynamic_viewports());
+            self.dynamic_state.viewports = None;
+        }
+
+        if let Some(ref scissors) = dynamic.scissors {
+            assert!(pipeline.has_dynamic_scissors());
+            assert_eq!(scissors.len() as u32, pipeline.num_viewports());
+            if self.dynamic_state.scissors.as_ref() != Some(scissors) {
+                let raw = scissors.iter().map(|s| s.clone().into()).collect::<Vec<vk::Rect2D>>();
+                self.write(Command::SetScissor { first_scissor: 0, scissors: raw });
+                self.dynamic_state.scissors = Some(scissors.clone());
+            }
+        } else {
+            assert!(!pipeline.has_dynamic_scissors());
+            self.dynamic_state.scissors = None;
+        }
+
+        if let Some(bias) = dynamic.depth_bias {
+            assert!(pipeline.has_dynamic_depth_bias());
+            if bias.clamp != 0.0 {
+                assert!(self.device.enabled_features().depth_bias_clamp);
+            }
+            if self.dynamic_state.depth_bias != Some(bias) {
+                self.write(Command::SetDepthBias {
+                    constant_factor: bias.constant_factor,
+                    clamp: bias.clamp,
+                    slope_factor: bias.slope_factor,
+                });
+                self.dynamic_state.depth_bias = Some(bias);
+            }
+        } else {
+            assert!(!pipeline.has_dynamic_depth_bias());
+            self.dynamic_state.depth_bias = None;
+        }
+
+        if let Some(constants) = dynamic.blend_constants {
+            assert!(pipeline.has_dynamic_blend_constants());
+            if self.dynamic_state.blend_constants != Some(constants) {
+                self.write(Command::SetBlendConstants(constants));
+                self.dynamic_state.blend_constants = Some(constants);
+            }
+        } else {
+            assert!(!pipeline.has_dynamic_blend_constants());
+            self.dynamic_state.blend_constants = None;
+        }
+
+        if let Some(ref bounds) = dynamic.depth_bounds {
+            assert!(pipeline.has_dynamic_depth_bounds());
+            assert!(bounds.start >= 0.0 && bounds.start <= 1.0);
+            assert!(bounds.end >= 0.0 && bounds.end <= 1.0);
+            if self.dynamic_state.depth_bounds.as_ref() != Some(bounds) {
+                self.write(Command::SetDepthBounds { min: bounds.start, max: bounds.end });
+                self.dynamic_state.depth_bounds = Some(bounds.clone());
+            }
+        } else {
+            assert!(!pipeline.has_dynamic_depth_bounds());
+            self.dynamic_state.depth_bounds = None;
+        }
+
+        if let Some(value) = dynamic.stencil_compare_mask {
+            assert!(pipeline.has_dynamic_stencil_compare_mask());
+            if self.dynamic_state.stencil_compare_mask != Some(value) {
+                for (face_mask, value) in stencil_values(value) {
+                    self.write(Command::SetStencilCompareMask { face_mask: face_mask, value: value });
+                }
+                self.dynamic_state.stencil_compare_mask = Some(value);
+            }
+        } else {
+            assert!(!pipeline.has_dynamic_stencil_compare_mask());
+            self.dynamic_state.stencil_compare_mask = None;
+        }
+
+        if let Some(value) = dynamic.stencil_write_mask {
+            assert!(pipeline.has_dynamic_stencil_write_mask());
+            if self.dynamic_state.stencil_write_mask != Some(value) {
+                for (face_mask, value) in stencil_values(value) {
+                    self.write(Command::SetStencilWriteMask { face_mask: face_mask, value: value });
+                }
+                self.dynamic_state.stencil_write_mask = Some(value);
+            }
+        } else {
+            assert!(!pipeline.has_dynamic_stencil_write_mask());
+            self.dynamic_state.stencil_write_mask = None;
+        }
+
+        if let Some(value) = dynamic.stencil_reference {
+            assert!(pipeline.has_dynamic_stencil_reference());
+            if self.dynamic_state.stencil_reference != Some(value) {
+                for (face_mask, value) in stencil_values(value) {
+                    self.write(Command::SetStencilReference { face_mask: face_mask, value: value });
+                }
+                self.dynamic_state.stencil_reference = Some(value);
+            }
+        } else {
+            assert!(!pipeline.has_dynamic_stencil_reference());
+            self.dynamic_state.stencil_reference = None;
+        }
+    }
+
+    // Writes `push_constants` to the command buffer, for each range of the pipeline layout.
+    unsafe fn push_constants<Pl>(&mut self, layout: &PipelineLayout<Pl>,
+                                 push_constants: &Pl::PushConstants)
+        where Pl: PipelineLayoutDesc
+    {
+        let limit = self.device.physical_device().limits().max_push_constants_size() as usize;
+
+        for range in layout.push_constant_ranges() {
+            assert!(range.offset + range.size <= mem::size_of_val(push_constants));
+            assert!(range.offset + range.size <= limit);
+
+            // TODO: don't push the ranges that haven't changed
+            let data = (push_constants as *const Pl::PushConstants as *const u8)
+                                                            .offset(range.offset as isize);
+            self.write(Command::PushConstants {
+                layout: layout.internal_object(),
+                stages: range.stages.into(),
+                offset: range.offset as u32,
+                data: slice::from_raw_parts(data, range.size).to_vec(),
+            });
+        }
+    }
+
+    /// Calls `vkCmdBeginRenderPass`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the framebuffer is not compatible with the renderpass.
+    /// - Panicks if the number of clear values is not the number of attachments.
+    /// - Panicks if one of the clear values doesn't correspond to the format of its attachment
+    ///   in `renderpass`, or if an attachment that is cleared doesn't have a clear value.
+    /// - Panicks if the render area is empty or out of range of the framebuffer.
+    /// - Panicks if the render area is not aligned to the granularity of the renderpass.
+    ///
+    /// # Safety
+    ///
+    /// - Care must be taken to respect the rules about secondary command buffers.
+    ///
+    pub unsafe fn begin_renderpass<R, F>(mut self, renderpass: &Arc<RenderPass<R>>,
+                                         framebuffer: &Arc<Framebuffer<F>>,
+                                         secondary_cmd_buffers: bool, area: &RenderArea,
+                                         clear_values: R::ClearValues)
+                                         -> InnerCommandBufferBuilder
+        where R: RenderPassLayout, F: RenderPassLayout
+    {
+        assert!(framebuffer.is_compatible_with(renderpass));
+
+        {
+            let dimensions = [framebuffer.width(), framebuffer.height()];
+            let granularity = renderpass.granularity();
+
+            for i in 0 .. 2 {
+                assert!(area.extent[i] != 0);
+                let end = area.offset[i].checked_add(area.extent[i]);
+                assert!(end.map_or(false, |end| end <= dimensions[i]));
+
+                // the end of the area doesn't need to be aligned if it touches the edge of the
+                // framebuffer
+                if granularity[i] != 0 {
+                    assert!(area.offset[i] % granularity[i] == 0);
+                    assert!(area.extent[i] % granularity[i] == 0 ||
+                            end == Some(dimensions[i]));
+                }
+            }
+        }
+
+        // the load operations are those of the render pass that is begun, which may be
+        // different from the ones of the render pass of the framebuffer
+        let clear_values = {
+            let layout = renderpass.layout();
+            let values = layout.convert_clear_values(clear_values).collect::<Vec<_>>();
+            let attachments = layout.attachments();
+            assert_eq!(values.len(), attachments.len());
+
+            attachments.zip(values.into_iter()).map(|(attachment, value)| {
+                attachment_clear_value(&attachment, value)
+            }).collect::<Vec<_>>()
+        };
+
+        // FIXME: change attachment image layouts if necessary, for both initial and final
+        /*for attachment in R::attachments() {
+
+        }*/
+
+        for attachment in framebuffer.attachments() {
+            self.image_resources.push(attachment.clone());
+        }
+
+        // the render pass can access any resource through descriptor sets
+        self.full_barrier();
+        self.inside_render_pass = true;
+        self.render_pass = Some(RenderPassState::new(renderpass, 0).with_framebuffer(framebuffer));
+
+        {
+            let content = if secondary_cmd_buffers {
+                vk::SUBPASS_CONTENTS_SECONDARY_COMMAND_BUFFERS
+            } else {
+                vk::SUBPASS_CONTENTS_INLINE
+            };
+
+            self.write(Command::BeginRenderPass {
+                render_pass: renderpass.internal_object(),
+                framebuffer: framebuffer.internal_object(),
+                render_area: vk::Rect2D {
+                    offset: vk::Offset2D { x: area.offset[0] as i32, y: area.offset[1] as i32 },
+                    extent: vk::Extent2D { width: area.extent[0], height: area.extent[1] },
+                },
+                clear_values: clear_values,
+                contents: content,
+            });
+        }
+
+        self
+    }
+
+    /// # Panic
+    ///
+    /// - Panicks if a query that was started inside the render pass is still active.
+    ///
+    #[inline]
+    pub unsafe fn next_subpass(mut self, secondary_cmd_buffers: bool) -> InnerCommandBufferBuilder {
+        // queries that are started inside a render pass must end in the same subpass
+        assert!(self.active_queries.iter().all(|&(_, _, _, inside)| !inside));
+
+        if let Some(ref mut render_pass) = self.render_pass {
+            render_pass.current_subpass += 1;
+        }
+
+        {
+            let content = if secondary_cmd_buffers {
+                vk::SUBPASS_CONTENTS_SECONDARY_COMMAND_BUFFERS
+            } else {
+                vk::SUBPASS_CONTENTS_INLINE
+            };
+
+            self.write(Command::NextSubpass { contents: content });
+        }
+
+        self
+    }
+
+    /// # Panic
+    ///
+    /// - Panicks if a query that was started inside the render pass is still active.
+    ///
+    #[inline]
+    pub unsafe fn end_renderpass(mut self) -> InnerCommandBufferBuilder {
+        assert!(self.active_queries.iter().all(|&(_, _, _, inside)| !inside));
+
+        self.write(Command::EndRenderPass);
+
+        self.inside_render_pass = false;
+        self.render_pass = None;
+        self.descriptor_stages = vk::PIPELINE_STAGE_ALL_GRAPHICS_BIT;
+        self
+    }
+
+    // Declares that the next command is going to access a buffer. Adds to `pending_barrier` the
+    // barrier that is necessary, if any.
+    fn access_buffer(&mut self, buffer: vk::Buffer, access: Access) {
+        if self.inside_render_pass {
+            return;
+        }
+
+        self.declare_access(access);
+
+        match self.buffers_state.entry(buffer) {
+            Entry::Vacant(entry) => {
+                entry.insert(access);
+            },
+
+            Entry::Occupied(mut entry) => {
+                let state = entry.get_mut();
+
+                if state.write || access.write {
+                    // read-after-write, write-after-read or write-after-write
+                    let src_access = if state.write { state.access } else { 0 };
+                    unsafe {
+                        self.pending_barrier.add_raw_buffer_barrier(buffer, 0, vk::WHOLE_SIZE,
+                                                                    state.stages, src_access,
+                                                                    access.stages, access.access);
+                    }
+                    *state = access;
+
+                } else {
+                    // read-after-read doesn't need any synchronization
+                    state.stages |= access.stages;
+                    state.access |= access.access;
+                }
+            },
+        }
+    }
+
+    // Declares that the next command is going to access an image in the given layout. Adds to
+    // `pending_barrier` the barrier and the layout transition that are necessary, if any.
+    fn access_image<Ty, F, M>(&mut self, image: &Image<Ty, F, M>, layout: Layout, access: Access)
+        where Ty: ImageTypeMarker, F: FormatMarker, M: MemorySourceChunk
+    {
+        if self.inside_render_pass {
+            return;
+        }
+
+        self.declare_access(access);
+
+        match self.images_state.entry(image.internal_object()) {
+            Entry::Vacant(entry) => {
+                let default_layout = image.default_layout();
+
+                if default_layout != layout {
+                    unsafe {
+                        self.pending_barrier.add_raw_image_barrier(image.internal_object(),
+                                                                   aspect_mask(F::format()),
+                                                                   default_layout, layout, 0, 0,
+                                                                   access.stages, access.access);
+                    }
+                }
+
+                entry.insert(ImageState {
+                    access: access,
+                    layout: layout,
+                    default_layout: default_layout,
+                    aspect_mask: aspect_mask(F::format()),
+                });
+            },
+
+            Entry::Occupied(mut entry) => {
+                let state = entry.get_mut();
+
+                // note that a layout transition is a write
+                if state.access.write || access.write || state.layout != layout {
+                    let src_access = if state.access.write { state.access.access } else { 0 };
+                    unsafe {
+                        self.pending_barrier.add_raw_image_barrier(image.internal_object(),
+                                                                   state.aspect_mask,
+                                                                   state.layout, layout,
+                                                                   state.access.stages,
+                                                                   src_access, access.stages,
+                                                                   access.access);
+                    }
+                    state.access = access;
+                    state.layout = layout;
+
+                } else {
+                    state.access.stages |= access.stages;
+                    state.access.access |= access.access;
+                }
+            },
+        }
+    }
+
+    // Common code of `access_buffer` and `access_image`.
+    fn declare_access(&mut self, access: Access) {
+        self.next_accesses.0 |= access.stages;
+        self.next_accesses.1 |= access.access;
+
+        if access.write {
+            self.pending_writes.0 |= access.stages;
+            self.pending_writes.1 |= access.access;
+        }
+    }
+
+    // Declares that the next command is going to access resources through descriptor sets from
+    // the given stages. Since the content of the descriptor sets is unknown, all the images are
+    // put back in their default layout and all the previous writes are made visible.
+    fn access_descriptors(&mut self, stages: vk::PipelineStageFlags) {
+        if self.inside_render_pass {
+            return;
+        }
+
+        self.restore_default_layouts();
+
+        let src_stages = self.pending_writes.0 | self.descriptor_stages;
+        if src_stages != 0 {
+            let src_access = self.pending_writes.1 | if self.descriptor_stages != 0 {
+                vk::ACCESS_MEMORY_WRITE_BIT
+            } else {
+                0
+            };
+
+            unsafe {
+                self.pending_barrier.add_raw_memory_barrier(src_stages, src_access, stages,
+                                                            vk::ACCESS_UNIFORM_READ_BIT |
+                                                            vk::ACCESS_SHADER_READ_BIT |
+                                                            vk::ACCESS_SHADER_WRITE_BIT);
+            }
+        }
+
+        self.pending_writes = (0, 0);
+        self.descriptor_stages = stages;
+    }
+
+    // Adds to `pending_barrier` the layout transitions that put back all the images in their
+    // default layout.
+    fn restore_default_layouts(&mut self) {
+        // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
+        let mut restored = Vec::new();
+
+        for (&image, state) in self.images_state.iter() {
+            if state.layout == state.default_layout {
+                continue;
+            }
+
+            let src_access = if state.access.write { state.access.access } else { 0 };
+            unsafe {
+                self.pending_barrier.add_raw_image_barrier(image, state.aspect_mask, state.layout,
+                                                           state.default_layout,
+                                                           state.access.stages, src_access,
+                                                           vk::PIPELINE_STAGE_ALL_COMMANDS_BIT,
+                                                           vk::ACCESS_MEMORY_READ_BIT |
+                                                           vk::ACCESS_MEMORY_WRITE_BIT);
+            }
+
+            restored.push(image);
+        }
+
+        // the barriers above cover all the accesses to these images
+        for image in restored {
+            self.images_state.remove(&image);
+        }
+    }
+
+    // Adds to `pending_barrier` a barrier that waits for all the previous commands and makes all
+    // their writes visible, then forgets about the state of all resources.
+    fn full_barrier(&mut self) {
+        if self.inside_render_pass {
+            return;
+        }
+
+        self.restore_default_layouts();
+
+        if !self.buffers_state.is_empty() || !self.images_state.is_empty() ||
+           self.descriptor_stages != 0
+        {
+            unsafe {
+                self.pending_barrier.add_raw_memory_barrier(vk::PIPELINE_STAGE_ALL_COMMANDS_BIT,
+                                                            vk::ACCESS_MEMORY_WRITE_BIT,
+                                                            vk::PIPELINE_STAGE_ALL_COMMANDS_BIT,
+                                                            vk::ACCESS_MEMORY_READ_BIT |
+                                                            vk::ACCESS_MEMORY_WRITE_BIT);
+            }
+        }
+
+        self.buffers_state.clear();
+        self.images_state.clear();
+        self.pending_writes = (0, 0);
+        self.descriptor_stages = 0;
+
+        unsafe { self.flush_barrier(); }
+    }
+
+    // Writes `pending_barrier` to the command buffer. Must be called right before recording a
+    // command, after all of its accesses have been declared.
+    unsafe fn flush_barrier(&mut self) {
+        // the next command must wait for the accesses that couldn't be tracked
+        if self.descriptor_stages != 0 && self.next_accesses.0 != 0 {
+            self.pending_barrier.add_raw_memory_barrier(self.descriptor_stages,
+                                                        vk::ACCESS_MEMORY_WRITE_BIT,
+                                                        self.next_accesses.0,
+                                                        self.next_accesses.1);
+            self.descriptor_stages = 0;
+        }
+
+        self.next_accesses = (0, 0);
+
+        // the automatic barriers don't hold any resource, so they can be ignored
+        let barrier = mem::replace(&mut self.pending_barrier, PipelineBarrierBuilder::new());
+        if let (Some(command), _, _) = barrier.into_command() {
+            self.write(command);
+        }
+    }
+
+    /// Finishes building the command buffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if a query is still active.
+    ///
+    pub fn build(mut self) -> Result<InnerCommandBuffer, OomError> {
+        assert!(self.active_queries.is_empty());
+
+        // the next users of the images expect them to be in their default layout
+        if !self.inside_render_pass {
+            self.restore_default_layouts();
+            unsafe { self.flush_barrier(); }
+        }
+
+        unsafe {
+            let vk = self.device.pointers();
+
+            // writing the recorded commands, now that the whole list is known
+            let commands = self.recorded.take().map(|mut commands| {
+                commands.remove_redundant_binds();
+                commands.replay(&self.device, self.cmd.unwrap());
+                commands
+            });
+
+            let cmd = self.cmd.take().unwrap();
+            self.pool.end_recording();
+
+            // ending the commands recording
+            if let Err(err) = check_errors(vk.EndCommandBuffer(cmd)) {
+                self.pool.free_command_buffer(cmd);
+                return Err(From::from(err));
+            }
+
+            Ok(InnerCommandBuffer {
+                device: self.device.clone(),
+                pool: self.pool.clone(),
+                cmd: cmd,
+                buffer_resources: mem::replace(&mut self.buffer_resources, Vec::new()),
+                image_resources: mem::replace(&mut self.image_resources, Vec::new()),
+                pipelines: mem::replace(&mut self.pipelines, Vec::new()),
+                descriptor_sets: mem::replace(&mut self.descriptor_sets, Vec::new()),
+                events: mem::replace(&mut self.events, Vec::new()),
+                query_pools: mem::replace(&mut self.query_pools, Vec::new()),
+                secondaries: mem::replace(&mut self.secondaries, Vec::new()),
+                usage: self.usage,
+                generation: self.generation,
+                render_pass: self.render_pass.take(),
+                secondary: self.secondary,
+                commands: commands,
+                submit_lock: AtomicBool::new(false),
+                fences: Mutex::new(Vec::new()),
+            })
+        }
+    }
+}
+
+impl Drop for InnerCommandBufferBuilder {
+    #[inline]
     fn drop(&mut self) {
         if let Some(cmd) = self.cmd {
             unsafe {
                 let vk = self.device.pointers();
                 vk.EndCommandBuffer(cmd);
-                vk.FreeCommandBuffers(self.device.internal_object(), self.pool.internal_object(),
-                                      1, &cmd);
+                self.pool.end_recording();
+                self.pool.free_command_buffer(cmd);
             }
         }
     }
@@ -520,128 +2599,625 @@ pub struct InnerCommandBuffer {
     buffer_resources: Vec<Arc<BufferResource>>,
     image_resources: Vec<Arc<ImageResource>>,
     pipelines: Vec<Arc<GenericPipeline>>,
+    descriptor_sets: Vec<Arc<AbstractDescriptorSet>>,
+    events: Vec<Arc<Event>>,
+    query_pools: Vec<Arc<QueryPool>>,
+    secondaries: Vec<Arc<AbstractSecondaryCommandBuffer>>,
+    usage: Usage,
+
+    // Generation of the pool when the command buffer was recorded. If the pool has been reset
+    // since then, the command buffer can't be submitted anymore.
+    generation: u64,
+
+    // Render pass inherited by a secondary command buffer. Also used when resetting the command
+    // buffer.
+    render_pass: Option<RenderPassState>,
+    secondary: bool,
+
+    // Commands that have been written to the command buffer, if the builder was in recording
+    // mode.
+    commands: Option<CommandList>,
+
+    // Locked while the command buffer is being submitted, and forever after its submission if it
+    // was built with `Usage::OneTime`. Never locked for `Usage::SimultaneousUse`.
+    submit_lock: AtomicBool,
+
+    // Fences of the submissions of this command buffer that may not be finished yet.
+    fences: Mutex<Vec<Arc<Fence>>>,
 }
 
 impl InnerCommandBuffer {
-    /// Submits the command buffer to a queue.
+    /// Prepares the submission of the command buffer to a queue by creating the semaphores that
+    /// the submission must signal for the resources of the command buffer.
+    ///
+    /// The state of the resources is not modified, so nothing has to be undone if the
+    /// submission is aborted afterwards. The returned list must be passed to `gpu_access`.
+    ///
+    /// `check_submit` must have been called before, and must have succeeded.
+    pub fn prepare_submit(&self, queue: &Queue)
+                          -> Result<Vec<Option<Arc<Semaphore>>>, SubmitError>
+    {
+        let mut post_semaphores = Vec::with_capacity(self.buffer_resources.len() +
+                                                     self.image_resources.len());
+
+        for resource in self.buffer_resources.iter() {
+            post_semaphores.push(if resource.requires_semaphore() {
+                Some(try!(Semaphore::raw(queue.device())))
+            } else {
+                None
+            });
+        }
+
+        for resource in self.image_resources.iter() {
+            post_semaphores.push(if resource.requires_semaphore() {
+                Some(try!(Semaphore::raw(queue.device())))
+            } else {
+                None
+            });
+        }
+
+        Ok(post_semaphores)
+    }
+
+    /// Informs the resources of the command buffer that they are going to be accessed by the
+    /// GPU, and returns the semaphores that the submission must wait upon.
+    ///
+    /// `post_semaphores` must be the list returned by `prepare_submit`, and `fence` must be the
+    /// fence that is going to be signaled at the end of the submission. `mark_submitted` must be
+    /// called once the command buffer has been successfully submitted.
     ///
     /// Queues are not thread-safe, therefore we need to get a `&mut`.
     ///
-    /// # Panic
+    /// # Safety
     ///
-    /// - Panicks if the queue doesn't belong to the device this command buffer was created with.
-    /// - Panicks if the queue doesn't belong to the family the pool was created with.
+    /// The submission must be performed afterwards. If it fails, the semaphores returned by this
+    /// function must still be waited upon and the post semaphores and the fence must still be
+    /// signaled, otherwise the resources would wait forever for the end of the submission.
     ///
-    pub fn submit(&self, queue: &mut Queue) -> Result<(), OomError> {       // TODO: wrong error type
+    pub unsafe fn gpu_access(&self, queue: &mut Queue, fence: &Arc<Fence>,
+                             post_semaphores: Vec<Option<Arc<Semaphore>>>) -> Vec<Arc<Semaphore>>
+    {
         // FIXME: the whole function should be checked
-        let vk = self.device.pointers();
-
-        assert_eq!(queue.device().internal_object(), self.pool.device().internal_object());
-        assert_eq!(queue.family().id(), self.pool.queue_family().id());
-
-        // FIXME: fence shouldn't be discarded, as it could be ignored by resources and
-        //        destroyed while in use
-        let fence = if self.buffer_resources.iter().any(|r| r.requires_fence()) ||
-                       self.image_resources.iter().any(|r| r.requires_fence())
-        {
-            Some(try!(Fence::new(queue.device())))
-        } else {
-            None
-        };
+        debug_assert_eq!(post_semaphores.len(),
+                         self.buffer_resources.len() + self.image_resources.len());
 
-        let mut post_semaphores = Vec::new();
-        let mut post_semaphores_ids = Vec::new();
         let mut pre_semaphores = Vec::new();
-        let mut pre_semaphores_ids = Vec::new();
-        let mut pre_semaphores_stages = Vec::new();
-
-        // FIXME: pre-semaphores shouldn't be discarded as they could be deleted while in use
-        //        they should be included in a return value instead
-        // FIXME: same for post-semaphores
+        let mut post_semaphores = post_semaphores.into_iter();
 
         for resource in self.buffer_resources.iter() {
-            let post_semaphore = if resource.requires_semaphore() {
-                let semaphore = try!(Semaphore::new(queue.device()));
-                post_semaphores.push(semaphore.clone());
-                post_semaphores_ids.push(semaphore.internal_object());
-                Some(semaphore)
-
-            } else {
-                None
-            };
-
             // FIXME: for the moment `write` is always true ; that shouldn't be the case
             // FIXME: wrong offset and size
-            let sem = unsafe {
-                resource.gpu_access(true, 0, 18, queue, fence.clone(), post_semaphore)
-            };
+            let sem = resource.gpu_access(true, 0, 18, queue, Some(fence.clone()),
+                                          post_semaphores.next().unwrap());
 
             if let Some(s) = sem {
-                pre_semaphores_ids.push(s.internal_object());
-                pre_semaphores_stages.push(vk::PIPELINE_STAGE_TOP_OF_PIPE_BIT);     // TODO:
                 pre_semaphores.push(s);
             }
         }
 
         for resource in self.image_resources.iter() {
-            let post_semaphore = if resource.requires_semaphore() {
-                let semaphore = try!(Semaphore::new(queue.device()));
-                post_semaphores.push(semaphore.clone());
-                post_semaphores_ids.push(semaphore.internal_object());
-                Some(semaphore)
-
-            } else {
-                None
-            };
-
             // FIXME: for the moment `write` is always true ; that shouldn't be the case
-            let sem = unsafe {
-                resource.gpu_access(true, queue, fence.clone(), post_semaphore)
-            };
+            let sem = resource.gpu_access(true, queue, Some(fence.clone()),
+                                          post_semaphores.next().unwrap());
 
             if let Some(s) = sem {
-                pre_semaphores_ids.push(s.internal_object());
-                pre_semaphores_stages.push(vk::PIPELINE_STAGE_TOP_OF_PIPE_BIT);     // TODO:
                 pre_semaphores.push(s);
             }
         }
 
-        let infos = vk::SubmitInfo {
-            sType: vk::STRUCTURE_TYPE_SUBMIT_INFO,
-            pNext: ptr::null(),
-            waitSemaphoreCount: pre_semaphores_ids.len() as u32,
-            pWaitSemaphores: pre_semaphores_ids.as_ptr(),
-            pWaitDstStageMask: pre_semaphores_stages.as_ptr(),
-            commandBufferCount: 1,
-            pCommandBuffers: &self.cmd,
-            signalSemaphoreCount: post_semaphores_ids.len() as u32,
-            pSignalSemaphores: post_semaphores_ids.as_ptr(),
-        };
+        pre_semaphores
+    }
+
+    /// Checks whether the command buffer can be submitted to the given queue, and locks it so
+    /// that it can't be submitted again until the submission is finished or aborted. Must be
+    /// called before `prepare_submit`.
+    ///
+    /// Returns an error if:
+    ///
+    /// - The queue doesn't belong to the device this command buffer was created with.
+    /// - The queue doesn't belong to the family the pool was created with.
+    /// - The command buffer was built with `Usage::OneTime` and has already been submitted.
+    /// - The command buffer was built with `Usage::MultipleSubmits` and is still being executed.
+    /// - The command buffer wasn't built with `Usage::SimultaneousUse` and is already being
+    ///   submitted, either by another thread or as part of the same submission.
+    /// - The pool of the command buffer has been reset since the command buffer was built.
+    /// - One of the secondary command buffers that it executes is in one of the situations
+    ///   above.
+    ///
+    /// If an error is returned, the command buffer is not locked. Otherwise either
+    /// `mark_submitted` or `abort_submit` must be called afterwards.
+    pub fn check_submit(&self, queue: &Queue) -> Result<(), SubmitError> {
+        if queue.device().internal_object() != self.pool.device().internal_object() {
+            return Err(SubmitError::WrongDevice);
+        }
+
+        if queue.family().id() != self.pool.queue_family().id() {
+            return Err(SubmitError::WrongQueueFamily);
+        }
+
+        self.lock_submit()
+    }
+
+    // Returns an error if this command buffer or one of its secondary command buffers has been
+    // reset by its pool, or if its usage doesn't allow submitting it again yet. Otherwise locks
+    // the command buffer and its secondary command buffers.
+    fn lock_submit(&self) -> Result<(), SubmitError> {
+        if self.pool.generation() != self.generation {
+            return Err(SubmitError::PoolReset);
+        }
+
+        if self.usage != Usage::SimultaneousUse {
+            if self.submit_lock.compare_and_swap(false, true, Ordering::Acquire) {
+                return Err(SubmitError::CommandBufferInUse);
+            }
+
+            if self.usage == Usage::MultipleSubmits {
+                if let Err(err) = self.check_finished() {
+                    self.submit_lock.store(false, Ordering::Release);
+                    return Err(err);
+                }
+            }
+        }
+
+        for (num, secondary) in self.secondaries.iter().enumerate() {
+            if let Err(err) = secondary.inner().lock_submit() {
+                for secondary in self.secondaries[.. num].iter() {
+                    secondary.inner().abort_submit();
+                }
+                if self.usage != Usage::SimultaneousUse {
+                    self.submit_lock.store(false, Ordering::Release);
+                }
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns an error if a previous submission of this command buffer isn't finished.
+    fn check_finished(&self) -> Result<(), SubmitError> {
+        let mut fences = self.fences.lock().unwrap();
+
+        // forgetting the submissions that are finished
+        // TODO: use `retain` with a fallible closure instead
+        let mut finished = Vec::new();
+        for (num, fence) in fences.iter().enumerate() {
+            if try!(fence.raw_ready()) { finished.push(num); }
+        }
+        for num in finished.into_iter().rev() { fences.remove(num); }
+
+        if !fences.is_empty() {
+            return Err(SubmitError::CommandBufferInUse);
+        }
+
+        Ok(())
+    }
+
+    /// Unlocks the command buffer after a successful call to `check_submit`, if the submission
+    /// has been aborted.
+    pub fn abort_submit(&self) {
+        for secondary in self.secondaries.iter() {
+            secondary.inner().abort_submit();
+        }
+
+        if self.usage != Usage::SimultaneousUse {
+            self.submit_lock.store(false, Ordering::Release);
+        }
+    }
+
+    /// Must be called after the command buffer has been submitted. `fence` is the fence that is
+    /// signaled at the end of the submission.
+    #[inline]
+    pub fn mark_submitted(&self, fence: Arc<Fence>) {
+        // the secondary command buffers are executed as part of the same submission
+        for secondary in self.secondaries.iter() {
+            secondary.inner().mark_submitted(fence.clone());
+        }
+
+        self.pool.register_submission(fence.clone());
+        self.fences.lock().unwrap().push(fence);
+
+        // command buffers that can be submitted multiple times are unlocked now that their
+        // submission is tracked by the fence
+        if self.usage == Usage::MultipleSubmits {
+            self.submit_lock.store(false, Ordering::Release);
+        }
+    }
+
+    /// Waits until the command buffer has finished executing, then resets it and returns a
+    /// builder that records into it again.
+    ///
+    /// Returns an error if the pool wasn't created with the flag that allows resetting command
+    /// buffers individually.
+    pub fn reset(mut self, usage: Usage) -> Result<InnerCommandBufferBuilder, ResetError> {
+        if !self.pool.can_reset_command_buffers() {
+            return Err(ResetError::ResetNotAllowed);
+        }
+
+        match self.wait_until_finished() {
+            Ok(()) => (),
+            Err(FenceWaitError::OomError(err)) => return Err(ResetError::OomError(err)),
+            Err(FenceWaitError::DeviceLost) => return Err(ResetError::DeviceLost),
+            Err(FenceWaitError::Timeout) => unreachable!(),
+        }
 
         unsafe {
-            let fence = if let Some(ref fence) = fence { fence.internal_object() } else { 0 };
-            try!(check_errors(vk.QueueSubmit(queue.internal_object(), 1, &infos, fence)));
+            try!(self.pool.reset_command_buffer(self.cmd));
         }
 
-        // FIXME: the return value shouldn't be () because the command buffer
-        //        could be deleted while in use
+        // the command buffer now belongs to the builder, so it mustn't be freed by the destructor
+        let cmd = mem::replace(&mut self.cmd, 0);
+        let builder = try!(InnerCommandBufferBuilder::begin(&self.pool, cmd, self.secondary, usage,
+                                                            self.render_pass.take()));
+
+        // the new builder records its commands in a list as well
+        if self.commands.is_some() {
+            Ok(builder.record_commands())
+        } else {
+            Ok(builder)
+        }
+    }
+
+    /// Returns the commands that were recorded by the builder, or `None` if `record_commands`
+    /// wasn't called on the builder.
+    #[inline]
+    pub fn commands(&self) -> Option<&CommandList> {
+        self.commands.as_ref()
+    }
+
+    // Blocks until all the submissions of this command buffer are finished. Never returns
+    // `FenceWaitError::Timeout`.
+    fn wait_until_finished(&self) -> Result<(), FenceWaitError> {
+        let mut fences = self.fences.lock().unwrap();
 
+        for fence in fences.iter() {
+            try!(fence.wait(u64::MAX));
+        }
+
+        fences.clear();
         Ok(())
     }
+}
+
+/// Trait for secondary command buffers, so that a primary command buffer can keep alive the
+/// secondary command buffers that it executes.
+#[doc(hidden)]
+pub trait AbstractSecondaryCommandBuffer {
+    /// Returns the actual command buffer.
+    fn inner(&self) -> &InnerCommandBuffer;
+}
+
+unsafe impl VulkanObject for InnerCommandBuffer {
+    type Object = vk::CommandBuffer;
+
+    #[inline]
+    fn internal_object(&self) -> vk::CommandBuffer {
+        self.cmd
+    }
+}
 
-/*  TODO:
-    fn reset() -> InnerCommandBufferBuilder {
+unsafe impl DebugName for InnerCommandBuffer {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_COMMAND_BUFFER_EXT;
 
-    }*/
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
 }
 
 impl Drop for InnerCommandBuffer {
     #[inline]
     fn drop(&mut self) {
+        if self.cmd == 0 {
+            return;
+        }
+
+        // the command buffer can't be destroyed while it is being executed
+        match self.wait_until_finished() {
+            // if the device has been lost, the command buffer isn't being executed anymore
+            Ok(()) | Err(FenceWaitError::DeviceLost) => (),
+            // FIXME: what to do in case of an out of memory error?
+            Err(_) => (),
+        }
+
         unsafe {
-            let vk = self.device.pointers();
-            vk.FreeCommandBuffers(self.device.internal_object(), self.pool.internal_object(),
-                                  1, &self.cmd);
+            self.pool.free_command_buffer(self.cmd);
+        }
+    }
+}
+
+// Checks that `instances` is a valid range and is within the per-instance buffers of `vertices`.
+fn check_instance_range<V>(vertices: &V, instances: &Range<u32>) -> Result<(), DrawError>
+    where V: MultiVertex
+{
+    if instances.start > instances.end {
+        return Err(DrawError::InvalidInstanceRange);
+    }
+
+    if let Some(num_instances) = vertices.num_instances() {
+        if instances.end as usize > num_instances {
+            return Err(DrawError::InstanceRangeOutOfBounds);
+        }
+    }
+
+    Ok(())
+}
+
+// Returns the face masks and values to pass to one of the `vkCmdSetStencil*` commands. Only one
+// command is needed if the values of both faces are identical.
+#[inline]
+fn stencil_values(value: DynamicStencilValue) -> Vec<(vk::StencilFaceFlags, u32)> {
+    if value.front == value.back {
+        vec![(vk::STENCIL_FRONT_AND_BACK, value.front)]
+    } else {
+        vec![(vk::STENCIL_FACE_FRONT_BIT, value.front), (vk::STENCIL_FACE_BACK_BIT, value.back)]
+    }
+}
+
+// Describes how a command accesses a buffer or an image.
+#[derive(Copy, Clone)]
+struct Access {
+    stages: vk::PipelineStageFlags,
+    access: vk::AccessFlags,
+    write: bool,
+}
+
+const TRANSFER_READ: Access = Access {
+    stages: vk::PIPELINE_STAGE_TRANSFER_BIT,
+    access: vk::ACCESS_TRANSFER_READ_BIT,
+    write: false,
+};
+
+const TRANSFER_WRITE: Access = Access {
+    stages: vk::PIPELINE_STAGE_TRANSFER_BIT,
+    access: vk::ACCESS_TRANSFER_WRITE_BIT,
+    write: true,
+};
+
+const INDIRECT_READ: Access = Access {
+    stages: vk::PIPELINE_STAGE_DRAW_INDIRECT_BIT,
+    access: vk::ACCESS_INDIRECT_COMMAND_READ_BIT,
+    write: false,
+};
+
+// State of an image within a command buffer.
+struct ImageState {
+    // Accesses since the last barrier that covers this image.
+    access: Access,
+    // Layout the image is currently in.
+    layout: Layout,
+    // Layout the image must be put back in.
+    default_layout: Layout,
+    aspect_mask: vk::ImageAspectFlags,
+}
+
+// Descriptor sets binded to a bind point.
+struct BoundDescriptorSets {
+    // Descriptor set layouts of the pipeline layout that was used when binding the sets.
+    set_layouts: Vec<u64>,
+    // Stages, offset and size of the push constant ranges of the pipeline layout that was used
+    // when binding the sets.
+    push_constants: Vec<(vk::ShaderStageFlags, usize, usize)>,
+    // Binded sets, starting from set 0, with their dynamic offsets.
+    sets: Vec<(vk::DescriptorSet, Vec<u32>)>,
+}
+
+/// Information about a render pass that is being recorded, or that is inherited by a secondary
+/// command buffer.
+#[derive(Clone)]
+pub struct RenderPassState {
+    renderpass: vk::RenderPass,
+    // Formats and number of samples of the attachments, used to check compatibility.
+    attachments: Vec<(Format, u32)>,
+    // Attachments that are used by each subpass.
+    subpasses: Vec<SubpassAttachments>,
+    // Source subpass, destination subpass and by-region flag of each dependency.
+    dependencies: Vec<(usize, usize, bool)>,
+    current_subpass: usize,
+    framebuffer: Option<vk::Framebuffer>,
+    // Width, height and number of layers of the framebuffer, if it is known.
+    dimensions: Option<[u32; 3]>,
+}
+
+impl RenderPassState {
+    /// Builds the state of the given subpass of a render pass, without any framebuffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the subpass doesn't exist.
+    ///
+    pub fn new<R>(renderpass: &RenderPass<R>, subpass: u32) -> RenderPassState
+        where R: RenderPassLayout
+    {
+        let layout = renderpass.layout();
+        let attachments = layout.attachments().map(|a| (a.format, a.samples)).collect::<Vec<_>>();
+
+        let subpasses = layout.passes().map(|pass| {
+            SubpassAttachments {
+                colors: pass.color_attachments.iter().map(|&(id, _)| id).collect(),
+                depth_stencil: pass.depth_stencil.map(|(id, _)| id),
+                inputs: pass.input_attachments.iter().map(|&(id, _)| id).collect(),
+                resolves: pass.resolve_attachments.iter().map(|&(id, _)| id).collect(),
+                preserves: pass.preserve_attachments.clone(),
+            }
+        }).collect::<Vec<_>>();
+
+        let dependencies = layout.pass_dependencies().map(|dependency| {
+            (dependency.source_subpass, dependency.destination_subpass, dependency.by_region)
+        }).collect();
+
+        assert!((subpass as usize) < subpasses.len());
+
+        RenderPassState {
+            renderpass: renderpass.internal_object(),
+            attachments: attachments,
+            subpasses: subpasses,
+            dependencies: dependencies,
+            current_subpass: subpass as usize,
+            framebuffer: None,
+            dimensions: None,
+        }
+    }
+
+    /// Specifies the framebuffer that is used with the render pass.
+    #[inline]
+    pub fn with_framebuffer<F>(mut self, framebuffer: &Framebuffer<F>) -> RenderPassState {
+        self.framebuffer = Some(framebuffer.internal_object());
+        self.dimensions = Some([framebuffer.width(), framebuffer.height(), framebuffer.layers()]);
+        self
+    }
+
+    // Returns the format of the attachment with the given index.
+    #[inline]
+    fn format(&self, attachment: usize) -> Format {
+        self.attachments[attachment].0
+    }
+
+    // Returns true if a secondary command buffer that inherits this state can be executed
+    // while `current` is being recorded.
+    fn can_be_executed_in(&self, current: &RenderPassState) -> bool {
+        if self.current_subpass != current.current_subpass {
+            return false;
+        }
+
+        match (self.framebuffer, current.framebuffer) {
+            (Some(a), Some(b)) if a != b => return false,
+            _ => ()
+        }
+
+        self.renderpass == current.renderpass || self.is_compatible_with(current)
+    }
+
+    // Returns true if the two render passes are compatible. According to the specifications,
+    // the attachment references of the subpasses must point to attachments that have the same
+    // format and number of samples, and the render passes must be otherwise identical except
+    // for the layouts and the load and store operations.
+    fn is_compatible_with(&self, other: &RenderPassState) -> bool {
+        if self.attachments != other.attachments || self.dependencies != other.dependencies ||
+           self.subpasses.len() != other.subpasses.len()
+        {
+            return false;
         }
+
+        let refs_compatible = |a: &[usize], b: &[usize]| {
+            a.len() == b.len() &&
+            a.iter().zip(b.iter()).all(|(&a, &b)| self.attachments[a] == other.attachments[b])
+        };
+
+        // the resolve attachments are ignored if the render passes only have one subpass
+        let check_resolves = self.subpasses.len() != 1;
+
+        self.subpasses.iter().zip(other.subpasses.iter()).all(|(a, b)| {
+            let depth_compatible = match (a.depth_stencil, b.depth_stencil) {
+                (Some(a), Some(b)) => self.attachments[a] == other.attachments[b],
+                (None, None) => true,
+                _ => false,
+            };
+
+            depth_compatible && refs_compatible(&a.colors, &b.colors) &&
+            refs_compatible(&a.inputs, &b.inputs) && a.preserves == b.preserves &&
+            (!check_resolves || refs_compatible(&a.resolves, &b.resolves))
+        })
+    }
+}
+
+// Indices of the attachments that are used by a subpass.
+#[derive(Clone)]
+struct SubpassAttachments {
+    colors: Vec<usize>,
+    depth_stencil: Option<usize>,
+    inputs: Vec<usize>,
+    resolves: Vec<usize>,
+    preserves: Vec<usize>,
+}
+
+// Converts a `ClearValue` for an image or an attachment of the given color format.
+//
+// Panicks if the value doesn't correspond to the format.
+fn color_clear_value(format: Format, value: ClearValue) -> vk::ClearColorValue {
+    match (format.ty(), value) {
+        (FormatTy::Float, ClearValue::Float(data)) => vk::ClearColorValue::float32(data),
+        (FormatTy::Uint, ClearValue::Uint(data)) => vk::ClearColorValue::uint32(data),
+        (FormatTy::Sint, ClearValue::Int(data)) => vk::ClearColorValue::int32(data),
+        _ => panic!("The clear value doesn't correspond to the format {:?}", format),
+    }
+}
+
+// Converts a `ClearValue` for an image or an attachment of the given depth and/or stencil
+// format. Returns the aspects to clear.
+//
+// Panicks if the value doesn't correspond to the format, or if the depth is out of range.
+fn depth_stencil_clear_value(format: Format, value: ClearValue)
+                             -> (vk::ImageAspectFlags, vk::ClearDepthStencilValue)
+{
+    let (aspect_mask, depth, stencil) = match (format.ty(), value) {
+        (FormatTy::Depth, ClearValue::Depth(d)) |
+        (FormatTy::DepthStencil, ClearValue::Depth(d)) => (vk::IMAGE_ASPECT_DEPTH_BIT, d, 0),
+        (FormatTy::Stencil, ClearValue::Stencil(s)) |
+        (FormatTy::DepthStencil, ClearValue::Stencil(s)) => (vk::IMAGE_ASPECT_STENCIL_BIT, 0.0, s),
+        (FormatTy::DepthStencil, ClearValue::DepthStencil((d, s))) => {
+            (vk::IMAGE_ASPECT_DEPTH_BIT | vk::IMAGE_ASPECT_STENCIL_BIT, d, s)
+        },
+        _ => panic!("The clear value doesn't correspond to the format {:?}", format),
+    };
+
+    assert!(depth >= 0.0 && depth <= 1.0);
+    (aspect_mask, vk::ClearDepthStencilValue { depth: depth, stencil: stencil })
+}
+
+// Converts the clear value of an attachment of a render pass.
+//
+// Panicks if the value doesn't correspond to the format of the attachment, or if the attachment
+// is cleared and the value is `ClearValue::None`.
+fn attachment_clear_value(attachment: &AttachmentDescription, value: ClearValue)
+                          -> vk::ClearValue
+{
+    if value == ClearValue::None {
+        assert!(attachment.load != LoadOp::Clear);
+        return vk::ClearValue::color(vk::ClearColorValue::uint32([0, 0, 0, 0]));
+    }
+
+    match attachment.format.ty() {
+        FormatTy::Depth | FormatTy::Stencil | FormatTy::DepthStencil => {
+            let (_, value) = depth_stencil_clear_value(attachment.format, value);
+            vk::ClearValue::depth_stencil(value)
+        },
+        _ => vk::ClearValue::color(color_clear_value(attachment.format, value)),
+    }
+}
+
+// Returns a subresource range that covers all the mipmap levels and array layers of an image.
+fn whole_subresource_range(aspect_mask: vk::ImageAspectFlags) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange {
+        aspectMask: aspect_mask,
+        baseMipLevel: 0,
+        levelCount: vk::REMAINING_MIP_LEVELS,
+        baseArrayLayer: 0,
+        layerCount: vk::REMAINING_ARRAY_LAYERS,
+    }
+}
+
+// Builds the `vk::ImageSubresourceLayers` corresponding to a region of an image of the given
+// format.
+fn subresource_layers(format: Format, region: &ImageRegion) -> vk::ImageSubresourceLayers {
+    vk::ImageSubresourceLayers {
+        aspectMask: aspect_mask(format),
+        mipLevel: region.mipmap_level,
+        baseArrayLayer: region.first_layer,
+        layerCount: region.num_layers,
+    }
+}
+
+#[inline]
+fn offset(offset: [u32; 3]) -> vk::Offset3D {
+    vk::Offset3D { x: offset[0] as i32, y: offset[1] as i32, z: offset[2] as i32 }
+}
+
+#[inline]
+fn end_offset(region: &ImageRegion) -> vk::Offset3D {
+    vk::Offset3D {
+        x: (region.offset[0] + region.extent[0]) as i32,
+        y: (region.offset[1] + region.extent[1]) as i32,
+        z: (region.offset[2] + region.extent[2]) as i32,
     }
 }
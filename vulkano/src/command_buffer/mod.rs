@@ -12,6 +12,10 @@
 //! A pool is linked to a queue family. Command buffers that are created from a certain pool can
 //! only be submitted to queues that belong to that specific family.
 //!
+//! If you record a lot of short-lived command buffers, you can create the pool with the
+//! `transient` flag, build the command buffers with `Usage::OneTime`, and either reset the
+//! command buffers individually in order to reuse them or reset the whole pool at once.
+//!
 //! # Primary and secondary command buffers.
 //!
 //! There are three types of command buffers:
@@ -32,17 +36,40 @@
 // API has several different command buffer wrappers, but they all use the same internal
 // struct. The restrictions are enforced only in the public types.
 
+pub use self::barrier::PipelineBarrierBuilder;
+pub use self::command::Command;
+pub use self::command::CommandList;
+pub use self::command::CommandListError;
+pub use self::outer::BufferCopyRegion;
+pub use self::outer::BufferImageLayout;
+pub use self::outer::ClearAttachment;
+pub use self::outer::ClearRect;
+pub use self::outer::DispatchIndirectCommand;
+pub use self::outer::DrawError;
+pub use self::outer::DrawIndexedIndirectCommand;
+pub use self::outer::DrawIndirectCommand;
 pub use self::outer::DynamicState;
+pub use self::outer::DynamicStencilValue;
+pub use self::outer::ImageRegion;
 pub use self::outer::PrimaryCommandBufferBuilder;
 pub use self::outer::PrimaryCommandBufferBuilderInlineDraw;
 pub use self::outer::PrimaryCommandBufferBuilderSecondaryDraw;
 pub use self::outer::PrimaryCommandBuffer;
+pub use self::outer::RenderArea;
 pub use self::outer::SecondaryGraphicsCommandBufferBuilder;
 pub use self::outer::SecondaryGraphicsCommandBuffer;
 pub use self::outer::SecondaryComputeCommandBufferBuilder;
 pub use self::outer::SecondaryComputeCommandBuffer;
+pub use self::outer::Usage;
 pub use self::pool::CommandBufferPool;
+pub use self::pool::ResetError;
+pub use self::submit::SubmitBuilder;
+pub use self::submit::SubmitError;
+pub use self::submit::Submission;
 
+mod barrier;
+mod command;
 mod inner;
 mod outer;
 mod pool;
+mod submit;
@@ -1,22 +1,54 @@
+use std::cmp;
+use std::error;
+use std::fmt;
+use std::ops::Range;
 use std::sync::Arc;
 
 use buffer::Buffer;
 use buffer::BufferSlice;
+use command_buffer::CommandList;
 use command_buffer::CommandBufferPool;
+use command_buffer::PipelineBarrierBuilder;
+use command_buffer::ResetError;
+use command_buffer::SubmitBuilder;
+use command_buffer::SubmitError;
+use command_buffer::Submission;
+use command_buffer::inner::AbstractSecondaryCommandBuffer;
 use command_buffer::inner::InnerCommandBufferBuilder;
 use command_buffer::inner::InnerCommandBuffer;
+use command_buffer::inner::RenderPassState;
 use descriptor_set::PipelineLayoutDesc;
 use descriptor_set::DescriptorSetsCollection;
+use device::DebugName;
+use device::Device;
 use device::Queue;
+use formats::Data;
+use formats::FormatMarker;
+use framebuffer::ClearValue;
 use framebuffer::Framebuffer;
 use framebuffer::RenderPass;
 use framebuffer::RenderPassLayout;
+use framebuffer::Subpass;
+use image::Image;
+use image::ImageTypeMarker;
 use memory::MemorySourceChunk;
+use pipeline::ComputePipeline;
 use pipeline::GraphicsPipeline;
 use pipeline::input_assembly::Index;
+use pipeline::raster::DepthBias;
 use pipeline::vertex::MultiVertex;
+use pipeline::viewport::Scissor;
+use pipeline::viewport::Viewport;
+use query::QueryPool;
+use query::QueryResultElement;
+use query::QueryResultFlags;
+use sampler::Filter;
+use sync::Event;
+use sync::PipelineStages;
 
 use OomError;
+use VulkanObject;
+use vk;
 
 /// A prototype of a primary command buffer.
 ///
@@ -37,31 +69,42 @@ pub struct PrimaryCommandBufferBuilder {
 
 impl PrimaryCommandBufferBuilder {
     /// Builds a new primary command buffer and start recording commands in it.
+    ///
+    /// The command buffer will be built with `Usage::SimultaneousUse`.
     #[inline]
     pub fn new(pool: &Arc<CommandBufferPool>)
                -> Result<PrimaryCommandBufferBuilder, OomError>
     {
-        let inner = try!(InnerCommandBufferBuilder::new(pool, false));
+        PrimaryCommandBufferBuilder::with_usage(pool, Usage::SimultaneousUse)
+    }
+
+    /// Same as `new`, but lets you choose how the command buffer is going to be used.
+    #[inline]
+    pub fn with_usage(pool: &Arc<CommandBufferPool>, usage: Usage)
+                      -> Result<PrimaryCommandBufferBuilder, OomError>
+    {
+        let inner = try!(InnerCommandBufferBuilder::new(pool, false, usage, None));
         Ok(PrimaryCommandBufferBuilder { inner: inner })
     }
 
     /// Writes data to a buffer.
     ///
     /// The data is stored inside the command buffer and written to the given buffer slice.
-    /// This function is intended to be used for small amounts of data (only 64kB is allowed). if
-    /// you want to transfer large amounts of data, use copies between buffers.
+    /// This function is intended to be used for small amounts of data (only 64kB is allowed). If
+    /// you want to transfer large amounts of data, use `upload` or copies between buffers.
     ///
     /// # Panic
     ///
     /// - Panicks if the size of `data` is not the same as the size of the buffer slice.
+    /// - Panicks if `data` is empty.
     /// - Panicks if the size of `data` is superior to 65536 bytes.
     /// - Panicks if the offset or size is not a multiple of 4.
     /// - Panicks if the buffer wasn't created with the right usage.
     /// - Panicks if the queue family doesn't support transfer operations.
     ///
     #[inline]
-    pub fn update_buffer<'a, B, T: 'a, M: 'a>(self, buffer: B, data: &T)
-                                              -> PrimaryCommandBufferBuilder
+    pub fn update_buffer<'a, B, T: ?Sized + 'a, M: 'a>(self, buffer: B, data: &T)
+                                                       -> PrimaryCommandBufferBuilder
         where B: Into<BufferSlice<'a, T, M>>
     {
         unsafe {
@@ -71,6 +114,31 @@ impl PrimaryCommandBufferBuilder {
         }
     }
 
+    /// Writes data to a buffer, whatever the amount of data.
+    ///
+    /// Small amounts of data are stored inside the command buffer, like with `update_buffer`.
+    /// Larger amounts of data are first written to a staging buffer that is allocated by this
+    /// function and kept alive by the command buffer, then copied to the given buffer slice.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the size of `data` is not the same as the size of the buffer slice.
+    /// - Panicks if `data` is empty.
+    /// - Panicks if the buffer wasn't created with the right usage.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    #[inline]
+    pub fn upload<'a, B, T: ?Sized + 'a, M: 'a>(self, buffer: B, data: &T)
+                                                -> Result<PrimaryCommandBufferBuilder, OomError>
+        where B: Into<BufferSlice<'a, T, M>>
+    {
+        unsafe {
+            Ok(PrimaryCommandBufferBuilder {
+                inner: try!(self.inner.upload(buffer, data))
+            })
+        }
+    }
+
     /// Fills a buffer with data.
     ///
     /// The data is repeated until it fills the range from `offset` to `offset + size`.
@@ -96,10 +164,22 @@ impl PrimaryCommandBufferBuilder {
         }
     }
 
-    pub fn copy_buffer<T: ?Sized + 'static, Ms, Md>(self, source: &Arc<Buffer<T, Ms>>,
-                                                    destination: &Arc<Buffer<T, Md>>)
-                                                    -> PrimaryCommandBufferBuilder
-        where Ms: MemorySourceChunk + 'static, Md: MemorySourceChunk + 'static
+    /// Copies the content of a buffer slice to another buffer slice.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the buffers don't belong to the same device.
+    /// - Panicks if one of the buffers wasn't created with the right usage.
+    /// - Panicks if the slices don't have the same size.
+    /// - Panicks if the slices are empty.
+    /// - Panicks if the slices overlap.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    #[inline]
+    pub fn copy_buffer<'a, 'b, S, D, T: ?Sized + 'static, Ms, Md>(self, source: S, destination: D)
+                                                                  -> PrimaryCommandBufferBuilder
+        where S: Into<BufferSlice<'a, T, Ms>>, D: Into<BufferSlice<'b, T, Md>>,
+              Ms: 'static, Md: 'static
     {
         unsafe {
             PrimaryCommandBufferBuilder {
@@ -108,411 +188,2236 @@ impl PrimaryCommandBufferBuilder {
         }
     }
 
-    /// Executes secondary compute command buffers within this primary command buffer.
+    /// Copies regions of a buffer slice to regions of another buffer slice.
+    ///
+    /// All the regions are copied with one command. The offsets and lengths of the regions are
+    /// in number of elements and are relative to the start of the slices. The source and the
+    /// destination can be slices of the same buffer, for example in order to compact its content,
+    /// as long as no source region overlaps a destination region.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the buffers don't belong to the same device.
+    /// - Panicks if one of the buffers wasn't created with the right usage.
+    /// - Panicks if one of the regions is empty or out of range of its slice.
+    /// - Panicks if one of the source regions overlaps one of the destination regions.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
     #[inline]
-    pub fn execute_commands<'a, I>(self, iter: I) -> PrimaryCommandBufferBuilder
-        where I: Iterator<Item = &'a SecondaryComputeCommandBuffer>
+    pub fn copy_buffer_regions<'a, 'b, S, D, T: 'static, Ms, Md>(self, source: S, destination: D,
+                                                                 regions: &[BufferCopyRegion])
+                                                                 -> PrimaryCommandBufferBuilder
+        where S: Into<BufferSlice<'a, [T], Ms>>, D: Into<BufferSlice<'b, [T], Md>>,
+              Ms: 'static, Md: 'static
     {
         unsafe {
             PrimaryCommandBufferBuilder {
-                inner: self.inner.execute_commands(iter.map(|cb| &cb.inner))
+                inner: self.inner.copy_buffer_regions(source, destination, regions),
             }
         }
     }
 
-    /// Start drawing on a framebuffer.
-    //
-    /// This function returns an object that can be used to submit draw commands on the first
-    /// subpass of the renderpass.
+    /// Copies a region of an image to another image.
     ///
     /// # Panic
     ///
-    /// - Panicks if the framebuffer is not compatible with the renderpass.
+    /// - Panicks if the images don't belong to the same device.
+    /// - Panicks if one of the images wasn't created with the right usage.
+    /// - Panicks if one of the regions is empty or out of range of its image.
+    /// - Panicks if the extents or the number of layers of the regions are not the same.
+    /// - Panicks if the formats of the images don't have the same size, or if one of them is a
+    ///   depth or stencil format and they are not identical.
+    /// - Panicks if the images don't have the same number of samples.
+    /// - Panicks if the queue family doesn't support transfer operations.
     ///
-    // FIXME: rest of the parameters (render area and clear attachment values)
     #[inline]
-    pub fn draw_inline<R, F>(self, renderpass: &Arc<RenderPass<R>>,
-                             framebuffer: &Arc<Framebuffer<F>>, clear_values: F::ClearValues)
-                             -> PrimaryCommandBufferBuilderInlineDraw
-        where F: RenderPassLayout, R: RenderPassLayout
+    pub fn copy_image<Ts, Fs, Ms, Td, Fd, Md>(self, source: &Arc<Image<Ts, Fs, Ms>>,
+                                              source_region: &ImageRegion,
+                                              destination: &Arc<Image<Td, Fd, Md>>,
+                                              destination_region: &ImageRegion) -> PrimaryCommandBufferBuilder
+        where Ts: ImageTypeMarker + 'static, Fs: FormatMarker + 'static,
+              Ms: MemorySourceChunk + 'static, Td: ImageTypeMarker + 'static,
+              Fd: FormatMarker + 'static, Md: MemorySourceChunk + 'static
     {
-        // FIXME: check for compatibility
-
-        // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
-        let clear_values = framebuffer.renderpass().layout().convert_clear_values(clear_values)
-                                      .collect::<Vec<_>>();
-
         unsafe {
-            let inner = self.inner.begin_renderpass(renderpass, framebuffer, false, &clear_values);
-
-            PrimaryCommandBufferBuilderInlineDraw {
-                inner: inner,
-                current_subpass: 0,
-                num_subpasses: 1,   // FIXME:
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.copy_image(source, source_region, destination,
+                                             destination_region),
             }
         }
     }
 
-    /// Start drawing on a framebuffer.
-    //
-    /// This function returns an object that can be used to submit secondary graphics command
-    /// buffers that will operate on the first subpass of the renderpass.
+    /// Copies a region of an image to another image, with scaling and format conversion.
     ///
     /// # Panic
     ///
-    /// - Panicks if the framebuffer is not compatible with the renderpass.
+    /// - Panicks if the images don't belong to the same device.
+    /// - Panicks if one of the images wasn't created with the right usage.
+    /// - Panicks if one of the regions is empty or out of range of its image.
+    /// - Panicks if the number of layers of the regions are not the same.
+    /// - Panicks if one of the images is multisampled.
+    /// - Panicks if one of the images has a compressed format.
+    /// - Panicks if one of the images has a depth or stencil format and the formats are not
+    ///   identical, or if `filter` is `Linear`.
+    /// - Panicks if one of the images has an integer format and the other doesn't have an
+    ///   integer format of the same signedness.
+    /// - Panicks if the queue family doesn't support graphics operations.
     ///
-    // FIXME: rest of the parameters (render area and clear attachment values)
     #[inline]
-    pub fn draw_secondary<R, F>(self, renderpass: &Arc<RenderPass<R>>,
-                                framebuffer: &Arc<Framebuffer<F>>, clear_values: F::ClearValues)
-                                -> PrimaryCommandBufferBuilderSecondaryDraw
-        where F: RenderPassLayout, R: RenderPassLayout
+    pub fn blit_image<Ts, Fs, Ms, Td, Fd, Md>(self, source: &Arc<Image<Ts, Fs, Ms>>,
+                                              source_region: &ImageRegion,
+                                              destination: &Arc<Image<Td, Fd, Md>>,
+                                              destination_region: &ImageRegion, filter: Filter)
+                                              -> PrimaryCommandBufferBuilder
+        where Ts: ImageTypeMarker + 'static, Fs: FormatMarker + 'static,
+              Ms: MemorySourceChunk + 'static, Td: ImageTypeMarker + 'static,
+              Fd: FormatMarker + 'static, Md: MemorySourceChunk + 'static
     {
-        // FIXME: check for compatibility
-
-        // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
-        let clear_values = framebuffer.renderpass().layout().convert_clear_values(clear_values)
-                                      .collect::<Vec<_>>();
-
         unsafe {
-            let inner = self.inner.begin_renderpass(renderpass, framebuffer, true, &clear_values);
-
-            PrimaryCommandBufferBuilderSecondaryDraw {
-                inner: inner,
-                current_subpass: 0,
-                num_subpasses: 1,   // FIXME:
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.blit_image(source, source_region, destination,
+                                             destination_region, filter),
             }
         }
     }
 
-    /// Finish recording commands and build the command buffer.
+    /// Resolves a region of a multisampled image into a non-multisampled image.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the images don't belong to the same device.
+    /// - Panicks if one of the images wasn't created with the right usage.
+    /// - Panicks if one of the regions is empty or out of range of its image.
+    /// - Panicks if the extents or the number of layers of the regions are not the same.
+    /// - Panicks if the source is not multisampled or if the destination is multisampled.
+    /// - Panicks if the images don't have the same format.
+    /// - Panicks if the queue family doesn't support graphics operations.
+    ///
     #[inline]
-    pub fn build(self) -> Result<Arc<PrimaryCommandBuffer>, OomError> {
-        let inner = try!(self.inner.build());
-        Ok(Arc::new(PrimaryCommandBuffer { inner: inner }))
-    }
-}
-
-/// Object that you obtain when calling `draw_inline` or `next_subpass_inline`.
-pub struct PrimaryCommandBufferBuilderInlineDraw {
-    inner: InnerCommandBufferBuilder,
-    current_subpass: u32,
-    num_subpasses: u32,
-}
-
-impl PrimaryCommandBufferBuilderInlineDraw {
-    /// Calls `vkCmdDraw`.
-    // FIXME: push constants
-    pub fn draw<V, L, Pl>(self, pipeline: &Arc<GraphicsPipeline<V, Pl>>,
-                          vertices: V, dynamic: &DynamicState, sets: L)
-                          -> PrimaryCommandBufferBuilderInlineDraw
-        where V: MultiVertex + 'static, Pl: PipelineLayoutDesc + 'static,
-              L: DescriptorSetsCollection + 'static
+    pub fn resolve_image<Ts, Fs, Ms, Td, Fd, Md>(self, source: &Arc<Image<Ts, Fs, Ms>>,
+                                                 source_region: &ImageRegion,
+                                                 destination: &Arc<Image<Td, Fd, Md>>,
+                                                 destination_region: &ImageRegion) -> PrimaryCommandBufferBuilder
+        where Ts: ImageTypeMarker + 'static, Fs: FormatMarker + 'static,
+              Ms: MemorySourceChunk + 'static, Td: ImageTypeMarker + 'static,
+              Fd: FormatMarker + 'static, Md: MemorySourceChunk + 'static
     {
         unsafe {
-            PrimaryCommandBufferBuilderInlineDraw {
-                inner: self.inner.draw(pipeline, vertices, dynamic, sets),
-                num_subpasses: self.num_subpasses,
-                current_subpass: self.current_subpass,
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.resolve_image(source, source_region, destination,
+                                                destination_region),
             }
         }
     }
 
-    /// Calls `vkCmdDrawIndexed`.
-    pub fn draw_indexed<'a, V, L, Pl, I, Ib, IbM>(self, pipeline: &Arc<GraphicsPipeline<V, Pl>>,
-                                              vertices: V, indices: Ib, dynamic: &DynamicState,
-                                              sets: L) -> PrimaryCommandBufferBuilderInlineDraw
-        where V: 'static + MultiVertex, Pl: 'static + PipelineLayoutDesc,
-              Ib: Into<BufferSlice<'a, [I], IbM>>, I: 'static + Index, IbM: 'static,
-              L: DescriptorSetsCollection + 'static
+    /// Copies data from a buffer to a region of an image.
+    ///
+    /// The element type of the buffer must correspond to the format of the image.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the buffer and the image don't belong to the same device.
+    /// - Panicks if the buffer or the image wasn't created with the right usage.
+    /// - Panicks if the size of the element type of the buffer is not the size of a texel of the
+    ///   image. The formats don't have to match, for example `[u8; 4]` can be used with any
+    ///   format of four 8-bit components.
+    /// - Panicks if the image has a depth-stencil or a compressed format.
+    /// - Panicks if the region is empty or out of range of the image.
+    /// - Panicks if the layout is invalid or if the buffer is too small for the region.
+    /// - Panicks if the offset of the buffer is not a multiple of 4.
+    /// - Panicks if the image is multisampled.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    #[inline]
+    pub fn copy_buffer_to_image<'a, B, T, Mb, Ty, F, Mi>(self, source: B,
+                                                        destination: &Arc<Image<Ty, F, Mi>>,
+                                                        layout: &BufferImageLayout,
+                                                        region: &ImageRegion) -> PrimaryCommandBufferBuilder
+        where B: Into<BufferSlice<'a, [T], Mb>>, T: Data + 'static, Mb: 'static,
+              Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              Mi: MemorySourceChunk + 'static
     {
         unsafe {
-            PrimaryCommandBufferBuilderInlineDraw {
-                inner: self.inner.draw_indexed(pipeline, vertices, indices, dynamic, sets),
-                num_subpasses: self.num_subpasses,
-                current_subpass: self.current_subpass,
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.copy_buffer_to_image(source, destination, layout, region),
             }
         }
     }
 
-    /// Switches to the next subpass of the current renderpass.
+    /// Copies data from a region of an image to a buffer.
     ///
-    /// This function is similar to `draw_inline` on the builder.
+    /// The element type of the buffer must correspond to the format of the image.
     ///
     /// # Panic
     ///
-    /// - Panicks if no more subpasses remain.
+    /// - Panicks if the buffer and the image don't belong to the same device.
+    /// - Panicks if the buffer or the image wasn't created with the right usage.
+    /// - Panicks if the size of the element type of the buffer is not the size of a texel of the
+    ///   image. The formats don't have to match, for example `[u8; 4]` can be used with any
+    ///   format of four 8-bit components.
+    /// - Panicks if the image has a depth-stencil or a compressed format.
+    /// - Panicks if the region is empty or out of range of the image.
+    /// - Panicks if the layout is invalid or if the buffer is too small for the region.
+    /// - Panicks if the offset of the buffer is not a multiple of 4.
+    /// - Panicks if the image is multisampled.
+    /// - Panicks if the queue family doesn't support transfer operations.
     ///
     #[inline]
-    pub fn next_subpass_inline(self) -> PrimaryCommandBufferBuilderInlineDraw {
-        assert!(self.current_subpass + 1 < self.num_subpasses);
-
+    pub fn copy_image_to_buffer<'a, B, T, Mb, Ty, F, Mi>(self, source: &Arc<Image<Ty, F, Mi>>,
+                                                        region: &ImageRegion, destination: B,
+                                                        layout: &BufferImageLayout) -> PrimaryCommandBufferBuilder
+        where B: Into<BufferSlice<'a, [T], Mb>>, T: Data + 'static, Mb: 'static,
+              Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              Mi: MemorySourceChunk + 'static
+    {
         unsafe {
-            let inner = self.inner.next_subpass(false);
-
-            PrimaryCommandBufferBuilderInlineDraw {
-                inner: inner,
-                num_subpasses: self.num_subpasses,
-                current_subpass: self.current_subpass + 1,
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.copy_image_to_buffer(source, region, destination, layout),
             }
         }
     }
 
-    /// Switches to the next subpass of the current renderpass.
-    ///
-    /// This function is similar to `draw_secondary` on the builder.
+    /// Clears all the mipmap levels and array layers of a color image with a specific value.
     ///
     /// # Panic
     ///
-    /// - Panicks if no more subpasses remain.
+    /// - Panicks if the image doesn't belong to the same device.
+    /// - Panicks if the image wasn't created with the right usage.
+    /// - Panicks if the image has a depth, stencil or compressed format.
+    /// - Panicks if the clear value doesn't correspond to the format of the image.
+    /// - Panicks if the queue family doesn't support graphics or compute operations.
     ///
     #[inline]
-    pub fn next_subpass_secondary(self) -> PrimaryCommandBufferBuilderSecondaryDraw {
-        assert!(self.current_subpass + 1 < self.num_subpasses);
-
+    pub fn clear_color_image<Ty, F, M>(self, image: &Arc<Image<Ty, F, M>>, color: ClearValue)
+                                       -> PrimaryCommandBufferBuilder
+        where Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              M: MemorySourceChunk + 'static
+    {
         unsafe {
-            let inner = self.inner.next_subpass(true);
-
-            PrimaryCommandBufferBuilderSecondaryDraw {
-                inner: inner,
-                num_subpasses: self.num_subpasses,
-                current_subpass: self.current_subpass + 1,
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.clear_color_image(image, color),
             }
         }
     }
 
-    /// Finish drawing this renderpass and get back the builder.
+    /// Clears all the mipmap levels and array layers of a depth and/or stencil image with a
+    /// specific value.
+    ///
+    /// If the image has both a depth and a stencil component, passing `ClearValue::Depth` or
+    /// `ClearValue::Stencil` only clears the corresponding component.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the image doesn't belong to the same device.
+    /// - Panicks if the image wasn't created with the right usage.
+    /// - Panicks if the image doesn't have a depth or stencil format.
+    /// - Panicks if the clear value doesn't correspond to the format of the image.
+    /// - Panicks if the depth value is not between 0.0 and 1.0.
+    /// - Panicks if the queue family doesn't support graphics operations.
+    ///
     #[inline]
-    pub fn draw_end(mut self) -> PrimaryCommandBufferBuilder {
+    pub fn clear_depth_stencil_image<Ty, F, M>(self, image: &Arc<Image<Ty, F, M>>,
+                                               value: ClearValue) -> PrimaryCommandBufferBuilder
+        where Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              M: MemorySourceChunk + 'static
+    {
         unsafe {
-            // skipping the remaining subpasses
-            for _ in 0 .. (self.num_subpasses - self.current_subpass - 1) {
-                self.inner = self.inner.next_subpass(false);
-            }
-
-            let inner = self.inner.end_renderpass();
-
             PrimaryCommandBufferBuilder {
-                inner: inner,
+                inner: self.inner.clear_depth_stencil_image(image, value),
             }
         }
     }
-}
-
-/// Object that you obtain when calling `draw_secondary` or `next_subpass_secondary`.
-pub struct PrimaryCommandBufferBuilderSecondaryDraw {
-    inner: InnerCommandBufferBuilder,
-    num_subpasses: u32,
-    current_subpass: u32,
-}
 
-impl PrimaryCommandBufferBuilderSecondaryDraw {
-    /// Switches to the next subpass of the current renderpass.
+    /// Executes a compute pipeline.
     ///
-    /// This function is similar to `draw_inline` on the builder.
+    /// The compute shader is invoked `dimensions[0] * dimensions[1] * dimensions[2]` times,
+    /// times the size of its local workgroup.
     ///
     /// # Panic
     ///
-    /// - Panicks if no more subpasses remain.
+    /// - Panicks if one of the dimensions is superior to the device's
+    ///   `max_compute_work_group_count` limit.
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the queue family doesn't support compute operations.
     ///
     #[inline]
-    pub fn next_subpass_inline(self) -> PrimaryCommandBufferBuilderInlineDraw {
-        assert!(self.current_subpass + 1 < self.num_subpasses);
-
+    pub fn dispatch<Pl, L>(self, dimensions: [u32; 3], pipeline: &Arc<ComputePipeline<Pl>>,
+                           sets: L, push_constants: &Pl::PushConstants)
+                           -> PrimaryCommandBufferBuilder
+        where L: 'static + DescriptorSetsCollection, Pl: 'static + PipelineLayoutDesc
+    {
         unsafe {
-            let inner = self.inner.next_subpass(false);
-
-            PrimaryCommandBufferBuilderInlineDraw {
-                inner: inner,
-                num_subpasses: self.num_subpasses,
-                current_subpass: self.current_subpass + 1,
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.dispatch(dimensions, pipeline, sets, push_constants)
             }
         }
     }
 
-    /// Switches to the next subpass of the current renderpass.
-    ///
-    /// This function is similar to `draw_secondary` on the builder.
+    /// Executes a compute pipeline, reading the number of workgroups from a buffer.
     ///
     /// # Panic
     ///
-    /// - Panicks if no more subpasses remain.
+    /// - Panicks if the indirect buffer wasn't created with the right usage.
+    /// - Panicks if the offset of the indirect buffer slice is not a multiple of 4.
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the queue family doesn't support compute operations.
+    ///
+    /// # Safety
+    ///
+    /// - The content of the indirect buffer is not checked against the device's limits.
     ///
     #[inline]
-    pub fn next_subpass_secondary(self) -> PrimaryCommandBufferBuilderSecondaryDraw {
-        assert!(self.current_subpass + 1 < self.num_subpasses);
+    pub unsafe fn dispatch_indirect<'a, Pl, L, B, M>(self, indirect: B,
+                                                     pipeline: &Arc<ComputePipeline<Pl>>, sets: L,
+                                                     push_constants: &Pl::PushConstants)
+                                                     -> PrimaryCommandBufferBuilder
+        where L: 'static + DescriptorSetsCollection, Pl: 'static + PipelineLayoutDesc,
+              B: Into<BufferSlice<'a, DispatchIndirectCommand, M>>, M: 'static
+    {
+        PrimaryCommandBufferBuilder {
+            inner: self.inner.dispatch_indirect(indirect, pipeline, sets, push_constants)
+        }
+    }
 
+    /// Adds a pipeline barrier to the command buffer.
+    ///
+    /// The barriers that are required between the commands of this command buffer are inserted
+    /// automatically. Adding a barrier manually is only needed for accesses that can't be
+    /// tracked, for example between two compute shader executions that access the same storage
+    /// buffer through descriptor sets.
+    #[inline]
+    pub fn pipeline_barrier(self, barrier: PipelineBarrierBuilder) -> PrimaryCommandBufferBuilder {
         unsafe {
-            let inner = self.inner.next_subpass(true);
-
-            PrimaryCommandBufferBuilderSecondaryDraw {
-                inner: inner,
-                num_subpasses: self.num_subpasses,
-                current_subpass: self.current_subpass + 1,
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.pipeline_barrier(barrier)
             }
         }
     }
 
-    /// Executes secondary graphics command buffers within this primary command buffer.
+    /// Signals an event once the given stages of all the previous commands are finished.
+    ///
+    /// Commands in a later command buffer can wait for the event with `wait_events`, and the
+    /// host can check its state with `Event::signaled`.
     ///
     /// # Panic
     ///
-    /// - Panicks if one of the secondary command buffers wasn't created with a compatible
-    ///   renderpass or is using the wrong subpass.
+    /// - Panicks if the event doesn't belong to the same device as the command buffer.
+    /// - Panicks if `stages` is empty.
+    ///
     #[inline]
-    pub fn execute_commands<'a, I>(mut self, iter: I) -> PrimaryCommandBufferBuilderSecondaryDraw
-        where I: Iterator<Item = &'a SecondaryGraphicsCommandBuffer>
-    {
-        // FIXME: check renderpass and subpass
-
+    pub fn set_event(self, event: &Arc<Event>, stages: PipelineStages) -> PrimaryCommandBufferBuilder {
         unsafe {
-            self.inner = self.inner.execute_commands(iter.map(|cb| &cb.inner));
-            self
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.set_event(event, stages)
+            }
         }
     }
 
-    /// Finish drawing this renderpass and get back the builder.
+    /// Unsignals an event once the given stages of all the previous commands are finished.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the event doesn't belong to the same device as the command buffer.
+    /// - Panicks if `stages` is empty.
+    ///
     #[inline]
-    pub fn draw_end(mut self) -> PrimaryCommandBufferBuilder {
+    pub fn reset_event(self, event: &Arc<Event>, stages: PipelineStages) -> PrimaryCommandBufferBuilder {
         unsafe {
-            // skipping the remaining subpasses
-            for _ in 0 .. (self.num_subpasses - self.current_subpass - 1) {
-                self.inner = self.inner.next_subpass(false);
-            }
-
-            let inner = self.inner.end_renderpass();
-
             PrimaryCommandBufferBuilder {
-                inner: inner,
+                inner: self.inner.reset_event(event, stages)
             }
         }
     }
-}
-
-/// Represents a collection of commands to be executed by the GPU.
-///
-/// A primary command buffer can contain any command.
-pub struct PrimaryCommandBuffer {
-    inner: InnerCommandBuffer,
-}
 
-impl PrimaryCommandBuffer {
-    /// Submits the command buffer to a queue so that it is executed.
+    /// Waits until all the events are signaled, either by the host or by a previous command,
+    /// then applies the barrier.
     ///
-    /// Fences and semaphores are automatically handled.
+    /// The destination stages of the barrier of the next commands are blocked until then. This
+    /// can be used to split a pipeline barrier in two parts: the work that is recorded between
+    /// `set_event` and `wait_events` can overlap with the commands before `set_event`.
     ///
     /// # Panic
     ///
-    /// - Panicks if the queue doesn't belong to the device this command buffer was created with.
-    /// - Panicks if the queue doesn't belong to the family the pool was created with.
+    /// - Panicks if `events` is empty.
+    /// - Panicks if one of the events doesn't belong to the same device as the command buffer.
+    /// - Panicks if the barrier doesn't have any source or destination stage.
+    ///
+    /// # Safety
+    ///
+    /// - The source stages of the barrier must include the stages that were passed to
+    ///   `set_event`, or `host` if the event is signaled by the host.
     ///
     #[inline]
-    pub fn submit(&self, queue: &mut Queue) -> Result<(), OomError> {       // TODO: wrong error type
-        self.inner.submit(queue)
+    pub unsafe fn wait_events(self, events: &[Arc<Event>], barrier: PipelineBarrierBuilder)
+                              -> PrimaryCommandBufferBuilder
+    {
+        PrimaryCommandBufferBuilder {
+            inner: self.inner.wait_events(events, barrier)
+        }
     }
-}
-
-/// A prototype of a secondary compute command buffer.
-pub struct SecondaryGraphicsCommandBufferBuilder {
-    inner: InnerCommandBufferBuilder,
-}
 
-impl SecondaryGraphicsCommandBufferBuilder {
-    /// Builds a new secondary command buffer and start recording commands in it.
+    /// Executes secondary compute command buffers within this primary command buffer.
+    ///
+    /// The secondary command buffers are kept alive by the primary command buffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if a query is active.
+    ///
     #[inline]
-    pub fn new(pool: &Arc<CommandBufferPool>)
-               -> Result<SecondaryGraphicsCommandBufferBuilder, OomError>
+    pub fn execute_commands<'a, I>(self, iter: I) -> PrimaryCommandBufferBuilder
+        where I: Iterator<Item = &'a Arc<SecondaryComputeCommandBuffer>>
     {
-        let inner = try!(InnerCommandBufferBuilder::new(pool, true));
+        unsafe {
+            let iter = iter.map(|cb| cb.clone() as Arc<AbstractSecondaryCommandBuffer>);
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.execute_commands(iter)
+            }
+        }
+    }
+
+    /// Start drawing on a framebuffer.
+    //
+    /// This function returns an object that can be used to submit draw commands on the first
+    /// subpass of the renderpass.
+    ///
+    /// The whole framebuffer is used as the render area.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the framebuffer is not compatible with the renderpass.
+    /// - Panicks if one of the clear values doesn't correspond to the format of its attachment
+    ///   in `renderpass`.
+    ///
+    #[inline]
+    pub fn draw_inline<R, F>(self, renderpass: &Arc<RenderPass<R>>,
+                             framebuffer: &Arc<Framebuffer<F>>, clear_values: R::ClearValues)
+                             -> PrimaryCommandBufferBuilderInlineDraw
+        where F: RenderPassLayout, R: RenderPassLayout
+    {
+        let area = RenderArea::whole(framebuffer);
+        self.draw_inline_with_area(renderpass, framebuffer, area, clear_values)
+    }
+
+    /// Same as `draw_inline`, but only the given area of the framebuffer is affected by the
+    /// render pass.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the framebuffer is not compatible with the renderpass.
+    /// - Panicks if one of the clear values doesn't correspond to the format of its attachment
+    ///   in `renderpass`.
+    /// - Panicks if the render area is empty or out of range of the framebuffer.
+    /// - Panicks if the render area is not aligned to the granularity of the renderpass.
+    ///
+    pub fn draw_inline_with_area<R, F>(self, renderpass: &Arc<RenderPass<R>>,
+                                       framebuffer: &Arc<Framebuffer<F>>, area: RenderArea,
+                                       clear_values: R::ClearValues)
+                                       -> PrimaryCommandBufferBuilderInlineDraw
+        where F: RenderPassLayout, R: RenderPassLayout
+    {
+        unsafe {
+            let inner = self.inner.begin_renderpass(renderpass, framebuffer, false, &area,
+                                                    clear_values);
+
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: inner,
+                current_subpass: 0,
+                num_subpasses: 1,   // FIXME:
+            }
+        }
+    }
+
+    /// Start drawing on a framebuffer.
+    //
+    /// This function returns an object that can be used to submit secondary graphics command
+    /// buffers that will operate on the first subpass of the renderpass.
+    ///
+    /// The whole framebuffer is used as the render area.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the framebuffer is not compatible with the renderpass.
+    /// - Panicks if one of the clear values doesn't correspond to the format of its attachment
+    ///   in `renderpass`.
+    ///
+    #[inline]
+    pub fn draw_secondary<R, F>(self, renderpass: &Arc<RenderPass<R>>,
+                                framebuffer: &Arc<Framebuffer<F>>, clear_values: R::ClearValues)
+                                -> PrimaryCommandBufferBuilderSecondaryDraw
+        where F: RenderPassLayout, R: RenderPassLayout
+    {
+        let area = RenderArea::whole(framebuffer);
+        self.draw_secondary_with_area(renderpass, framebuffer, area, clear_values)
+    }
+
+    /// Same as `draw_secondary`, but only the given area of the framebuffer is affected by the
+    /// render pass.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the framebuffer is not compatible with the renderpass.
+    /// - Panicks if one of the clear values doesn't correspond to the format of its attachment
+    ///   in `renderpass`.
+    /// - Panicks if the render area is empty or out of range of the framebuffer.
+    /// - Panicks if the render area is not aligned to the granularity of the renderpass.
+    ///
+    pub fn draw_secondary_with_area<R, F>(self, renderpass: &Arc<RenderPass<R>>,
+                                          framebuffer: &Arc<Framebuffer<F>>, area: RenderArea,
+                                          clear_values: R::ClearValues)
+                                          -> PrimaryCommandBufferBuilderSecondaryDraw
+        where F: RenderPassLayout, R: RenderPassLayout
+    {
+        unsafe {
+            let inner = self.inner.begin_renderpass(renderpass, framebuffer, true, &area,
+                                                    clear_values);
+
+            PrimaryCommandBufferBuilderSecondaryDraw {
+                inner: inner,
+                current_subpass: 0,
+                num_subpasses: 1,   // FIXME:
+            }
+        }
+    }
+
+    /// Resets the queries within `range`. Queries must be reset before they are used.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the pool doesn't belong to the same device as the command buffer.
+    /// - Panicks if the range is out of range of the pool.
+    ///
+    #[inline]
+    pub fn reset_query_pool<P>(self, pool: &Arc<P>, range: Range<u32>) -> PrimaryCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.reset_query_pool(pool, range),
+            }
+        }
+    }
+
+    /// Starts a query. The query is active until `end_query` is called.
+    ///
+    /// If `precise` is true, an occlusion query counts the exact number of samples instead of
+    /// only producing a non-zero value if any sample passed.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the pool doesn't belong to the same device as the command buffer.
+    /// - Panicks if the slot is out of range of the pool.
+    /// - Panicks if the pool is a timestamp pool.
+    /// - Panicks if `precise` is true and the pool is not an occlusion pool, or the
+    ///   `occlusion_query_precise` feature is not enabled.
+    /// - Panicks if a query of the same type is already active.
+    ///
+    #[inline]
+    pub fn begin_query<P>(self, pool: &Arc<P>, slot: u32, precise: bool) -> PrimaryCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.begin_query(pool, slot, precise),
+            }
+        }
+    }
+
+    /// Ends a query that was started with `begin_query`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the query is not active.
+    /// - Panicks if the query was started inside a render pass and this is called outside of
+    ///   it, or the other way around.
+    ///
+    #[inline]
+    pub fn end_query<P>(self, pool: &Arc<P>, slot: u32) -> PrimaryCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.end_query(pool, slot),
+            }
+        }
+    }
+
+    /// Writes a timestamp to a query once the given stage of all the previous commands is
+    /// finished.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the pool doesn't belong to the same device as the command buffer.
+    /// - Panicks if the slot is out of range of the pool.
+    /// - Panicks if the pool is not a timestamp pool.
+    /// - Panicks if `stage` doesn't contain exactly one stage.
+    ///
+    #[inline]
+    pub fn write_timestamp<P>(self, pool: &Arc<P>, slot: u32, stage: PipelineStages) -> PrimaryCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.write_timestamp(pool, slot, stage),
+            }
+        }
+    }
+
+    /// Opens a debug label that ends with `end_label`. Labels show up in debugging tools and
+    /// can be nested. Does nothing if the debug markers aren't enabled on the device.
+    #[inline]
+    pub fn begin_label(self, name: &str, color: [f32; 4]) -> PrimaryCommandBufferBuilder {
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.begin_label(name, color),
+            }
+        }
+    }
+
+    /// Closes the last label opened with `begin_label`.
+    #[inline]
+    pub fn end_label(self) -> PrimaryCommandBufferBuilder {
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.end_label(),
+            }
+        }
+    }
+
+    /// Inserts a single debug label. Does nothing if the debug markers aren't enabled on the
+    /// device.
+    #[inline]
+    pub fn insert_label(self, name: &str, color: [f32; 4]) -> PrimaryCommandBufferBuilder {
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.insert_label(name, color),
+            }
+        }
+    }
+
+    /// Copies the results of the queries within `range` to a buffer.
+    ///
+    /// Each query produces `num_values_per_query()` values. Using `u64` as the element type of
+    /// the buffer produces 64-bit results.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the pool doesn't belong to the same device as the command buffer.
+    /// - Panicks if the range is out of range of the pool.
+    /// - Panicks if the buffer doesn't have the right length.
+    /// - Panicks if the buffer wasn't created with the right usage.
+    /// - Panicks if the offset of the buffer is not a multiple of the size of `T`.
+    /// - Panicks if `flags.partial` is true and the pool is a timestamp pool.
+    ///
+    #[inline]
+    pub fn copy_query_pool_results<'a, P, B, T, M>(self, pool: &Arc<P>, range: Range<u32>,
+                                                   destination: B, flags: QueryResultFlags)
+                                                   -> PrimaryCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static,
+              B: Into<BufferSlice<'a, [T], M>>, T: QueryResultElement + 'static, M: 'static
+    {
+        unsafe {
+            PrimaryCommandBufferBuilder {
+                inner: self.inner.copy_query_pool_results(pool, range, destination, flags),
+            }
+        }
+    }
+
+    /// Switches the builder to a mode where the commands are stored in a list that can be
+    /// inspected with `commands()`, instead of being written directly to the command buffer.
+    ///
+    /// The list is written to the command buffer when `build` is called, after the binds that
+    /// don't change anything have been removed. Commands that have been added before calling
+    /// this function are not part of the list.
+    ///
+    /// Note that the builder still owns a Vulkan command buffer in this mode. Use a
+    /// `CommandList` directly if you need to build commands without a device.
+    #[inline]
+    pub fn record_commands(self) -> PrimaryCommandBufferBuilder {
+        PrimaryCommandBufferBuilder {
+            inner: self.inner.record_commands(),
+        }
+    }
+
+    /// Returns the commands that have been recorded so far, or `None` if `record_commands`
+    /// hasn't been called.
+    #[inline]
+    pub fn commands(&self) -> Option<&CommandList> {
+        self.inner.commands()
+    }
+
+    /// Finish recording commands and build the command buffer.
+    #[inline]
+    pub fn build(self) -> Result<Arc<PrimaryCommandBuffer>, OomError> {
+        let inner = try!(self.inner.build());
+        Ok(Arc::new(PrimaryCommandBuffer { inner: inner }))
+    }
+}
+
+/// Object that you obtain when calling `draw_inline` or `next_subpass_inline`.
+pub struct PrimaryCommandBufferBuilderInlineDraw {
+    inner: InnerCommandBufferBuilder,
+    current_subpass: u32,
+    num_subpasses: u32,
+}
+
+impl PrimaryCommandBufferBuilderInlineDraw {
+    /// Calls `vkCmdDraw`.
+    ///
+    /// Draws the vertices within `vertex_range`, `instances.end - instances.start` times.
+    ///
+    /// Returns an error if `vertex_range` is out of range of the per-vertex buffers, or if
+    /// `instances` is out of range of the per-instance buffers.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the dynamic state is not compatible with the pipeline.
+    ///
+    pub fn draw<V, L, Pl>(self, pipeline: &Arc<GraphicsPipeline<V::Definition, Pl>>,
+                          vertices: V, vertex_range: Range<u32>, instances: Range<u32>,
+                          dynamic: &DynamicState, sets: L, push_constants: &Pl::PushConstants)
+                          -> Result<PrimaryCommandBufferBuilderInlineDraw, DrawError>
+        where V: MultiVertex, Pl: PipelineLayoutDesc + 'static,
+              L: DescriptorSetsCollection + 'static
+    {
+        unsafe {
+            Ok(PrimaryCommandBufferBuilderInlineDraw {
+                inner: try!(self.inner.draw(pipeline, vertices, vertex_range, instances,
+                                            dynamic, sets, push_constants)),
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass,
+            })
+        }
+    }
+
+    /// Calls `vkCmdDrawIndexed`.
+    ///
+    /// Draws the indices within `indices_range`, `instances.end - instances.start` times.
+    /// `vertex_offset` is added to each index before reading from the vertex buffers.
+    ///
+    /// Returns an error if `indices_range` is out of range of the index buffer, or if
+    /// `instances` is out of range of the per-instance vertex buffers.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the dynamic state is not compatible with the pipeline.
+    ///
+    pub fn draw_indexed<'a, V, L, Pl, I, Ib, IbM>(self, pipeline: &Arc<GraphicsPipeline<V::Definition, Pl>>,
+                                              vertices: V, indices: Ib,
+                                              indices_range: Range<u32>, vertex_offset: i32,
+                                              instances: Range<u32>, dynamic: &DynamicState,
+                                              sets: L, push_constants: &Pl::PushConstants)
+                                              -> Result<PrimaryCommandBufferBuilderInlineDraw, DrawError>
+        where V: MultiVertex, Pl: 'static + PipelineLayoutDesc,
+              Ib: Into<BufferSlice<'a, [I], IbM>>, I: 'static + Index, IbM: 'static,
+              L: DescriptorSetsCollection + 'static
+    {
+        unsafe {
+            Ok(PrimaryCommandBufferBuilderInlineDraw {
+                inner: try!(self.inner.draw_indexed(pipeline, vertices, indices, indices_range,
+                                                    vertex_offset, instances, dynamic, sets,
+                                                    push_constants)),
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass,
+            })
+        }
+    }
+
+    /// Calls `vkCmdDrawIndirect`.
+    ///
+    /// One draw is performed for each element of `indirect`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the indirect buffer wasn't created with the right usage.
+    /// - Panicks if the offset of the indirect buffer slice is not a multiple of 4.
+    /// - Panicks if `indirect` contains more than one element and the `multi_draw_indirect`
+    ///   feature is not enabled.
+    /// - Panicks if `indirect` contains more elements than the `max_draw_indirect_count` limit.
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the dynamic state is not compatible with the pipeline.
+    ///
+    /// # Safety
+    ///
+    /// - The content of the indirect buffer is not checked.
+    ///
+    pub unsafe fn draw_indirect<'a, V, L, Pl, B, M>(self, pipeline: &Arc<GraphicsPipeline<V::Definition, Pl>>,
+                                                    vertices: V, indirect: B,
+                                                    dynamic: &DynamicState, sets: L,
+                                                    push_constants: &Pl::PushConstants)
+                                                    -> PrimaryCommandBufferBuilderInlineDraw
+        where V: MultiVertex, Pl: 'static + PipelineLayoutDesc,
+              L: DescriptorSetsCollection + 'static,
+              B: Into<BufferSlice<'a, [DrawIndirectCommand], M>>, M: 'static
+    {
+        PrimaryCommandBufferBuilderInlineDraw {
+            inner: self.inner.draw_indirect(pipeline, vertices, indirect, dynamic, sets,
+                                            push_constants),
+            num_subpasses: self.num_subpasses,
+            current_subpass: self.current_subpass,
+        }
+    }
+
+    /// Calls `vkCmdDrawIndexedIndirect`.
+    ///
+    /// One draw is performed for each element of `indirect`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the indirect buffer wasn't created with the right usage.
+    /// - Panicks if the offset of the indirect buffer slice is not a multiple of 4.
+    /// - Panicks if `indirect` contains more than one element and the `multi_draw_indirect`
+    ///   feature is not enabled.
+    /// - Panicks if `indirect` contains more elements than the `max_draw_indirect_count` limit.
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the dynamic state is not compatible with the pipeline.
+    ///
+    /// # Safety
+    ///
+    /// - The content of the indirect buffer is not checked.
+    ///
+    pub unsafe fn draw_indexed_indirect<'a, V, L, Pl, I, Ib, IbM, B, M>(self,
+                                        pipeline: &Arc<GraphicsPipeline<V::Definition, Pl>>, vertices: V,
+                                        indices: Ib, indirect: B, dynamic: &DynamicState,
+                                        sets: L, push_constants: &Pl::PushConstants)
+                                        -> PrimaryCommandBufferBuilderInlineDraw
+        where V: MultiVertex, Pl: 'static + PipelineLayoutDesc,
+              Ib: Into<BufferSlice<'a, [I], IbM>>, I: 'static + Index, IbM: 'static,
+              L: DescriptorSetsCollection + 'static,
+              B: Into<BufferSlice<'a, [DrawIndexedIndirectCommand], M>>, M: 'static
+    {
+        PrimaryCommandBufferBuilderInlineDraw {
+            inner: self.inner.draw_indexed_indirect(pipeline, vertices, indices, indirect,
+                                                    dynamic, sets, push_constants),
+            num_subpasses: self.num_subpasses,
+            current_subpass: self.current_subpass,
+        }
+    }
+
+    /// Clears regions of attachments of the current subpass.
+    ///
+    /// The color attachments are designated by their index in the list of color attachments of
+    /// the subpass.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if one of the attachments doesn't exist in the current subpass.
+    /// - Panicks if one of the clear values doesn't correspond to the format of its attachment.
+    /// - Panicks if one of the depth values is not between 0.0 and 1.0.
+    /// - Panicks if one of the rectangles is empty or out of range of the framebuffer.
+    ///
+    #[inline]
+    pub fn clear_attachments(self, attachments: &[ClearAttachment], rects: &[ClearRect])
+                             -> PrimaryCommandBufferBuilderInlineDraw
+    {
+        unsafe {
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: self.inner.clear_attachments(attachments, rects),
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass,
+            }
+        }
+    }
+
+    /// Starts a query. The query is active until `end_query` is called.
+    ///
+    /// If `precise` is true, an occlusion query counts the exact number of samples instead of
+    /// only producing a non-zero value if any sample passed.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the pool doesn't belong to the same device as the command buffer.
+    /// - Panicks if the slot is out of range of the pool.
+    /// - Panicks if the pool is a timestamp pool.
+    /// - Panicks if `precise` is true and the pool is not an occlusion pool, or the
+    ///   `occlusion_query_precise` feature is not enabled.
+    /// - Panicks if a query of the same type is already active.
+    ///
+    #[inline]
+    pub fn begin_query<P>(self, pool: &Arc<P>, slot: u32, precise: bool) -> PrimaryCommandBufferBuilderInlineDraw
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: self.inner.begin_query(pool, slot, precise),
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass,
+            }
+        }
+    }
+
+    /// Ends a query that was started with `begin_query`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the query is not active.
+    /// - Panicks if the query was started inside a render pass and this is called outside of
+    ///   it, or the other way around.
+    ///
+    #[inline]
+    pub fn end_query<P>(self, pool: &Arc<P>, slot: u32) -> PrimaryCommandBufferBuilderInlineDraw
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: self.inner.end_query(pool, slot),
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass,
+            }
+        }
+    }
+
+    /// Writes a timestamp to a query once the given stage of all the previous commands is
+    /// finished.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the pool doesn't belong to the same device as the command buffer.
+    /// - Panicks if the slot is out of range of the pool.
+    /// - Panicks if the pool is not a timestamp pool.
+    /// - Panicks if `stage` doesn't contain exactly one stage.
+    ///
+    #[inline]
+    pub fn write_timestamp<P>(self, pool: &Arc<P>, slot: u32, stage: PipelineStages) -> PrimaryCommandBufferBuilderInlineDraw
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: self.inner.write_timestamp(pool, slot, stage),
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass,
+            }
+        }
+    }
+
+    /// Opens a debug label that ends with `end_label`. Labels show up in debugging tools and
+    /// can be nested. Does nothing if the debug markers aren't enabled on the device.
+    #[inline]
+    pub fn begin_label(self, name: &str, color: [f32; 4]) -> PrimaryCommandBufferBuilderInlineDraw {
+        unsafe {
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: self.inner.begin_label(name, color),
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass,
+            }
+        }
+    }
+
+    /// Closes the last label opened with `begin_label`.
+    #[inline]
+    pub fn end_label(self) -> PrimaryCommandBufferBuilderInlineDraw {
+        unsafe {
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: self.inner.end_label(),
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass,
+            }
+        }
+    }
+
+    /// Inserts a single debug label. Does nothing if the debug markers aren't enabled on the
+    /// device.
+    #[inline]
+    pub fn insert_label(self, name: &str, color: [f32; 4]) -> PrimaryCommandBufferBuilderInlineDraw {
+        unsafe {
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: self.inner.insert_label(name, color),
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass,
+            }
+        }
+    }
+
+    /// Switches to the next subpass of the current renderpass.
+    ///
+    /// This function is similar to `draw_inline` on the builder.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if no more subpasses remain.
+    /// - Panicks if a query that was started in the current subpass is still active.
+    ///
+    #[inline]
+    pub fn next_subpass_inline(self) -> PrimaryCommandBufferBuilderInlineDraw {
+        assert!(self.current_subpass + 1 < self.num_subpasses);
+
+        unsafe {
+            let inner = self.inner.next_subpass(false);
+
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: inner,
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass + 1,
+            }
+        }
+    }
+
+    /// Switches to the next subpass of the current renderpass.
+    ///
+    /// This function is similar to `draw_secondary` on the builder.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if no more subpasses remain.
+    /// - Panicks if a query that was started in the current subpass is still active.
+    ///
+    #[inline]
+    pub fn next_subpass_secondary(self) -> PrimaryCommandBufferBuilderSecondaryDraw {
+        assert!(self.current_subpass + 1 < self.num_subpasses);
+
+        unsafe {
+            let inner = self.inner.next_subpass(true);
+
+            PrimaryCommandBufferBuilderSecondaryDraw {
+                inner: inner,
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass + 1,
+            }
+        }
+    }
+
+    /// Finish drawing this renderpass and get back the builder.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if a query that was started inside the render pass is still active.
+    ///
+    #[inline]
+    pub fn draw_end(mut self) -> PrimaryCommandBufferBuilder {
+        unsafe {
+            // skipping the remaining subpasses
+            for _ in 0 .. (self.num_subpasses - self.current_subpass - 1) {
+                self.inner = self.inner.next_subpass(false);
+            }
+
+            let inner = self.inner.end_renderpass();
+
+            PrimaryCommandBufferBuilder {
+                inner: inner,
+            }
+        }
+    }
+}
+
+/// Object that you obtain when calling `draw_secondary` or `next_subpass_secondary`.
+pub struct PrimaryCommandBufferBuilderSecondaryDraw {
+    inner: InnerCommandBufferBuilder,
+    num_subpasses: u32,
+    current_subpass: u32,
+}
+
+impl PrimaryCommandBufferBuilderSecondaryDraw {
+    /// Switches to the next subpass of the current renderpass.
+    ///
+    /// This function is similar to `draw_inline` on the builder.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if no more subpasses remain.
+    /// - Panicks if a query that was started in the current subpass is still active.
+    ///
+    #[inline]
+    pub fn next_subpass_inline(self) -> PrimaryCommandBufferBuilderInlineDraw {
+        assert!(self.current_subpass + 1 < self.num_subpasses);
+
+        unsafe {
+            let inner = self.inner.next_subpass(false);
+
+            PrimaryCommandBufferBuilderInlineDraw {
+                inner: inner,
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass + 1,
+            }
+        }
+    }
+
+    /// Switches to the next subpass of the current renderpass.
+    ///
+    /// This function is similar to `draw_secondary` on the builder.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if no more subpasses remain.
+    /// - Panicks if a query that was started in the current subpass is still active.
+    ///
+    #[inline]
+    pub fn next_subpass_secondary(self) -> PrimaryCommandBufferBuilderSecondaryDraw {
+        assert!(self.current_subpass + 1 < self.num_subpasses);
+
+        unsafe {
+            let inner = self.inner.next_subpass(true);
+
+            PrimaryCommandBufferBuilderSecondaryDraw {
+                inner: inner,
+                num_subpasses: self.num_subpasses,
+                current_subpass: self.current_subpass + 1,
+            }
+        }
+    }
+
+    /// Executes secondary graphics command buffers within this primary command buffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if one of the secondary command buffers wasn't created with a compatible
+    ///   renderpass or is using the wrong subpass.
+    /// - Panicks if one of the secondary command buffers was created with a different
+    ///   framebuffer.
+    /// - Panicks if a query is active.
+    ///
+    #[inline]
+    pub fn execute_commands<'a, I>(mut self, iter: I) -> PrimaryCommandBufferBuilderSecondaryDraw
+        where I: Iterator<Item = &'a Arc<SecondaryGraphicsCommandBuffer>>
+    {
+        unsafe {
+            let iter = iter.map(|cb| cb.clone() as Arc<AbstractSecondaryCommandBuffer>);
+            self.inner = self.inner.execute_commands(iter);
+            self
+        }
+    }
+
+    /// Finish drawing this renderpass and get back the builder.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if a query that was started inside the render pass is still active.
+    ///
+    #[inline]
+    pub fn draw_end(mut self) -> PrimaryCommandBufferBuilder {
+        unsafe {
+            // skipping the remaining subpasses
+            for _ in 0 .. (self.num_subpasses - self.current_subpass - 1) {
+                self.inner = self.inner.next_subpass(false);
+            }
+
+            let inner = self.inner.end_renderpass();
+
+            PrimaryCommandBufferBuilder {
+                inner: inner,
+            }
+        }
+    }
+}
+
+/// Represents a collection of commands to be executed by the GPU.
+///
+/// A primary command buffer can contain any command.
+pub struct PrimaryCommandBuffer {
+    inner: InnerCommandBuffer,
+}
+
+impl PrimaryCommandBuffer {
+    /// Submits the command buffer to a queue so that it is executed.
+    ///
+    /// Fences and semaphores are automatically handled. The returned `Submission` keeps alive
+    /// the command buffer and the synchronization objects until the end of the execution.
+    ///
+    /// This is the same as submitting a `SubmitBuilder` that only contains this command buffer.
+    ///
+    /// Returns an error if the queue doesn't belong to the device or to the queue family of the
+    /// command buffer, or if the command buffer's usage doesn't allow submitting it again yet.
+    ///
+    #[inline]
+    pub fn submit(me: &Arc<PrimaryCommandBuffer>, queue: &mut Queue)
+                  -> Result<Submission, SubmitError>
+    {
+        SubmitBuilder::new().add_command_buffer(me).submit(queue)
+    }
+
+    /// Resets the command buffer and returns a builder that records commands into it again.
+    ///
+    /// This avoids allocating a new command buffer. Blocks until all the submissions of the
+    /// command buffer are finished.
+    ///
+    /// Returns an error if the pool wasn't created with the flag that allows resetting command
+    /// buffers individually, or if the command buffer is still shared with other `Arc`s, for
+    /// example with a `Submission` that is still alive. The `Arc` is dropped in both cases.
+    ///
+    pub fn reset(me: Arc<PrimaryCommandBuffer>, usage: Usage)
+                 -> Result<PrimaryCommandBufferBuilder, ResetError>
+    {
+        let me = match Arc::try_unwrap(me) {
+            Ok(me) => me,
+            Err(_) => return Err(ResetError::CommandBufferInUse),
+        };
+
+        let inner = try!(me.inner.reset(usage));
+        Ok(PrimaryCommandBufferBuilder { inner: inner })
+    }
+
+    /// Returns the commands that were written to the command buffer, or `None` if
+    /// `record_commands` wasn't called on the builder.
+    #[inline]
+    pub fn commands(&self) -> Option<&CommandList> {
+        self.inner.commands()
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub fn inner(&self) -> &InnerCommandBuffer {
+        &self.inner
+    }
+}
+
+unsafe impl VulkanObject for PrimaryCommandBuffer {
+    type Object = vk::CommandBuffer;
+
+    #[inline]
+    fn internal_object(&self) -> vk::CommandBuffer {
+        self.inner.internal_object()
+    }
+}
+
+unsafe impl DebugName for PrimaryCommandBuffer {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_COMMAND_BUFFER_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        DebugName::device(&self.inner)
+    }
+}
+
+/// A prototype of a secondary compute command buffer.
+pub struct SecondaryGraphicsCommandBufferBuilder {
+    inner: InnerCommandBufferBuilder,
+}
+
+impl SecondaryGraphicsCommandBufferBuilder {
+    /// Builds a new secondary command buffer and start recording commands in it.
+    ///
+    /// The command buffer can only be executed inside of the given subpass, or inside of the
+    /// same subpass of a compatible renderpass.
+    ///
+    /// The command buffer will be built with `Usage::SimultaneousUse`.
+    #[inline]
+    pub fn new<R>(pool: &Arc<CommandBufferPool>, subpass: Subpass<R>)
+                  -> Result<SecondaryGraphicsCommandBufferBuilder, OomError>
+        where R: RenderPassLayout
+    {
+        SecondaryGraphicsCommandBufferBuilder::with_usage(pool, subpass, Usage::SimultaneousUse)
+    }
+
+    /// Same as `new`, but lets you choose how the command buffer is going to be used.
+    #[inline]
+    pub fn with_usage<R>(pool: &Arc<CommandBufferPool>, subpass: Subpass<R>, usage: Usage)
+                         -> Result<SecondaryGraphicsCommandBufferBuilder, OomError>
+        where R: RenderPassLayout
+    {
+        let render_pass = RenderPassState::new(subpass.renderpass(), subpass.index());
+        let inner = try!(InnerCommandBufferBuilder::new(pool, true, usage, Some(render_pass)));
+        Ok(SecondaryGraphicsCommandBufferBuilder { inner: inner })
+    }
+
+    /// Same as `with_usage`, but also specifies the framebuffer that the command buffer is
+    /// going to draw to.
+    ///
+    /// This may allow the implementation to optimize the command buffer, but the command buffer
+    /// can then only be executed while drawing to this framebuffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the framebuffer is not compatible with the renderpass of the subpass.
+    ///
+    pub fn with_framebuffer<R, F>(pool: &Arc<CommandBufferPool>, subpass: Subpass<R>,
+                                  framebuffer: &Arc<Framebuffer<F>>, usage: Usage)
+                                  -> Result<SecondaryGraphicsCommandBufferBuilder, OomError>
+        where R: RenderPassLayout, F: RenderPassLayout
+    {
+        assert!(framebuffer.renderpass().is_compatible_with(subpass.renderpass()));
+
+        let render_pass = RenderPassState::new(subpass.renderpass(), subpass.index())
+                                            .with_framebuffer(framebuffer);
+        let inner = try!(InnerCommandBufferBuilder::new(pool, true, usage, Some(render_pass)));
         Ok(SecondaryGraphicsCommandBufferBuilder { inner: inner })
     }
 
-    /// Finish recording commands and build the command buffer.
+    /// Calls `vkCmdDraw`.
+    ///
+    /// Draws the vertices within `vertex_range`, `instances.end - instances.start` times.
+    ///
+    /// Returns an error if `vertex_range` is out of range of the per-vertex buffers, or if
+    /// `instances` is out of range of the per-instance buffers.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the dynamic state is not compatible with the pipeline.
+    ///
+    pub fn draw<V, L, Pl>(self, pipeline: &Arc<GraphicsPipeline<V::Definition, Pl>>,
+                          vertices: V, vertex_range: Range<u32>, instances: Range<u32>,
+                          dynamic: &DynamicState, sets: L, push_constants: &Pl::PushConstants)
+                          -> Result<SecondaryGraphicsCommandBufferBuilder, DrawError>
+        where V: MultiVertex, Pl: PipelineLayoutDesc + 'static,
+              L: DescriptorSetsCollection + 'static
+    {
+        unsafe {
+            Ok(SecondaryGraphicsCommandBufferBuilder {
+                inner: try!(self.inner.draw(pipeline, vertices, vertex_range, instances,
+                                            dynamic, sets, push_constants)),
+            })
+        }
+    }
+
+    /// Calls `vkCmdDrawIndexed`.
+    ///
+    /// Draws the indices within `indices_range`, `instances.end - instances.start` times.
+    /// `vertex_offset` is added to each index before reading from the vertex buffers.
+    ///
+    /// Returns an error if `indices_range` is out of range of the index buffer, or if
+    /// `instances` is out of range of the per-instance vertex buffers.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the dynamic state is not compatible with the pipeline.
+    ///
+    pub fn draw_indexed<'a, V, L, Pl, I, Ib, IbM>(self, pipeline: &Arc<GraphicsPipeline<V::Definition, Pl>>,
+                                              vertices: V, indices: Ib,
+                                              indices_range: Range<u32>, vertex_offset: i32,
+                                              instances: Range<u32>, dynamic: &DynamicState,
+                                              sets: L, push_constants: &Pl::PushConstants)
+                                              -> Result<SecondaryGraphicsCommandBufferBuilder, DrawError>
+        where V: MultiVertex, Pl: 'static + PipelineLayoutDesc,
+              Ib: Into<BufferSlice<'a, [I], IbM>>, I: 'static + Index, IbM: 'static,
+              L: DescriptorSetsCollection + 'static
+    {
+        unsafe {
+            Ok(SecondaryGraphicsCommandBufferBuilder {
+                inner: try!(self.inner.draw_indexed(pipeline, vertices, indices, indices_range,
+                                                    vertex_offset, instances, dynamic, sets,
+                                                    push_constants)),
+            })
+        }
+    }
+
+    /// Calls `vkCmdDrawIndirect`.
+    ///
+    /// One draw is performed for each element of `indirect`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the indirect buffer wasn't created with the right usage.
+    /// - Panicks if the offset of the indirect buffer slice is not a multiple of 4.
+    /// - Panicks if `indirect` contains more than one element and the `multi_draw_indirect`
+    ///   feature is not enabled.
+    /// - Panicks if `indirect` contains more elements than the `max_draw_indirect_count` limit.
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the dynamic state is not compatible with the pipeline.
+    ///
+    /// # Safety
+    ///
+    /// - The content of the indirect buffer is not checked.
+    ///
+    pub unsafe fn draw_indirect<'a, V, L, Pl, B, M>(self, pipeline: &Arc<GraphicsPipeline<V::Definition, Pl>>,
+                                                    vertices: V, indirect: B,
+                                                    dynamic: &DynamicState, sets: L,
+                                                    push_constants: &Pl::PushConstants)
+                                                    -> SecondaryGraphicsCommandBufferBuilder
+        where V: MultiVertex, Pl: 'static + PipelineLayoutDesc,
+              L: DescriptorSetsCollection + 'static,
+              B: Into<BufferSlice<'a, [DrawIndirectCommand], M>>, M: 'static
+    {
+        SecondaryGraphicsCommandBufferBuilder {
+            inner: self.inner.draw_indirect(pipeline, vertices, indirect, dynamic, sets,
+                                            push_constants),
+        }
+    }
+
+    /// Calls `vkCmdDrawIndexedIndirect`.
+    ///
+    /// One draw is performed for each element of `indirect`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the indirect buffer wasn't created with the right usage.
+    /// - Panicks if the offset of the indirect buffer slice is not a multiple of 4.
+    /// - Panicks if `indirect` contains more than one element and the `multi_draw_indirect`
+    ///   feature is not enabled.
+    /// - Panicks if `indirect` contains more elements than the `max_draw_indirect_count` limit.
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the dynamic state is not compatible with the pipeline.
+    ///
+    /// # Safety
+    ///
+    /// - The content of the indirect buffer is not checked.
+    ///
+    pub unsafe fn draw_indexed_indirect<'a, V, L, Pl, I, Ib, IbM, B, M>(self,
+                                        pipeline: &Arc<GraphicsPipeline<V::Definition, Pl>>, vertices: V,
+                                        indices: Ib, indirect: B, dynamic: &DynamicState,
+                                        sets: L, push_constants: &Pl::PushConstants)
+                                        -> SecondaryGraphicsCommandBufferBuilder
+        where V: MultiVertex, Pl: 'static + PipelineLayoutDesc,
+              Ib: Into<BufferSlice<'a, [I], IbM>>, I: 'static + Index, IbM: 'static,
+              L: DescriptorSetsCollection + 'static,
+              B: Into<BufferSlice<'a, [DrawIndexedIndirectCommand], M>>, M: 'static
+    {
+        SecondaryGraphicsCommandBufferBuilder {
+            inner: self.inner.draw_indexed_indirect(pipeline, vertices, indices, indirect,
+                                                    dynamic, sets, push_constants),
+        }
+    }
+
+    /// Starts a query. The query is active until `end_query` is called.
+    ///
+    /// If `precise` is true, an occlusion query counts the exact number of samples instead of
+    /// only producing a non-zero value if any sample passed.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the pool doesn't belong to the same device as the command buffer.
+    /// - Panicks if the slot is out of range of the pool.
+    /// - Panicks if the pool is a timestamp pool.
+    /// - Panicks if `precise` is true and the pool is not an occlusion pool, or the
+    ///   `occlusion_query_precise` feature is not enabled.
+    /// - Panicks if a query of the same type is already active.
+    ///
+    #[inline]
+    pub fn begin_query<P>(self, pool: &Arc<P>, slot: u32, precise: bool) -> SecondaryGraphicsCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            SecondaryGraphicsCommandBufferBuilder {
+                inner: self.inner.begin_query(pool, slot, precise),
+            }
+        }
+    }
+
+    /// Ends a query that was started with `begin_query`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the query is not active.
+    /// - Panicks if the query was started inside a render pass and this is called outside of
+    ///   it, or the other way around.
+    ///
+    #[inline]
+    pub fn end_query<P>(self, pool: &Arc<P>, slot: u32) -> SecondaryGraphicsCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            SecondaryGraphicsCommandBufferBuilder {
+                inner: self.inner.end_query(pool, slot),
+            }
+        }
+    }
+
+    /// Writes a timestamp to a query once the given stage of all the previous commands is
+    /// finished.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the pool doesn't belong to the same device as the command buffer.
+    /// - Panicks if the slot is out of range of the pool.
+    /// - Panicks if the pool is not a timestamp pool.
+    /// - Panicks if `stage` doesn't contain exactly one stage.
+    ///
+    #[inline]
+    pub fn write_timestamp<P>(self, pool: &Arc<P>, slot: u32, stage: PipelineStages) -> SecondaryGraphicsCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            SecondaryGraphicsCommandBufferBuilder {
+                inner: self.inner.write_timestamp(pool, slot, stage),
+            }
+        }
+    }
+
+    /// Opens a debug label that ends with `end_label`. Labels show up in debugging tools and
+    /// can be nested. Does nothing if the debug markers aren't enabled on the device.
+    #[inline]
+    pub fn begin_label(self, name: &str, color: [f32; 4]) -> SecondaryGraphicsCommandBufferBuilder {
+        unsafe {
+            SecondaryGraphicsCommandBufferBuilder {
+                inner: self.inner.begin_label(name, color),
+            }
+        }
+    }
+
+    /// Closes the last label opened with `begin_label`.
+    #[inline]
+    pub fn end_label(self) -> SecondaryGraphicsCommandBufferBuilder {
+        unsafe {
+            SecondaryGraphicsCommandBufferBuilder {
+                inner: self.inner.end_label(),
+            }
+        }
+    }
+
+    /// Inserts a single debug label. Does nothing if the debug markers aren't enabled on the
+    /// device.
+    #[inline]
+    pub fn insert_label(self, name: &str, color: [f32; 4]) -> SecondaryGraphicsCommandBufferBuilder {
+        unsafe {
+            SecondaryGraphicsCommandBufferBuilder {
+                inner: self.inner.insert_label(name, color),
+            }
+        }
+    }
+
+    /// Switches the builder to a mode where the commands are stored in a list that can be
+    /// inspected with `commands()`, instead of being written directly to the command buffer.
+    ///
+    /// The list is written to the command buffer when `build` is called, after the binds that
+    /// don't change anything have been removed. Commands that have been added before calling
+    /// this function are not part of the list.
+    ///
+    /// Note that the builder still owns a Vulkan command buffer in this mode. Use a
+    /// `CommandList` directly if you need to build commands without a device.
+    #[inline]
+    pub fn record_commands(self) -> SecondaryGraphicsCommandBufferBuilder {
+        SecondaryGraphicsCommandBufferBuilder {
+            inner: self.inner.record_commands(),
+        }
+    }
+
+    /// Returns the commands that have been recorded so far, or `None` if `record_commands`
+    /// hasn't been called.
+    #[inline]
+    pub fn commands(&self) -> Option<&CommandList> {
+        self.inner.commands()
+    }
+
+    /// Finish recording commands and build the command buffer.
+    #[inline]
+    pub fn build(self) -> Result<Arc<SecondaryGraphicsCommandBuffer>, OomError> {
+        let inner = try!(self.inner.build());
+        Ok(Arc::new(SecondaryGraphicsCommandBuffer { inner: inner }))
+    }
+}
+
+/// Represents a collection of commands to be executed by the GPU.
+///
+/// A secondary graphics command buffer contains draw commands and non-draw commands. Secondary
+/// command buffers are created for a specific subpass and can optionally specify which
+/// framebuffer they are drawing to. In order to execute them, you must create a primary command
+/// buffer, begin a compatible renderpass, and then call the secondary command buffer from the
+/// same subpass.
+///
+/// A secondary graphics command buffer can't be called outside of a renderpass.
+pub struct SecondaryGraphicsCommandBuffer {
+    inner: InnerCommandBuffer,
+}
+
+impl AbstractSecondaryCommandBuffer for SecondaryGraphicsCommandBuffer {
+    #[inline]
+    fn inner(&self) -> &InnerCommandBuffer {
+        &self.inner
+    }
+}
+
+unsafe impl VulkanObject for SecondaryGraphicsCommandBuffer {
+    type Object = vk::CommandBuffer;
+
+    #[inline]
+    fn internal_object(&self) -> vk::CommandBuffer {
+        self.inner.internal_object()
+    }
+}
+
+unsafe impl DebugName for SecondaryGraphicsCommandBuffer {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_COMMAND_BUFFER_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        DebugName::device(&self.inner)
+    }
+}
+
+impl SecondaryGraphicsCommandBuffer {
+    /// Returns the commands that were written to the command buffer, or `None` if
+    /// `record_commands` wasn't called on the builder.
+    #[inline]
+    pub fn commands(&self) -> Option<&CommandList> {
+        self.inner.commands()
+    }
+}
+
+/// A prototype of a secondary compute command buffer.
+pub struct SecondaryComputeCommandBufferBuilder {
+    inner: InnerCommandBufferBuilder,
+}
+
+impl SecondaryComputeCommandBufferBuilder {
+    /// Builds a new secondary command buffer and start recording commands in it.
+    ///
+    /// The command buffer will be built with `Usage::SimultaneousUse`.
+    #[inline]
+    pub fn new(pool: &Arc<CommandBufferPool>)
+               -> Result<SecondaryComputeCommandBufferBuilder, OomError>
+    {
+        SecondaryComputeCommandBufferBuilder::with_usage(pool, Usage::SimultaneousUse)
+    }
+
+    /// Same as `new`, but lets you choose how the command buffer is going to be used.
+    #[inline]
+    pub fn with_usage(pool: &Arc<CommandBufferPool>, usage: Usage)
+                      -> Result<SecondaryComputeCommandBufferBuilder, OomError>
+    {
+        let inner = try!(InnerCommandBufferBuilder::new(pool, true, usage, None));
+        Ok(SecondaryComputeCommandBufferBuilder { inner: inner })
+    }
+
+    /// Writes data to a buffer.
+    ///
+    /// The data is stored inside the command buffer and written to the given buffer slice.
+    /// This function is intended to be used for small amounts of data (only 64kB is allowed). If
+    /// you want to transfer large amounts of data, use `upload` or copies between buffers.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the size of `data` is not the same as the size of the buffer slice.
+    /// - Panicks if `data` is empty.
+    /// - Panicks if the size of `data` is superior to 65536 bytes.
+    /// - Panicks if the offset or size is not a multiple of 4.
+    /// - Panicks if the buffer wasn't created with the right usage.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    #[inline]
+    pub fn update_buffer<'a, B, T: ?Sized + 'a, M: 'a>(self, buffer: B, data: &T)
+                                                       -> SecondaryComputeCommandBufferBuilder
+        where B: Into<BufferSlice<'a, T, M>>
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.update_buffer(buffer, data)
+            }
+        }
+    }
+
+    /// Writes data to a buffer, whatever the amount of data.
+    ///
+    /// Small amounts of data are stored inside the command buffer, like with `update_buffer`.
+    /// Larger amounts of data are first written to a staging buffer that is allocated by this
+    /// function and kept alive by the command buffer, then copied to the given buffer slice.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the size of `data` is not the same as the size of the buffer slice.
+    /// - Panicks if `data` is empty.
+    /// - Panicks if the buffer wasn't created with the right usage.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    #[inline]
+    pub fn upload<'a, B, T: ?Sized + 'a, M: 'a>(self, buffer: B, data: &T)
+                                                -> Result<SecondaryComputeCommandBufferBuilder, OomError>
+        where B: Into<BufferSlice<'a, T, M>>
+    {
+        unsafe {
+            Ok(SecondaryComputeCommandBufferBuilder {
+                inner: try!(self.inner.upload(buffer, data))
+            })
+        }
+    }
+
+    /// Fills a buffer with data.
+    ///
+    /// The data is repeated until it fills the range from `offset` to `offset + size`.
+    /// Since the data is a u32, the offset and the size must be multiples of 4.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `offset + data` is superior to the size of the buffer.
+    /// - Panicks if the offset or size is not a multiple of 4.
+    /// - Panicks if the buffer wasn't created with the right usage.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    /// # Safety
+    ///
+    /// - Type safety is not enforced by the API.
+    pub unsafe fn fill_buffer<T: 'static, M>(self, buffer: &Arc<Buffer<T, M>>, offset: usize,
+                                             size: usize, data: u32)
+                                             -> SecondaryComputeCommandBufferBuilder
+        where M: MemorySourceChunk + 'static
+    {
+        SecondaryComputeCommandBufferBuilder {
+            inner: self.inner.fill_buffer(buffer, offset, size, data)
+        }
+    }
+
+    /// Copies the content of a buffer slice to another buffer slice.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the buffers don't belong to the same device.
+    /// - Panicks if one of the buffers wasn't created with the right usage.
+    /// - Panicks if the slices don't have the same size.
+    /// - Panicks if the slices are empty.
+    /// - Panicks if the slices overlap.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    #[inline]
+    pub fn copy_buffer<'a, 'b, S, D, T: ?Sized + 'static, Ms, Md>(self, source: S, destination: D)
+                                                                  -> SecondaryComputeCommandBufferBuilder
+        where S: Into<BufferSlice<'a, T, Ms>>, D: Into<BufferSlice<'b, T, Md>>,
+              Ms: 'static, Md: 'static
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.copy_buffer(source, destination),
+            }
+        }
+    }
+
+    /// Copies regions of a buffer slice to regions of another buffer slice.
+    ///
+    /// All the regions are copied with one command. The offsets and lengths of the regions are
+    /// in number of elements and are relative to the start of the slices. The source and the
+    /// destination can be slices of the same buffer, for example in order to compact its content,
+    /// as long as no source region overlaps a destination region.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the buffers don't belong to the same device.
+    /// - Panicks if one of the buffers wasn't created with the right usage.
+    /// - Panicks if one of the regions is empty or out of range of its slice.
+    /// - Panicks if one of the source regions overlaps one of the destination regions.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    #[inline]
+    pub fn copy_buffer_regions<'a, 'b, S, D, T: 'static, Ms, Md>(self, source: S, destination: D,
+                                                                 regions: &[BufferCopyRegion])
+                                                                 -> SecondaryComputeCommandBufferBuilder
+        where S: Into<BufferSlice<'a, [T], Ms>>, D: Into<BufferSlice<'b, [T], Md>>,
+              Ms: 'static, Md: 'static
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.copy_buffer_regions(source, destination, regions),
+            }
+        }
+    }
+
+    /// Copies a region of an image to another image.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the images don't belong to the same device.
+    /// - Panicks if one of the images wasn't created with the right usage.
+    /// - Panicks if one of the regions is empty or out of range of its image.
+    /// - Panicks if the extents or the number of layers of the regions are not the same.
+    /// - Panicks if the formats of the images don't have the same size, or if one of them is a
+    ///   depth or stencil format and they are not identical.
+    /// - Panicks if the images don't have the same number of samples.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    #[inline]
+    pub fn copy_image<Ts, Fs, Ms, Td, Fd, Md>(self, source: &Arc<Image<Ts, Fs, Ms>>,
+                                              source_region: &ImageRegion,
+                                              destination: &Arc<Image<Td, Fd, Md>>,
+                                              destination_region: &ImageRegion) -> SecondaryComputeCommandBufferBuilder
+        where Ts: ImageTypeMarker + 'static, Fs: FormatMarker + 'static,
+              Ms: MemorySourceChunk + 'static, Td: ImageTypeMarker + 'static,
+              Fd: FormatMarker + 'static, Md: MemorySourceChunk + 'static
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.copy_image(source, source_region, destination,
+                                             destination_region),
+            }
+        }
+    }
+
+    /// Copies a region of an image to another image, with scaling and format conversion.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the images don't belong to the same device.
+    /// - Panicks if one of the images wasn't created with the right usage.
+    /// - Panicks if one of the regions is empty or out of range of its image.
+    /// - Panicks if the number of layers of the regions are not the same.
+    /// - Panicks if one of the images is multisampled.
+    /// - Panicks if one of the images has a compressed format.
+    /// - Panicks if one of the images has a depth or stencil format and the formats are not
+    ///   identical, or if `filter` is `Linear`.
+    /// - Panicks if one of the images has an integer format and the other doesn't have an
+    ///   integer format of the same signedness.
+    /// - Panicks if the queue family doesn't support graphics operations.
+    ///
+    #[inline]
+    pub fn blit_image<Ts, Fs, Ms, Td, Fd, Md>(self, source: &Arc<Image<Ts, Fs, Ms>>,
+                                              source_region: &ImageRegion,
+                                              destination: &Arc<Image<Td, Fd, Md>>,
+                                              destination_region: &ImageRegion, filter: Filter)
+                                              -> SecondaryComputeCommandBufferBuilder
+        where Ts: ImageTypeMarker + 'static, Fs: FormatMarker + 'static,
+              Ms: MemorySourceChunk + 'static, Td: ImageTypeMarker + 'static,
+              Fd: FormatMarker + 'static, Md: MemorySourceChunk + 'static
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.blit_image(source, source_region, destination,
+                                             destination_region, filter),
+            }
+        }
+    }
+
+    /// Resolves a region of a multisampled image into a non-multisampled image.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the images don't belong to the same device.
+    /// - Panicks if one of the images wasn't created with the right usage.
+    /// - Panicks if one of the regions is empty or out of range of its image.
+    /// - Panicks if the extents or the number of layers of the regions are not the same.
+    /// - Panicks if the source is not multisampled or if the destination is multisampled.
+    /// - Panicks if the images don't have the same format.
+    /// - Panicks if the queue family doesn't support graphics operations.
+    ///
+    #[inline]
+    pub fn resolve_image<Ts, Fs, Ms, Td, Fd, Md>(self, source: &Arc<Image<Ts, Fs, Ms>>,
+                                                 source_region: &ImageRegion,
+                                                 destination: &Arc<Image<Td, Fd, Md>>,
+                                                 destination_region: &ImageRegion) -> SecondaryComputeCommandBufferBuilder
+        where Ts: ImageTypeMarker + 'static, Fs: FormatMarker + 'static,
+              Ms: MemorySourceChunk + 'static, Td: ImageTypeMarker + 'static,
+              Fd: FormatMarker + 'static, Md: MemorySourceChunk + 'static
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.resolve_image(source, source_region, destination,
+                                                destination_region),
+            }
+        }
+    }
+
+    /// Copies data from a buffer to a region of an image.
+    ///
+    /// The element type of the buffer must correspond to the format of the image.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the buffer and the image don't belong to the same device.
+    /// - Panicks if the buffer or the image wasn't created with the right usage.
+    /// - Panicks if the size of the element type of the buffer is not the size of a texel of the
+    ///   image. The formats don't have to match, for example `[u8; 4]` can be used with any
+    ///   format of four 8-bit components.
+    /// - Panicks if the image has a depth-stencil or a compressed format.
+    /// - Panicks if the region is empty or out of range of the image.
+    /// - Panicks if the layout is invalid or if the buffer is too small for the region.
+    /// - Panicks if the offset of the buffer is not a multiple of 4.
+    /// - Panicks if the image is multisampled.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    #[inline]
+    pub fn copy_buffer_to_image<'a, B, T, Mb, Ty, F, Mi>(self, source: B,
+                                                        destination: &Arc<Image<Ty, F, Mi>>,
+                                                        layout: &BufferImageLayout,
+                                                        region: &ImageRegion) -> SecondaryComputeCommandBufferBuilder
+        where B: Into<BufferSlice<'a, [T], Mb>>, T: Data + 'static, Mb: 'static,
+              Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              Mi: MemorySourceChunk + 'static
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.copy_buffer_to_image(source, destination, layout, region),
+            }
+        }
+    }
+
+    /// Copies data from a region of an image to a buffer.
+    ///
+    /// The element type of the buffer must correspond to the format of the image.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the buffer and the image don't belong to the same device.
+    /// - Panicks if the buffer or the image wasn't created with the right usage.
+    /// - Panicks if the size of the element type of the buffer is not the size of a texel of the
+    ///   image. The formats don't have to match, for example `[u8; 4]` can be used with any
+    ///   format of four 8-bit components.
+    /// - Panicks if the image has a depth-stencil or a compressed format.
+    /// - Panicks if the region is empty or out of range of the image.
+    /// - Panicks if the layout is invalid or if the buffer is too small for the region.
+    /// - Panicks if the offset of the buffer is not a multiple of 4.
+    /// - Panicks if the image is multisampled.
+    /// - Panicks if the queue family doesn't support transfer operations.
+    ///
+    #[inline]
+    pub fn copy_image_to_buffer<'a, B, T, Mb, Ty, F, Mi>(self, source: &Arc<Image<Ty, F, Mi>>,
+                                                        region: &ImageRegion, destination: B,
+                                                        layout: &BufferImageLayout) -> SecondaryComputeCommandBufferBuilder
+        where B: Into<BufferSlice<'a, [T], Mb>>, T: Data + 'static, Mb: 'static,
+              Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              Mi: MemorySourceChunk + 'static
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.copy_image_to_buffer(source, region, destination, layout),
+            }
+        }
+    }
+
+    /// Clears all the mipmap levels and array layers of a color image with a specific value.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the image doesn't belong to the same device.
+    /// - Panicks if the image wasn't created with the right usage.
+    /// - Panicks if the image has a depth, stencil or compressed format.
+    /// - Panicks if the clear value doesn't correspond to the format of the image.
+    /// - Panicks if the queue family doesn't support graphics or compute operations.
+    ///
+    #[inline]
+    pub fn clear_color_image<Ty, F, M>(self, image: &Arc<Image<Ty, F, M>>, color: ClearValue)
+                                       -> SecondaryComputeCommandBufferBuilder
+        where Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              M: MemorySourceChunk + 'static
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.clear_color_image(image, color),
+            }
+        }
+    }
+
+    /// Clears all the mipmap levels and array layers of a depth and/or stencil image with a
+    /// specific value.
+    ///
+    /// If the image has both a depth and a stencil component, passing `ClearValue::Depth` or
+    /// `ClearValue::Stencil` only clears the corresponding component.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the image doesn't belong to the same device.
+    /// - Panicks if the image wasn't created with the right usage.
+    /// - Panicks if the image doesn't have a depth or stencil format.
+    /// - Panicks if the clear value doesn't correspond to the format of the image.
+    /// - Panicks if the depth value is not between 0.0 and 1.0.
+    /// - Panicks if the queue family doesn't support graphics operations.
+    ///
+    #[inline]
+    pub fn clear_depth_stencil_image<Ty, F, M>(self, image: &Arc<Image<Ty, F, M>>,
+                                               value: ClearValue) -> SecondaryComputeCommandBufferBuilder
+        where Ty: ImageTypeMarker + 'static, F: FormatMarker + 'static,
+              M: MemorySourceChunk + 'static
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.clear_depth_stencil_image(image, value),
+            }
+        }
+    }
+
+    /// Executes a compute pipeline.
+    ///
+    /// The compute shader is invoked `dimensions[0] * dimensions[1] * dimensions[2]` times,
+    /// times the size of its local workgroup.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if one of the dimensions is superior to the device's
+    ///   `max_compute_work_group_count` limit.
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the queue family doesn't support compute operations.
+    ///
+    #[inline]
+    pub fn dispatch<Pl, L>(self, dimensions: [u32; 3], pipeline: &Arc<ComputePipeline<Pl>>,
+                           sets: L, push_constants: &Pl::PushConstants)
+                           -> SecondaryComputeCommandBufferBuilder
+        where L: 'static + DescriptorSetsCollection, Pl: 'static + PipelineLayoutDesc
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.dispatch(dimensions, pipeline, sets, push_constants)
+            }
+        }
+    }
+
+    /// Executes a compute pipeline, reading the number of workgroups from a buffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the indirect buffer wasn't created with the right usage.
+    /// - Panicks if the offset of the indirect buffer slice is not a multiple of 4.
+    /// - Panicks if the descriptor sets are not compatible with the pipeline's layout.
+    /// - Panicks if the number of dynamic offsets doesn't match the number of dynamic descriptors
+    ///   in the descriptor sets, or if an offset is not properly aligned.
+    /// - Panicks if the queue family doesn't support compute operations.
+    ///
+    /// # Safety
+    ///
+    /// - The content of the indirect buffer is not checked against the device's limits.
+    ///
+    #[inline]
+    pub unsafe fn dispatch_indirect<'a, Pl, L, B, M>(self, indirect: B,
+                                                     pipeline: &Arc<ComputePipeline<Pl>>, sets: L,
+                                                     push_constants: &Pl::PushConstants)
+                                                     -> SecondaryComputeCommandBufferBuilder
+        where L: 'static + DescriptorSetsCollection, Pl: 'static + PipelineLayoutDesc,
+              B: Into<BufferSlice<'a, DispatchIndirectCommand, M>>, M: 'static
+    {
+        SecondaryComputeCommandBufferBuilder {
+            inner: self.inner.dispatch_indirect(indirect, pipeline, sets, push_constants)
+        }
+    }
+
+    /// Adds a pipeline barrier to the command buffer.
+    ///
+    /// The barriers that are required between the commands of this command buffer are inserted
+    /// automatically. Adding a barrier manually is only needed for accesses that can't be
+    /// tracked, for example between two compute shader executions that access the same storage
+    /// buffer through descriptor sets.
     #[inline]
-    pub fn build(self) -> Result<Arc<SecondaryGraphicsCommandBuffer>, OomError> {
-        let inner = try!(self.inner.build());
-        Ok(Arc::new(SecondaryGraphicsCommandBuffer { inner: inner }))
+    pub fn pipeline_barrier(self, barrier: PipelineBarrierBuilder) -> SecondaryComputeCommandBufferBuilder {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.pipeline_barrier(barrier)
+            }
+        }
     }
-}
 
-/// Represents a collection of commands to be executed by the GPU.
-///
-/// A secondary graphics command buffer contains draw commands and non-draw commands. Secondary
-/// command buffers can't specify which framebuffer they are drawing to. Instead you must create
-/// a primary command buffer, specify a framebuffer, and then call the secondary command buffer.
-///
-/// A secondary graphics command buffer can't be called outside of a renderpass.
-pub struct SecondaryGraphicsCommandBuffer {
-    inner: InnerCommandBuffer,
-}
+    /// Signals an event once the given stages of all the previous commands are finished.
+    ///
+    /// Commands in a later command buffer can wait for the event with `wait_events`, and the
+    /// host can check its state with `Event::signaled`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the event doesn't belong to the same device as the command buffer.
+    /// - Panicks if `stages` is empty.
+    ///
+    #[inline]
+    pub fn set_event(self, event: &Arc<Event>, stages: PipelineStages) -> SecondaryComputeCommandBufferBuilder {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.set_event(event, stages)
+            }
+        }
+    }
 
-/// A prototype of a secondary compute command buffer.
-pub struct SecondaryComputeCommandBufferBuilder {
-    inner: InnerCommandBufferBuilder,
-}
+    /// Unsignals an event once the given stages of all the previous commands are finished.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the event doesn't belong to the same device as the command buffer.
+    /// - Panicks if `stages` is empty.
+    ///
+    #[inline]
+    pub fn reset_event(self, event: &Arc<Event>, stages: PipelineStages) -> SecondaryComputeCommandBufferBuilder {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.reset_event(event, stages)
+            }
+        }
+    }
 
-impl SecondaryComputeCommandBufferBuilder {
-    /// Builds a new secondary command buffer and start recording commands in it.
+    /// Waits until all the events are signaled, either by the host or by a previous command,
+    /// then applies the barrier.
+    ///
+    /// The destination stages of the barrier of the next commands are blocked until then. This
+    /// can be used to split a pipeline barrier in two parts: the work that is recorded between
+    /// `set_event` and `wait_events` can overlap with the commands before `set_event`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `events` is empty.
+    /// - Panicks if one of the events doesn't belong to the same device as the command buffer.
+    /// - Panicks if the barrier doesn't have any source or destination stage.
+    ///
+    /// # Safety
+    ///
+    /// - The source stages of the barrier must include the stages that were passed to
+    ///   `set_event`, or `host` if the event is signaled by the host.
+    ///
     #[inline]
-    pub fn new(pool: &Arc<CommandBufferPool>)
-               -> Result<SecondaryComputeCommandBufferBuilder, OomError>
+    pub unsafe fn wait_events(self, events: &[Arc<Event>], barrier: PipelineBarrierBuilder)
+                              -> SecondaryComputeCommandBufferBuilder
     {
-        let inner = try!(InnerCommandBufferBuilder::new(pool, true));
-        Ok(SecondaryComputeCommandBufferBuilder { inner: inner })
+        SecondaryComputeCommandBufferBuilder {
+            inner: self.inner.wait_events(events, barrier)
+        }
     }
 
-    /// Writes data to a buffer.
+    /// Resets the queries within `range`. Queries must be reset before they are used.
     ///
-    /// The data is stored inside the command buffer and written to the given buffer slice.
-    /// This function is intended to be used for small amounts of data (only 64kB is allowed). if
-    /// you want to transfer large amounts of data, use copies between buffers.
+    /// # Panic
+    ///
+    /// - Panicks if the pool doesn't belong to the same device as the command buffer.
+    /// - Panicks if the range is out of range of the pool.
+    ///
+    #[inline]
+    pub fn reset_query_pool<P>(self, pool: &Arc<P>, range: Range<u32>) -> SecondaryComputeCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.reset_query_pool(pool, range),
+            }
+        }
+    }
+
+    /// Starts a query. The query is active until `end_query` is called.
+    ///
+    /// If `precise` is true, an occlusion query counts the exact number of samples instead of
+    /// only producing a non-zero value if any sample passed.
     ///
     /// # Panic
     ///
-    /// - Panicks if the size of `data` is not the same as the size of the buffer slice.
-    /// - Panicks if the size of `data` is superior to 65536 bytes.
-    /// - Panicks if the offset or size is not a multiple of 4.
-    /// - Panicks if the buffer wasn't created with the right usage.
-    /// - Panicks if the queue family doesn't support transfer operations.
+    /// - Panicks if the pool doesn't belong to the same device as the command buffer.
+    /// - Panicks if the slot is out of range of the pool.
+    /// - Panicks if the pool is a timestamp pool.
+    /// - Panicks if `precise` is true and the pool is not an occlusion pool, or the
+    ///   `occlusion_query_precise` feature is not enabled.
+    /// - Panicks if a query of the same type is already active.
     ///
     #[inline]
-    pub fn update_buffer<'a, B, T: 'a, M: 'a>(self, buffer: B, data: &T)
-                                              -> SecondaryComputeCommandBufferBuilder
-        where B: Into<BufferSlice<'a, T, M>>
+    pub fn begin_query<P>(self, pool: &Arc<P>, slot: u32, precise: bool) -> SecondaryComputeCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
     {
         unsafe {
             SecondaryComputeCommandBufferBuilder {
-                inner: self.inner.update_buffer(buffer, data)
+                inner: self.inner.begin_query(pool, slot, precise),
             }
         }
     }
 
-    /// Fills a buffer with data.
+    /// Ends a query that was started with `begin_query`.
     ///
-    /// The data is repeated until it fills the range from `offset` to `offset + size`.
-    /// Since the data is a u32, the offset and the size must be multiples of 4.
+    /// # Panic
+    ///
+    /// - Panicks if the query is not active.
+    /// - Panicks if the query was started inside a render pass and this is called outside of
+    ///   it, or the other way around.
+    ///
+    #[inline]
+    pub fn end_query<P>(self, pool: &Arc<P>, slot: u32) -> SecondaryComputeCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.end_query(pool, slot),
+            }
+        }
+    }
+
+    /// Writes a timestamp to a query once the given stage of all the previous commands is
+    /// finished.
     ///
     /// # Panic
     ///
-    /// - Panicks if `offset + data` is superior to the size of the buffer.
-    /// - Panicks if the offset or size is not a multiple of 4.
-    /// - Panicks if the buffer wasn't created with the right usage.
-    /// - Panicks if the queue family doesn't support transfer operations.
+    /// - Panicks if the pool doesn't belong to the same device as the command buffer.
+    /// - Panicks if the slot is out of range of the pool.
+    /// - Panicks if the pool is not a timestamp pool.
+    /// - Panicks if `stage` doesn't contain exactly one stage.
     ///
-    /// # Safety
+    #[inline]
+    pub fn write_timestamp<P>(self, pool: &Arc<P>, slot: u32, stage: PipelineStages) -> SecondaryComputeCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static
+    {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.write_timestamp(pool, slot, stage),
+            }
+        }
+    }
+
+    /// Opens a debug label that ends with `end_label`. Labels show up in debugging tools and
+    /// can be nested. Does nothing if the debug markers aren't enabled on the device.
+    #[inline]
+    pub fn begin_label(self, name: &str, color: [f32; 4]) -> SecondaryComputeCommandBufferBuilder {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.begin_label(name, color),
+            }
+        }
+    }
+
+    /// Closes the last label opened with `begin_label`.
+    #[inline]
+    pub fn end_label(self) -> SecondaryComputeCommandBufferBuilder {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.end_label(),
+            }
+        }
+    }
+
+    /// Inserts a single debug label. Does nothing if the debug markers aren't enabled on the
+    /// device.
+    #[inline]
+    pub fn insert_label(self, name: &str, color: [f32; 4]) -> SecondaryComputeCommandBufferBuilder {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.insert_label(name, color),
+            }
+        }
+    }
+
+    /// Copies the results of the queries within `range` to a buffer.
     ///
-    /// - Type safety is not enforced by the API.
-    pub unsafe fn fill_buffer<T: 'static, M>(self, buffer: &Arc<Buffer<T, M>>, offset: usize,
-                                             size: usize, data: u32)
-                                             -> SecondaryComputeCommandBufferBuilder
-        where M: MemorySourceChunk + 'static
+    /// Each query produces `num_values_per_query()` values. Using `u64` as the element type of
+    /// the buffer produces 64-bit results.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the pool doesn't belong to the same device as the command buffer.
+    /// - Panicks if the range is out of range of the pool.
+    /// - Panicks if the buffer doesn't have the right length.
+    /// - Panicks if the buffer wasn't created with the right usage.
+    /// - Panicks if the offset of the buffer is not a multiple of the size of `T`.
+    /// - Panicks if `flags.partial` is true and the pool is a timestamp pool.
+    ///
+    #[inline]
+    pub fn copy_query_pool_results<'a, P, B, T, M>(self, pool: &Arc<P>, range: Range<u32>,
+                                                   destination: B, flags: QueryResultFlags)
+                                                   -> SecondaryComputeCommandBufferBuilder
+        where P: QueryPool + VulkanObject<Object = vk::QueryPool> + 'static,
+              B: Into<BufferSlice<'a, [T], M>>, T: QueryResultElement + 'static, M: 'static
     {
+        unsafe {
+            SecondaryComputeCommandBufferBuilder {
+                inner: self.inner.copy_query_pool_results(pool, range, destination, flags),
+            }
+        }
+    }
+
+    /// Switches the builder to a mode where the commands are stored in a list that can be
+    /// inspected with `commands()`, instead of being written directly to the command buffer.
+    ///
+    /// The list is written to the command buffer when `build` is called, after the binds that
+    /// don't change anything have been removed. Commands that have been added before calling
+    /// this function are not part of the list.
+    ///
+    /// Note that the builder still owns a Vulkan command buffer in this mode. Use a
+    /// `CommandList` directly if you need to build commands without a device.
+    #[inline]
+    pub fn record_commands(self) -> SecondaryComputeCommandBufferBuilder {
         SecondaryComputeCommandBufferBuilder {
-            inner: self.inner.fill_buffer(buffer, offset, size, data)
+            inner: self.inner.record_commands(),
         }
     }
 
+    /// Returns the commands that have been recorded so far, or `None` if `record_commands`
+    /// hasn't been called.
+    #[inline]
+    pub fn commands(&self) -> Option<&CommandList> {
+        self.inner.commands()
+    }
+
     /// Finish recording commands and build the command buffer.
     #[inline]
     pub fn build(self) -> Result<Arc<SecondaryComputeCommandBuffer>, OomError> {
@@ -529,10 +2434,65 @@ pub struct SecondaryComputeCommandBuffer {
     inner: InnerCommandBuffer,
 }
 
+impl AbstractSecondaryCommandBuffer for SecondaryComputeCommandBuffer {
+    #[inline]
+    fn inner(&self) -> &InnerCommandBuffer {
+        &self.inner
+    }
+}
+
+unsafe impl VulkanObject for SecondaryComputeCommandBuffer {
+    type Object = vk::CommandBuffer;
+
+    #[inline]
+    fn internal_object(&self) -> vk::CommandBuffer {
+        self.inner.internal_object()
+    }
+}
+
+unsafe impl DebugName for SecondaryComputeCommandBuffer {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_COMMAND_BUFFER_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        DebugName::device(&self.inner)
+    }
+}
+
+impl SecondaryComputeCommandBuffer {
+    /// Returns the commands that were written to the command buffer, or `None` if
+    /// `record_commands` wasn't called on the builder.
+    #[inline]
+    pub fn commands(&self) -> Option<&CommandList> {
+        self.inner.commands()
+    }
+}
+
 /// The dynamic state to use for a draw command.
-#[derive(Debug, Copy, Clone)]
+///
+/// Each field must be `Some` if and only if the corresponding state of the pipeline is dynamic.
+#[derive(Debug, Clone, PartialEq)]
 pub struct DynamicState {
     pub line_width: Option<f32>,
+
+    /// Must contain as many viewports as the pipeline.
+    pub viewports: Option<Vec<Viewport>>,
+
+    /// Must contain as many scissors as the pipeline.
+    pub scissors: Option<Vec<Scissor>>,
+
+    pub depth_bias: Option<DepthBias>,
+
+    pub blend_constants: Option<[f32; 4]>,
+
+    /// Both bounds must be between 0.0 and 1.0.
+    pub depth_bounds: Option<Range<f32>>,
+
+    pub stencil_compare_mask: Option<DynamicStencilValue>,
+
+    pub stencil_write_mask: Option<DynamicStencilValue>,
+
+    pub stencil_reference: Option<DynamicStencilValue>,
 }
 
 impl DynamicState {
@@ -540,6 +2500,14 @@ impl DynamicState {
     pub fn none() -> DynamicState {
         DynamicState {
             line_width: None,
+            viewports: None,
+            scissors: None,
+            depth_bias: None,
+            blend_constants: None,
+            depth_bounds: None,
+            stencil_compare_mask: None,
+            stencil_write_mask: None,
+            stencil_reference: None,
         }
     }
 }
@@ -550,3 +2518,252 @@ impl Default for DynamicState {
         DynamicState::none()
     }
 }
+
+/// How a command buffer is going to be used once it has been built.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Usage {
+    /// The command buffer will be submitted only once. Allows the implementation to perform
+    /// some optimizations.
+    OneTime,
+
+    /// The command buffer can be submitted multiple times, but not while a previous submission
+    /// is still being executed.
+    MultipleSubmits,
+
+    /// The command buffer can be submitted multiple times, even while previous submissions are
+    /// still being executed. This is the least efficient usage.
+    SimultaneousUse,
+}
+
+/// Value of a dynamic stencil state, for the front and back faces.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DynamicStencilValue {
+    pub front: u32,
+    pub back: u32,
+}
+
+/// Attachment of the current subpass to clear with `clear_attachments`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ClearAttachment {
+    /// Clears the color attachment with the given index within the color attachments of the
+    /// subpass. The value must be `Float`, `Int` or `Uint` depending on the format.
+    Color(u32, ClearValue),
+
+    /// Clears the depth-stencil attachment of the subpass. The value must be `Depth`, `Stencil`
+    /// or `DepthStencil`.
+    DepthStencil(ClearValue),
+}
+
+/// Describes a rectangle of the framebuffer, for `clear_attachments`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ClearRect {
+    /// Coordinates of the top-left corner of the rectangle.
+    pub offset: [u32; 2],
+    /// Size of the rectangle, in pixels.
+    pub extent: [u32; 2],
+    /// First layer of the framebuffer to clear.
+    pub first_layer: u32,
+    /// Number of layers to clear, starting at `first_layer`.
+    pub num_layers: u32,
+}
+
+/// Describes the part of the framebuffer that is affected by a render pass.
+///
+/// The content of the attachments outside of the render area is undefined after the render pass.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RenderArea {
+    /// Coordinates of the top-left corner of the area.
+    pub offset: [u32; 2],
+    /// Size of the area, in pixels.
+    pub extent: [u32; 2],
+}
+
+impl RenderArea {
+    /// Builds a render area that covers the whole framebuffer.
+    #[inline]
+    pub fn whole<L>(framebuffer: &Framebuffer<L>) -> RenderArea {
+        RenderArea {
+            offset: [0, 0],
+            extent: [framebuffer.width(), framebuffer.height()],
+        }
+    }
+}
+
+/// Describes a region to copy with `copy_buffer_regions`.
+///
+/// The offsets and the length are in number of elements.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BufferCopyRegion {
+    /// Offset of the first element to read, relative to the start of the source slice.
+    pub source_offset: usize,
+    /// Offset of the first element to write, relative to the start of the destination slice.
+    pub destination_offset: usize,
+    /// Number of elements to copy.
+    pub len: usize,
+}
+
+/// Describes a region of an image, for the commands that copy between images.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ImageRegion {
+    /// Mipmap level of the image to use.
+    pub mipmap_level: u32,
+    /// First array layer to use.
+    pub first_layer: u32,
+    /// Number of array layers to use, starting at `first_layer`.
+    pub num_layers: u32,
+    /// Coordinates of the first texel of the region.
+    pub offset: [u32; 3],
+    /// Size of the region, in texels.
+    pub extent: [u32; 3],
+}
+
+impl ImageRegion {
+    /// Builds a region that covers the whole of the first mipmap level of an image.
+    #[inline]
+    pub fn whole<Ty, F, M>(image: &Image<Ty, F, M>) -> ImageRegion
+        where Ty: ImageTypeMarker, F: FormatMarker, M: MemorySourceChunk
+    {
+        ImageRegion {
+            mipmap_level: 0,
+            first_layer: 0,
+            num_layers: image.array_layers(),
+            offset: [0, 0, 0],
+            extent: Ty::extent(image.dimensions()),
+        }
+    }
+
+    /// Returns true if the region is within the given image and is not empty.
+    pub fn is_within<Ty, F, M>(&self, image: &Image<Ty, F, M>) -> bool
+        where Ty: ImageTypeMarker, F: FormatMarker, M: MemorySourceChunk
+    {
+        if self.mipmap_level >= image.mipmap_levels() {
+            return false;
+        }
+
+        if self.num_layers == 0 {
+            return false;
+        }
+
+        match self.first_layer.checked_add(self.num_layers) {
+            Some(end) if end <= image.array_layers() => (),
+            _ => return false,
+        }
+
+        let extent = Ty::extent(image.dimensions());
+        for i in 0 .. 3 {
+            if self.extent[i] == 0 {
+                return false;
+            }
+
+            let mip_extent = cmp::max(extent[i] >> self.mipmap_level, 1);
+            match self.offset[i].checked_add(self.extent[i]) {
+                Some(end) if end <= mip_extent => (),
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+/// Describes how the texels of an image region are laid out in a buffer, for the commands that
+/// copy between buffers and images.
+///
+/// The `Default` implementation describes tightly-packed texels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct BufferImageLayout {
+    /// Number of texels between the start of two consecutive rows. `0` means that rows are
+    /// tightly packed.
+    pub row_length: u32,
+    /// Number of rows between the start of two consecutive 2D slices or array layers. `0` means
+    /// that slices are tightly packed.
+    pub image_height: u32,
+}
+
+impl BufferImageLayout {
+    /// Returns the minimum number of texels that the buffer must contain in order to hold a
+    /// region of the given dimensions with this layout.
+    pub fn required_len(&self, region: &ImageRegion) -> usize {
+        let width = region.extent[0] as usize;
+        let height = region.extent[1] as usize;
+        let slices = region.extent[2] as usize * region.num_layers as usize;
+
+        if width == 0 || height == 0 || slices == 0 {
+            return 0;
+        }
+
+        let row_length = if self.row_length == 0 { width } else { self.row_length as usize };
+        let image_height = if self.image_height == 0 { height } else { self.image_height as usize };
+
+        ((slices - 1) * image_height + (height - 1)) * row_length + width
+    }
+}
+
+/// Parameters of a draw command read by `draw_indirect`.
+// Has the same memory representation as a `VkDrawIndirectCommand`.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct DrawIndirectCommand {
+    pub vertex_count: u32,
+    pub instance_count: u32,
+    pub first_vertex: u32,
+    pub first_instance: u32,
+}
+
+/// Parameters of a draw command read by `draw_indexed_indirect`.
+// Has the same memory representation as a `VkDrawIndexedIndirectCommand`.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct DrawIndexedIndirectCommand {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub vertex_offset: i32,
+    pub first_instance: u32,
+}
+
+/// Parameters of a dispatch command read by `dispatch_indirect`.
+// Has the same memory representation as a `VkDispatchIndirectCommand`.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct DispatchIndirectCommand {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+/// Error that can happen when recording a draw command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DrawError {
+    /// The range of vertices is out of range of the vertex buffers.
+    VertexRangeOutOfBounds,
+    /// The range of indices is out of range of the index buffer.
+    IndexRangeOutOfBounds,
+    /// The start of the range of instances is superior to its end.
+    InvalidInstanceRange,
+    /// The range of instances is out of range of the per-instance vertex buffers.
+    InstanceRangeOutOfBounds,
+}
+
+impl error::Error for DrawError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            DrawError::VertexRangeOutOfBounds => "the range of vertices is out of range of the \
+                                                  vertex buffers",
+            DrawError::IndexRangeOutOfBounds => "the range of indices is out of range of the \
+                                                 index buffer",
+            DrawError::InvalidInstanceRange => "the start of the range of instances is superior \
+                                                to its end",
+            DrawError::InstanceRangeOutOfBounds => "the range of instances is out of range of \
+                                                    the per-instance vertex buffers",
+        }
+    }
+}
+
+impl fmt::Display for DrawError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
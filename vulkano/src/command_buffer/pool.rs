@@ -1,10 +1,18 @@
+use std::error;
+use std::fmt;
 use std::mem;
 use std::ptr;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::u64;
 
 use instance::QueueFamily;
+use sync::Fence;
+use sync::FenceWaitError;
 
+use device::DebugName;
 use device::Device;
+use Error;
 use OomError;
 use VulkanObject;
 use VulkanPointers;
@@ -16,6 +24,23 @@ pub struct CommandBufferPool {
     device: Arc<Device>,
     pool: vk::CommandPool,
     queue_family_index: u32,
+    transient: bool,
+    reset_command_buffer: bool,
+
+    // The mutex is also used to synchronize the accesses to the pool, as required by Vulkan.
+    state: Mutex<PoolState>,
+}
+
+struct PoolState {
+    // Number of command buffer builders of this pool that are currently recording.
+    recording: usize,
+
+    // Incremented every time the pool is reset. The command buffers that were built before a
+    // reset are no longer valid.
+    generation: u64,
+
+    // Fences of the submissions of command buffers of this pool that may not be finished yet.
+    fences: Vec<Arc<Fence>>,
 }
 
 impl CommandBufferPool {
@@ -24,6 +49,9 @@ impl CommandBufferPool {
     /// The command buffers created with this pool can only be executed on queues of the given
     /// family.
     ///
+    /// The command buffers of this pool can be reset individually. This is the same as calling
+    /// `with_flags(device, queue_family, false, true)`.
+    ///
     /// # Panic
     ///
     /// Panicks if the queue family doesn't belong to the same physical device as `device`.
@@ -31,17 +59,45 @@ impl CommandBufferPool {
     #[inline]
     pub fn new(device: &Arc<Device>, queue_family: &QueueFamily)
                -> Result<Arc<CommandBufferPool>, OomError>
+    {
+        CommandBufferPool::with_flags(device, queue_family, false, true)
+    }
+
+    /// Creates a new pool with custom flags.
+    ///
+    /// If `transient` is true, the implementation will expect the command buffers of this pool
+    /// to be short-lived and will optimize the pool for this usage. If `reset_command_buffer` is
+    /// true, the command buffers of this pool can be reset individually in order to be reused.
+    /// Otherwise they can only be reset all at once by resetting the pool.
+    ///
+    /// # Panic
+    ///
+    /// Panicks if the queue family doesn't belong to the same physical device as `device`.
+    ///
+    pub fn with_flags(device: &Arc<Device>, queue_family: &QueueFamily, transient: bool,
+                      reset_command_buffer: bool) -> Result<Arc<CommandBufferPool>, OomError>
     {
         assert_eq!(device.physical_device().internal_object(),
                    queue_family.physical_device().internal_object());
 
         let vk = device.pointers();
 
+        let flags = {
+            let flag1 = if transient { vk::COMMAND_POOL_CREATE_TRANSIENT_BIT } else { 0 };
+            let flag2 = if reset_command_buffer {
+                vk::COMMAND_POOL_CREATE_RESET_COMMAND_BUFFER_BIT
+            } else {
+                0
+            };
+
+            flag1 | flag2
+        };
+
         let pool = unsafe {
             let infos = vk::CommandPoolCreateInfo {
                 sType: vk::STRUCTURE_TYPE_COMMAND_POOL_CREATE_INFO,
                 pNext: ptr::null(),
-                flags: 0,       // TODO: 
+                flags: flags,
                 queueFamilyIndex: queue_family.id(),
             };
 
@@ -55,9 +111,73 @@ impl CommandBufferPool {
             device: device.clone(),
             pool: pool,
             queue_family_index: queue_family.id(),
+            transient: transient,
+            reset_command_buffer: reset_command_buffer,
+            state: Mutex::new(PoolState {
+                recording: 0,
+                generation: 0,
+                fences: Vec::new(),
+            }),
         }))
     }
 
+    /// Resets the pool, so that the memory that was used by the command buffers of this pool can
+    /// be reused by the next command buffers.
+    ///
+    /// Blocks until all the submissions of the command buffers of this pool are finished. The
+    /// command buffers that were built from this pool can't be submitted anymore afterwards, and
+    /// must be reset or destroyed.
+    ///
+    /// Returns an error if a command buffer builder of this pool is still alive.
+    ///
+    #[inline]
+    pub fn reset(&self) -> Result<(), ResetError> {
+        self.reset_impl(false)
+    }
+
+    /// Same as `reset`, but also gives back to the system the memory that was used by the
+    /// command buffers of this pool.
+    #[inline]
+    pub fn reset_and_release_resources(&self) -> Result<(), ResetError> {
+        self.reset_impl(true)
+    }
+
+    fn reset_impl(&self, release_resources: bool) -> Result<(), ResetError> {
+        let mut state = self.state.lock().unwrap();
+
+        // resetting the pool would put the command buffers that are being recorded in an
+        // invalid state
+        if state.recording != 0 {
+            return Err(ResetError::CommandBufferRecording);
+        }
+
+        // the command buffers must not be reset while they are being executed
+        for fence in state.fences.iter() {
+            match fence.wait(u64::MAX) {
+                Ok(()) => (),
+                Err(FenceWaitError::OomError(err)) => return Err(ResetError::OomError(err)),
+                Err(FenceWaitError::DeviceLost) => return Err(ResetError::DeviceLost),
+                Err(FenceWaitError::Timeout) => unreachable!(),
+            }
+        }
+        state.fences.clear();
+
+        let flags = if release_resources {
+            vk::COMMAND_POOL_RESET_RELEASE_RESOURCES_BIT
+        } else {
+            0
+        };
+
+        unsafe {
+            let vk = self.device.pointers();
+            try!(check_errors(vk.ResetCommandPool(self.device.internal_object(), self.pool,
+                                                  flags)));
+        }
+
+        state.generation += 1;
+        Ok(())
+    }
+
     /// Returns the device this command pool was created with.
     #[inline]
     pub fn device(&self) -> &Arc<Device> {
@@ -69,6 +189,120 @@ impl CommandBufferPool {
     pub fn queue_family(&self) -> QueueFamily {
         self.device.physical_device().queue_family_by_id(self.queue_family_index).unwrap()
     }
+
+    /// Returns true if the pool was created with the `transient` flag.
+    #[inline]
+    pub fn is_transient(&self) -> bool {
+        self.transient
+    }
+
+    /// Returns true if the command buffers of this pool can be reset individually.
+    #[inline]
+    pub fn can_reset_command_buffers(&self) -> bool {
+        self.reset_command_buffer
+    }
+
+    /// Allocates a command buffer from this pool.
+    #[doc(hidden)]
+    pub fn alloc_command_buffer(&self, secondary: bool) -> Result<vk::CommandBuffer, OomError> {
+        let _state = self.state.lock().unwrap();
+
+        let cmd = unsafe {
+            let vk = self.device.pointers();
+
+            let infos = vk::CommandBufferAllocateInfo {
+                sType: vk::STRUCTURE_TYPE_COMMAND_BUFFER_ALLOCATE_INFO,
+                pNext: ptr::null(),
+                commandPool: self.pool,
+                level: if secondary {
+                    vk::COMMAND_BUFFER_LEVEL_SECONDARY
+                } else {
+                    vk::COMMAND_BUFFER_LEVEL_PRIMARY
+                },
+                // vulkan can allocate multiple command buffers at once, hence the 1
+                commandBufferCount: 1,
+            };
+
+            let mut output = mem::uninitialized();
+            try!(check_errors(vk.AllocateCommandBuffers(self.device.internal_object(), &infos,
+                                                        &mut output)));
+            output
+        };
+
+        Ok(cmd)
+    }
+
+    /// Frees a command buffer that was allocated with `alloc_command_buffer`.
+    ///
+    /// # Safety
+    ///
+    /// - The command buffer must not be in use by the GPU anymore.
+    ///
+    #[doc(hidden)]
+    pub unsafe fn free_command_buffer(&self, cmd: vk::CommandBuffer) {
+        let _state = self.state.lock().unwrap();
+
+        let vk = self.device.pointers();
+        vk.FreeCommandBuffers(self.device.internal_object(), self.pool, 1, &cmd);
+    }
+
+    /// Resets a command buffer that was allocated from this pool.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer must have been allocated from this pool, must not be in use by the
+    /// GPU, and the pool must have been created with the flag that allows resetting command
+    /// buffers individually.
+    #[doc(hidden)]
+    pub unsafe fn reset_command_buffer(&self, cmd: vk::CommandBuffer) -> Result<(), ResetError> {
+        let _state = self.state.lock().unwrap();
+
+        let vk = self.device.pointers();
+        try!(check_errors(vk.ResetCommandBuffer(cmd, 0)));
+        Ok(())
+    }
+
+    /// Must be called when a command buffer of this pool starts being recorded. Returns the
+    /// current generation of the pool, which is incremented every time the pool is reset.
+    #[doc(hidden)]
+    #[inline]
+    pub fn begin_recording(&self) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        state.recording += 1;
+        state.generation
+    }
+
+    /// Must be called when a command buffer of this pool stops being recorded, either because it
+    /// has been built or because the builder has been destroyed.
+    #[doc(hidden)]
+    #[inline]
+    pub fn end_recording(&self) {
+        let mut state = self.state.lock().unwrap();
+        debug_assert!(state.recording >= 1);
+        state.recording -= 1;
+    }
+
+    /// Returns the current generation of the pool. Command buffers that were built with a
+    /// different generation have been reset by the pool.
+    #[doc(hidden)]
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
+    }
+
+    /// Must be called after a command buffer of this pool has been submitted. `fence` is the
+    /// fence that is signaled at the end of the submission.
+    #[doc(hidden)]
+    pub fn register_submission(&self, fence: Arc<Fence>) {
+        let mut state = self.state.lock().unwrap();
+
+        // forgetting the submissions that are finished, so that the list doesn't grow forever
+        state.fences.retain(|f| f.ready() != Ok(true));
+
+        if !state.fences.iter().any(|f| f.internal_object() == fence.internal_object()) {
+            state.fences.push(fence);
+        }
+    }
 }
 
 unsafe impl VulkanObject for CommandBufferPool {
@@ -80,6 +314,15 @@ unsafe impl VulkanObject for CommandBufferPool {
     }
 }
 
+unsafe impl DebugName for CommandBufferPool {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_COMMAND_POOL_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl Drop for CommandBufferPool {
     #[inline]
     fn drop(&mut self) {
@@ -89,3 +332,74 @@ impl Drop for CommandBufferPool {
         }
     }
 }
+
+/// Error that can happen when resetting a pool or a command buffer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResetError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The connection to the device has been lost.
+    DeviceLost,
+
+    /// A command buffer builder of the pool is still alive.
+    CommandBufferRecording,
+
+    /// The command buffer is still shared with other `Arc`s, for example with a `Submission`
+    /// that is still alive.
+    CommandBufferInUse,
+
+    /// The pool of the command buffer wasn't created with the flag that allows resetting
+    /// command buffers individually.
+    ResetNotAllowed,
+}
+
+impl From<OomError> for ResetError {
+    #[inline]
+    fn from(err: OomError) -> ResetError {
+        ResetError::OomError(err)
+    }
+}
+
+impl From<Error> for ResetError {
+    #[inline]
+    fn from(err: Error) -> ResetError {
+        match err {
+            err @ Error::OutOfHostMemory => ResetError::OomError(OomError::from(err)),
+            err @ Error::OutOfDeviceMemory => ResetError::OomError(OomError::from(err)),
+            // no other error is allowed by the specifications ; if the implementation returns
+            // one anyway, the state of the device can't be trusted anymore
+            _ => ResetError::DeviceLost,
+        }
+    }
+}
+
+impl error::Error for ResetError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            ResetError::OomError(_) => "no memory available",
+            ResetError::DeviceLost => "the connection to the device has been lost",
+            ResetError::CommandBufferRecording => "a command buffer builder of the pool is still \
+                                                   alive",
+            ResetError::CommandBufferInUse => "the command buffer is still in use",
+            ResetError::ResetNotAllowed => "the pool doesn't allow resetting command buffers \
+                                            individually",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            ResetError::OomError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ResetError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
@@ -0,0 +1,439 @@
+use std::error;
+use std::fmt;
+use std::ptr;
+use std::sync::Arc;
+use std::u64;
+
+use command_buffer::PrimaryCommandBuffer;
+use device::Queue;
+use sync::Fence;
+use sync::FenceWaitError;
+use sync::PipelineStages;
+use sync::Semaphore;
+
+use Error;
+use OomError;
+use VulkanObject;
+use VulkanPointers;
+use check_errors;
+use vk;
+
+/// Prototype for a submission of command buffers to a queue.
+///
+/// All the command buffers are submitted with one call to `vkQueueSubmit`. The semaphores and
+/// the fence that are required by the resources used by the command buffers are automatically
+/// handled, but you can add your own semaphores and fence in order to synchronize the submission
+/// with other operations.
+pub struct SubmitBuilder {
+    command_buffers: Vec<Arc<PrimaryCommandBuffer>>,
+    wait_semaphores: Vec<(Arc<Semaphore>, PipelineStages)>,
+    signal_semaphores: Vec<Arc<Semaphore>>,
+    fence: Option<Arc<Fence>>,
+}
+
+impl SubmitBuilder {
+    /// Builds a new empty submission.
+    #[inline]
+    pub fn new() -> SubmitBuilder {
+        SubmitBuilder {
+            command_buffers: Vec::new(),
+            wait_semaphores: Vec::new(),
+            signal_semaphores: Vec::new(),
+            fence: None,
+        }
+    }
+
+    /// Adds a command buffer to execute. The command buffers are executed in the order in which
+    /// they are added.
+    #[inline]
+    pub fn add_command_buffer(mut self, command_buffer: &Arc<PrimaryCommandBuffer>)
+                              -> SubmitBuilder
+    {
+        self.command_buffers.push(command_buffer.clone());
+        self
+    }
+
+    /// Adds a semaphore that must be signaled before the given stages of the command buffers
+    /// can be executed.
+    #[inline]
+    pub fn add_wait_semaphore(mut self, semaphore: &Arc<Semaphore>, stages: PipelineStages)
+                              -> SubmitBuilder
+    {
+        self.wait_semaphores.push((semaphore.clone(), stages));
+        self
+    }
+
+    /// Adds a semaphore that is going to be signaled when the command buffers have finished
+    /// executing.
+    #[inline]
+    pub fn add_signal_semaphore(mut self, semaphore: &Arc<Semaphore>) -> SubmitBuilder {
+        self.signal_semaphores.push(semaphore.clone());
+        self
+    }
+
+    /// Sets the fence that is going to be signaled when the command buffers have finished
+    /// executing. If no fence is set, a new one is created.
+    ///
+    /// The fence must be in the unsignaled state when `submit` is called.
+    #[inline]
+    pub fn fence(mut self, fence: &Arc<Fence>) -> SubmitBuilder {
+        self.fence = Some(fence.clone());
+        self
+    }
+
+    /// Submits the command buffers to a queue.
+    ///
+    /// Queues are not thread-safe, therefore we need to get a `&mut`.
+    ///
+    /// Nothing is submitted if an error is returned because one of the objects doesn't match
+    /// the queue, or because one of the command buffers or the fence is still in use. A command
+    /// buffer that wasn't built with `Usage::SimultaneousUse` can't be added twice to the same
+    /// submission, and neither can a secondary command buffer executed by two of them.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if one of the wait semaphores doesn't have any stage.
+    ///
+    pub fn submit(self, queue: &mut Queue) -> Result<Submission, SubmitError> {
+        // locking the command buffers, so that they can't be submitted by another thread or
+        // twice in this submission
+        for (num, command_buffer) in self.command_buffers.iter().enumerate() {
+            if let Err(err) = command_buffer.inner().check_submit(queue) {
+                for command_buffer in self.command_buffers[.. num].iter() {
+                    command_buffer.inner().abort_submit();
+                }
+                return Err(err);
+            }
+        }
+
+        let command_buffers = self.command_buffers.clone();
+        match self.submit_locked(queue) {
+            Ok(submission) => Ok(submission),
+            Err(err) => {
+                for command_buffer in command_buffers.iter() {
+                    command_buffer.inner().abort_submit();
+                }
+                Err(err)
+            },
+        }
+    }
+
+    // Second part of `submit`, once all the command buffers have been locked.
+    fn submit_locked(self, queue: &mut Queue) -> Result<Submission, SubmitError> {
+        for &(ref semaphore, _) in self.wait_semaphores.iter() {
+            if &**semaphore.device() as *const _ != &**queue.device() as *const _ {
+                return Err(SubmitError::WrongDevice);
+            }
+        }
+
+        for semaphore in self.signal_semaphores.iter() {
+            if &**semaphore.device() as *const _ != &**queue.device() as *const _ {
+                return Err(SubmitError::WrongDevice);
+            }
+        }
+
+        let fence = match self.fence {
+            Some(fence) => {
+                if &**fence.device() as *const _ != &**queue.device() as *const _ {
+                    return Err(SubmitError::WrongDevice);
+                }
+                if try!(fence.raw_ready()) {
+                    return Err(SubmitError::FenceSignaled);
+                }
+                fence
+            },
+            None => try!(Fence::raw(queue.device(), false)),
+        };
+
+        // creating all the semaphores required by the resources before modifying the state of
+        // any of them, so that a failed allocation doesn't leave anything behind
+        let mut post_semaphores = Vec::with_capacity(self.command_buffers.len());
+        for command_buffer in self.command_buffers.iter() {
+            post_semaphores.push(try!(command_buffer.inner().prepare_submit(queue)));
+        }
+
+        let mut semaphores = Vec::new();
+
+        // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
+        let mut wait_semaphores = Vec::with_capacity(self.wait_semaphores.len());
+        let mut wait_stages = Vec::with_capacity(self.wait_semaphores.len());
+        for (semaphore, stages) in self.wait_semaphores.into_iter() {
+            let stages: vk::PipelineStageFlags = stages.into();
+            assert!(stages != 0);
+            wait_semaphores.push(semaphore.internal_object());
+            wait_stages.push(stages);
+            semaphores.push(semaphore);
+        }
+
+        // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
+        let mut signal_semaphores = Vec::with_capacity(self.signal_semaphores.len());
+        for semaphore in self.signal_semaphores.into_iter() {
+            signal_semaphores.push(semaphore.internal_object());
+            semaphores.push(semaphore);
+        }
+
+        // semaphores waited upon and signaled on behalf of the resources, in case the submission
+        // needs to be rolled back
+        let mut resources_wait = Vec::new();
+        let mut resources_signal = Vec::new();
+
+        // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
+        let mut command_buffers = Vec::with_capacity(self.command_buffers.len());
+        for (command_buffer, post) in self.command_buffers.iter().zip(post_semaphores) {
+            for semaphore in post.iter().filter_map(|s| s.as_ref()) {
+                signal_semaphores.push(semaphore.internal_object());
+                resources_signal.push(semaphore.internal_object());
+                semaphores.push(semaphore.clone());
+            }
+
+            let pre = unsafe { command_buffer.inner().gpu_access(queue, &fence, post) };
+            for semaphore in pre.into_iter() {
+                wait_semaphores.push(semaphore.internal_object());
+                wait_stages.push(vk::PIPELINE_STAGE_TOP_OF_PIPE_BIT);     // TODO:
+                resources_wait.push(semaphore.internal_object());
+                semaphores.push(semaphore);
+            }
+
+            command_buffers.push(command_buffer.inner().internal_object());
+        }
+
+        unsafe {
+            let vk = queue.device().pointers();
+
+            let infos = vk::SubmitInfo {
+                sType: vk::STRUCTURE_TYPE_SUBMIT_INFO,
+                pNext: ptr::null(),
+                waitSemaphoreCount: wait_semaphores.len() as u32,
+                pWaitSemaphores: wait_semaphores.as_ptr(),
+                pWaitDstStageMask: wait_stages.as_ptr(),
+                commandBufferCount: command_buffers.len() as u32,
+                pCommandBuffers: command_buffers.as_ptr(),
+                signalSemaphoreCount: signal_semaphores.len() as u32,
+                pSignalSemaphores: signal_semaphores.as_ptr(),
+            };
+
+            let result = check_errors(vk.QueueSubmit(queue.internal_object(), 1, &infos,
+                                                     fence.internal_object()));
+
+            if let Err(err) = result {
+                // the resources have already been told that they are accessed by this
+                // submission ; an empty submission waits on and signals the same semaphores and
+                // signals the fence, so that they don't wait forever for it to finish. If this
+                // fails as well, the device is lost and waiting on the fence returns an error.
+                let wait_stages = vec![vk::PIPELINE_STAGE_TOP_OF_PIPE_BIT; resources_wait.len()];
+                let infos = vk::SubmitInfo {
+                    sType: vk::STRUCTURE_TYPE_SUBMIT_INFO,
+                    pNext: ptr::null(),
+                    waitSemaphoreCount: resources_wait.len() as u32,
+                    pWaitSemaphores: resources_wait.as_ptr(),
+                    pWaitDstStageMask: wait_stages.as_ptr(),
+                    commandBufferCount: 0,
+                    pCommandBuffers: ptr::null(),
+                    signalSemaphoreCount: resources_signal.len() as u32,
+                    pSignalSemaphores: resources_signal.as_ptr(),
+                };
+
+                let _ = vk.QueueSubmit(queue.internal_object(), 1, &infos,
+                                       fence.internal_object());
+                return Err(From::from(err));
+            }
+        }
+
+        for command_buffer in self.command_buffers.iter() {
+            command_buffer.inner().mark_submitted(fence.clone());
+        }
+
+        Ok(Submission::new(fence, semaphores, self.command_buffers))
+    }
+}
+
+/// Error that can happen when submitting command buffers to a queue.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SubmitError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The connection to the device has been lost. The device and all the objects that were
+    /// created from it must be recreated.
+    DeviceLost,
+
+    /// A command buffer or a synchronization object doesn't belong to the same device as the
+    /// queue.
+    WrongDevice,
+
+    /// A command buffer wasn't created from a pool of the family of the queue.
+    WrongQueueFamily,
+
+    /// A command buffer can't be submitted again yet because of its usage. Either it was built
+    /// with `Usage::OneTime` and has already been submitted, or it was built with
+    /// `Usage::MultipleSubmits` and is still being executed, or it is already being submitted
+    /// by another thread or as part of the same submission.
+    CommandBufferInUse,
+
+    /// The pool of a command buffer has been reset since the command buffer was built. The
+    /// command buffer must be reset or recreated.
+    PoolReset,
+
+    /// The fence passed to the submission is already signaled.
+    FenceSignaled,
+}
+
+impl From<OomError> for SubmitError {
+    #[inline]
+    fn from(err: OomError) -> SubmitError {
+        SubmitError::OomError(err)
+    }
+}
+
+impl From<Error> for SubmitError {
+    #[inline]
+    fn from(err: Error) -> SubmitError {
+        match err {
+            err @ Error::OutOfHostMemory => SubmitError::OomError(OomError::from(err)),
+            err @ Error::OutOfDeviceMemory => SubmitError::OomError(OomError::from(err)),
+            // no other error is allowed by the specifications ; if the implementation returns
+            // one anyway, the state of the device can't be trusted anymore
+            _ => SubmitError::DeviceLost,
+        }
+    }
+}
+
+impl error::Error for SubmitError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            SubmitError::OomError(_) => "no memory available",
+            SubmitError::DeviceLost => "the connection to the device has been lost",
+            SubmitError::WrongDevice => "an object doesn't belong to the same device as the \
+                                         queue",
+            SubmitError::WrongQueueFamily => "a command buffer wasn't created from a pool of \
+                                              the family of the queue",
+            SubmitError::CommandBufferInUse => "a command buffer can't be submitted again yet \
+                                                because of its usage",
+            SubmitError::PoolReset => "the pool of a command buffer has been reset",
+            SubmitError::FenceSignaled => "the fence is already signaled",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            SubmitError::OomError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SubmitError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+/// Represents the execution of command buffers by the GPU.
+///
+/// This object keeps alive the command buffers, the fence and the semaphores that are used by
+/// the GPU during the execution.
+///
+/// Destroying a `Submission` blocks until the execution is finished. If you don't want to block,
+/// keep the object alive and check `is_finished()` from time to time.
+#[must_use]
+pub struct Submission {
+    fence: Arc<Fence>,
+
+    // Semaphores that are waited upon or signaled by the submission.
+    semaphores: Vec<Arc<Semaphore>>,
+
+    // Command buffers that are being executed.
+    command_buffers: Vec<Arc<PrimaryCommandBuffer>>,
+}
+
+impl Submission {
+    #[doc(hidden)]
+    #[inline]
+    pub fn new(fence: Arc<Fence>, semaphores: Vec<Arc<Semaphore>>,
+               command_buffers: Vec<Arc<PrimaryCommandBuffer>>) -> Submission
+    {
+        Submission {
+            fence: fence,
+            semaphores: semaphores,
+            command_buffers: command_buffers,
+        }
+    }
+
+    /// Returns true if the GPU has finished executing the command buffers of this submission.
+    ///
+    /// Never returns `FenceWaitError::Timeout`.
+    #[inline]
+    pub fn is_finished(&self) -> Result<bool, FenceWaitError> {
+        self.fence.ready()
+    }
+
+    /// Blocks until the GPU has finished executing the command buffers of this submission, or
+    /// until the number of nanoseconds of the timeout has elapsed.
+    ///
+    /// Returns `true` if the submission is finished, or `false` if the timeout was reached.
+    ///
+    /// Never returns `FenceWaitError::Timeout`.
+    pub fn wait(&self, timeout_ns: u64) -> Result<bool, FenceWaitError> {
+        match self.fence.wait(timeout_ns) {
+            Ok(()) => Ok(true),
+            Err(FenceWaitError::Timeout) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the fence that is signaled when the submission is finished.
+    #[inline]
+    pub fn fence(&self) -> &Arc<Fence> {
+        &self.fence
+    }
+
+    /// Returns the command buffers that are executed by this submission.
+    #[inline]
+    pub fn command_buffers(&self) -> &[Arc<PrimaryCommandBuffer>] {
+        &self.command_buffers
+    }
+}
+
+impl Drop for Submission {
+    #[inline]
+    fn drop(&mut self) {
+        // the command buffers and the semaphores can't be destroyed while the GPU is using them
+        match self.wait(u64::MAX) {
+            // if the device has been lost, the GPU isn't using anything anymore
+            Ok(_) | Err(FenceWaitError::DeviceLost) => (),
+            // FIXME: what to do in case of an out of memory error?
+            Err(_) => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use command_buffer::CommandBufferPool;
+    use command_buffer::PrimaryCommandBufferBuilder;
+    use command_buffer::SubmitBuilder;
+    use command_buffer::SubmitError;
+    use command_buffer::Usage;
+
+    #[test]
+    fn same_command_buffer_twice() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let mut queue = queue.lock().unwrap();
+        let pool = CommandBufferPool::new(&device, &queue.family()).unwrap();
+        let builder = PrimaryCommandBufferBuilder::with_usage(&pool, Usage::MultipleSubmits);
+        let cb = builder.unwrap().build().unwrap();
+
+        match SubmitBuilder::new().add_command_buffer(&cb).add_command_buffer(&cb)
+                                  .submit(&mut queue)
+        {
+            Err(SubmitError::CommandBufferInUse) => (),
+            _ => panic!()
+        }
+
+        // the failed submission must have unlocked the command buffer
+        let _submission = SubmitBuilder::new().add_command_buffer(&cb).submit(&mut queue).unwrap();
+    }
+}
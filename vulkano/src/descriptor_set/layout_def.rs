@@ -16,7 +16,11 @@ pub unsafe trait PipelineLayoutDesc {
     /// be passed when creating a `PipelineLayout` struct.
     type DescriptorSetLayouts;
 
-    /// Not yet implemented. Useless for now.
+    /// Type of the push constants. A value of this type must be passed when you add a draw or
+    /// dispatch command to a command buffer that uses this layout.
+    ///
+    /// Which parts of this value are accessible by which shader stages is described by
+    /// `push_constant_ranges`.
     type PushConstants;
 
     /// Turns the `DescriptorSets` associated type into something vulkano can understand.
@@ -26,10 +30,32 @@ pub unsafe trait PipelineLayoutDesc {
     fn decode_descriptor_set_layouts(&self, Self::DescriptorSetLayouts)
                                      -> Vec<Arc<AbstractDescriptorSetLayout>>;  // TODO: vec is slow
 
+    /// Returns the ranges of `PushConstants` that are accessible by the shaders.
+    ///
+    /// The default implementation returns an empty list, meaning that the push constants are not
+    /// used.
+    #[inline]
+    fn push_constant_ranges(&self) -> Vec<PushConstantRange> {        // TODO: vec is slow
+        Vec::new()
+    }
+
     // FIXME: implement this correctly
     fn is_compatible_with<P>(&self, _: &P) -> bool where P: PipelineLayoutDesc { true }
 }
 
+/// Describes a range of the push constants of a pipeline layout.
+#[derive(Debug, Copy, Clone)]
+pub struct PushConstantRange {
+    /// Offset in bytes of the range within the push constants. Must be a multiple of 4.
+    pub offset: usize,
+
+    /// Size in bytes of the range. Must be a multiple of 4 and not 0.
+    pub size: usize,
+
+    /// Which shader stages are going to access this range.
+    pub stages: ShaderStages,
+}
+
 /// Types that describe a single descriptor set.
 pub unsafe trait DescriptorSetDesc {
     /// Represents a modification of a descriptor set. A parameter of this type must be passed
@@ -84,7 +110,7 @@ pub struct DescriptorDesc {
 
 /// Describes what kind of resource may later be bound to a descriptor.
 // FIXME: add immutable sampler when relevant
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u32)]
 pub enum DescriptorType {
     Sampler = vk::DESCRIPTOR_TYPE_SAMPLER,
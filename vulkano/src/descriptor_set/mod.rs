@@ -41,6 +41,7 @@ pub use self::layout_def::DescriptorWrite;
 pub use self::layout_def::DescriptorBind;
 pub use self::layout_def::DescriptorDesc;
 pub use self::layout_def::DescriptorType;
+pub use self::layout_def::PushConstantRange;
 pub use self::layout_def::ShaderStages;
 pub use self::pool::DescriptorPool;
 pub use self::runtime_desc::RuntimeDesc;
@@ -66,6 +67,16 @@ pub unsafe trait DescriptorSetsCollection {
     fn list(&self) -> Self::Iter;
 
     fn is_compatible_with<P>(&self, pipeline_layout: &Arc<PipelineLayout<P>>) -> bool;
+
+    /// Returns the offsets to use for the dynamic descriptors of the sets, in the same order as
+    /// the sets and as their `dynamic_descriptors()`.
+    ///
+    /// The default implementation returns an empty list, which is only valid if the sets don't
+    /// contain any dynamic descriptor.
+    #[inline]
+    fn dynamic_offsets(&self) -> Vec<u32> {        // TODO: vec is slow
+        Vec::new()
+    }
 }
 
 unsafe impl<T> DescriptorSetsCollection for Arc<DescriptorSet<T>>
@@ -85,6 +96,49 @@ unsafe impl<T> DescriptorSetsCollection for Arc<DescriptorSet<T>>
     }
 }
 
+/// Wraps around a collection of descriptor sets and adds the offsets to use for their
+/// `UniformBufferDynamic` and `StorageBufferDynamic` descriptors.
+///
+/// The offsets must be in the same order as the descriptor sets, and within each set in the order
+/// of the bindings. Each offset must be a multiple of the device's
+/// `min_uniform_buffer_offset_alignment` or `min_storage_buffer_offset_alignment` limit.
+pub struct DynamicOffsets<L> {
+    sets: L,
+    offsets: Vec<u32>,
+}
+
+impl<L> DynamicOffsets<L> where L: DescriptorSetsCollection {
+    /// Builds a new `DynamicOffsets`.
+    #[inline]
+    pub fn new(sets: L, offsets: Vec<u32>) -> DynamicOffsets<L> {
+        DynamicOffsets {
+            sets: sets,
+            offsets: offsets,
+        }
+    }
+}
+
+unsafe impl<L> DescriptorSetsCollection for DynamicOffsets<L>
+    where L: DescriptorSetsCollection
+{
+    type Iter = L::Iter;
+
+    #[inline]
+    fn list(&self) -> Self::Iter {
+        self.sets.list()
+    }
+
+    #[inline]
+    fn is_compatible_with<P>(&self, pipeline_layout: &Arc<PipelineLayout<P>>) -> bool {
+        self.sets.is_compatible_with(pipeline_layout)
+    }
+
+    #[inline]
+    fn dynamic_offsets(&self) -> Vec<u32> {
+        self.offsets.clone()
+    }
+}
+
 /*
 #[macro_export]
 macro_rules! pipeline_layout {
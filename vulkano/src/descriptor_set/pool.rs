@@ -2,6 +2,7 @@ use std::mem;
 use std::ptr;
 use std::sync::Arc;
 
+use device::DebugName;
 use device::Device;
 
 use OomError;
@@ -71,6 +72,15 @@ unsafe impl VulkanObject for DescriptorPool {
     }
 }
 
+unsafe impl DebugName for DescriptorPool {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_DESCRIPTOR_POOL_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl Drop for DescriptorPool {
     #[inline]
     fn drop(&mut self) {
@@ -7,7 +7,10 @@ use descriptor_set::layout_def::PipelineLayoutDesc;
 use descriptor_set::layout_def::DescriptorSetDesc;
 use descriptor_set::layout_def::DescriptorWrite;
 use descriptor_set::layout_def::DescriptorBind;
+use descriptor_set::layout_def::DescriptorType;
+use descriptor_set::layout_def::PushConstantRange;
 use descriptor_set::pool::DescriptorPool;
+use device::DebugName;
 use device::Device;
 
 use OomError;
@@ -21,6 +24,10 @@ pub struct DescriptorSet<S> {
     set: vk::DescriptorSet,
     pool: Arc<DescriptorPool>,
     layout: Arc<DescriptorSetLayout<S>>,
+
+    // Types of the dynamic descriptors of the set, with one entry per array element and in
+    // binding order. A dynamic offset must be provided for each of them when the set is bound.
+    dynamic_descriptors: Vec<DescriptorType>,
 }
 
 impl<S> DescriptorSet<S> where S: DescriptorSetDesc {
@@ -67,10 +74,27 @@ impl<S> DescriptorSet<S> where S: DescriptorSetDesc {
             output
         };
 
+        let dynamic_descriptors = {
+            let mut descriptors = layout.description().descriptors();
+            descriptors.sort_by(|a, b| a.binding.cmp(&b.binding));
+
+            let mut list = Vec::new();
+            for desc in descriptors.into_iter() {
+                match desc.ty {
+                    DescriptorType::UniformBufferDynamic | DescriptorType::StorageBufferDynamic => {
+                        for _ in 0 .. desc.array_count { list.push(desc.ty); }
+                    },
+                    _ => ()
+                }
+            }
+            list
+        };
+
         Ok(Arc::new(DescriptorSet {
             set: set,
             pool: pool.clone(),
             layout: layout.clone(),
+            dynamic_descriptors: dynamic_descriptors,
         }))
     }
 
@@ -106,8 +130,14 @@ impl<S> DescriptorSet<S> where S: DescriptorSetDesc {
             }
         }).collect::<Vec<_>>();
 
+        let descriptors = self.layout.description().descriptors();
+
         // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
         let vk_writes = write.iter().enumerate().map(|(num, write)| {
+            let ty = descriptors.iter().find(|d| d.binding == write.binding)
+                                .map(|d| d.ty.vk_enum())
+                                .unwrap_or(vk::DESCRIPTOR_TYPE_UNIFORM_BUFFER);
+
             vk::WriteDescriptorSet {
                 sType: vk::STRUCTURE_TYPE_WRITE_DESCRIPTOR_SET,
                 pNext: ptr::null(),
@@ -115,7 +145,7 @@ impl<S> DescriptorSet<S> where S: DescriptorSetDesc {
                 dstBinding: write.binding,
                 dstArrayElement: write.array_element,
                 descriptorCount: 1,
-                descriptorType: vk::DESCRIPTOR_TYPE_UNIFORM_BUFFER,     // FIXME:
+                descriptorType: ty,
                 pImageInfo: ptr::null(),        // FIXME:
                 pBufferInfo: if let Some(ref b) = buffer_descriptors[num] { b } else { ptr::null() },
                 pTexelBufferView: ptr::null(),      // FIXME:
@@ -138,6 +168,15 @@ unsafe impl<S> VulkanObject for DescriptorSet<S> {
     }
 }
 
+unsafe impl<S> DebugName for DescriptorSet<S> {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_DESCRIPTOR_SET_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        self.pool.device()
+    }
+}
+
 impl<S> Drop for DescriptorSet<S> {
     #[inline]
     fn drop(&mut self) {
@@ -151,8 +190,20 @@ impl<S> Drop for DescriptorSet<S> {
 
 
 /// Implemented on all `DescriptorSet` objects. Hides the template parameters.
-pub unsafe trait AbstractDescriptorSet: ::VulkanObjectU64 {}
-unsafe impl<S> AbstractDescriptorSet for DescriptorSet<S> {}
+pub unsafe trait AbstractDescriptorSet: ::VulkanObjectU64 {
+    /// Returns the types of the dynamic descriptors of the set, with one entry per array element.
+    ///
+    /// The entries are in the order in which their dynamic offsets must be passed when binding
+    /// the set.
+    fn dynamic_descriptors(&self) -> &[DescriptorType];
+}
+
+unsafe impl<S> AbstractDescriptorSet for DescriptorSet<S> {
+    #[inline]
+    fn dynamic_descriptors(&self) -> &[DescriptorType] {
+        &self.dynamic_descriptors
+    }
+}
 
 /// Describes the layout of all descriptors within a descriptor set.
 pub struct DescriptorSetLayout<S> {
@@ -215,6 +266,15 @@ unsafe impl<S> VulkanObject for DescriptorSetLayout<S> {
     }
 }
 
+unsafe impl<S> DebugName for DescriptorSetLayout<S> {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_DESCRIPTOR_SET_LAYOUT_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl<S> Drop for DescriptorSetLayout<S> {
     #[inline]
     fn drop(&mut self) {
@@ -229,22 +289,57 @@ impl<S> Drop for DescriptorSetLayout<S> {
 pub unsafe trait AbstractDescriptorSetLayout: ::VulkanObjectU64 {}
 unsafe impl<S> AbstractDescriptorSetLayout for DescriptorSetLayout<S> {}
 
-/// A collection of `DescriptorSetLayout` structs.
-// TODO: push constants.
+/// A collection of `DescriptorSetLayout` structs, plus the push constants ranges.
 pub struct PipelineLayout<P> {
     device: Arc<Device>,
     layout: vk::PipelineLayout,
     description: P,
     layouts: Vec<Arc<AbstractDescriptorSetLayout>>,     // TODO: is it necessary to keep the layouts alive? check the specs
+    push_constant_ranges: Vec<PushConstantRange>,
 }
 
 impl<P> PipelineLayout<P> where P: PipelineLayoutDesc {
     /// Creates a new `PipelineLayout`.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the offset or the size of a push constants range is not a multiple of 4, or
+    ///   if the size is 0.
+    /// - Panicks if a push constants range is out of range of `P::PushConstants` or exceeds the
+    ///   device's `max_push_constants_size` limit.
+    /// - Panicks if a shader stage is part of multiple push constants ranges.
+    ///
     pub fn new(device: &Arc<Device>, description: P, layouts: P::DescriptorSetLayouts)
                -> Result<Arc<PipelineLayout<P>>, OomError>
     {
         let vk = device.pointers();
 
+        let push_constant_ranges = description.push_constant_ranges();
+        // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
+        let push_constant_ranges_raw = {
+            let limit = device.physical_device().limits().max_push_constants_size() as usize;
+            let mut stages_so_far = 0;
+
+            push_constant_ranges.iter().map(|range| {
+                assert!(range.offset % 4 == 0);
+                assert!(range.size % 4 == 0);
+                assert!(range.size != 0);
+                assert!(range.offset + range.size <= mem::size_of::<P::PushConstants>());
+                assert!(range.offset + range.size <= limit);
+
+                let stages: vk::ShaderStageFlags = range.stages.into();
+                assert!(stages != 0);
+                assert!(stages & stages_so_far == 0);
+                stages_so_far |= stages;
+
+                vk::PushConstantRange {
+                    stageFlags: stages,
+                    offset: range.offset as u32,
+                    size: range.size as u32,
+                }
+            }).collect::<Vec<_>>()
+        };
+
         let layouts = description.decode_descriptor_set_layouts(layouts);
         // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
         let layouts_ids = layouts.iter().map(|l| {
@@ -259,8 +354,8 @@ impl<P> PipelineLayout<P> where P: PipelineLayoutDesc {
                 flags: 0,   // reserved
                 setLayoutCount: layouts_ids.len() as u32,
                 pSetLayouts: layouts_ids.as_ptr(),
-                pushConstantRangeCount: 0,      // TODO: unimplemented
-                pPushConstantRanges: ptr::null(),    // TODO: unimplemented
+                pushConstantRangeCount: push_constant_ranges_raw.len() as u32,
+                pPushConstantRanges: push_constant_ranges_raw.as_ptr(),
             };
 
             let mut output = mem::uninitialized();
@@ -274,13 +369,28 @@ impl<P> PipelineLayout<P> where P: PipelineLayoutDesc {
             layout: layout,
             description: description,
             layouts: layouts,
+            push_constant_ranges: push_constant_ranges,
         }))
     }
+}
 
+impl<P> PipelineLayout<P> {
     #[inline]
     pub fn description(&self) -> &P {
         &self.description
     }
+
+    /// Returns the push constants ranges that were returned by the description.
+    #[inline]
+    pub fn push_constant_ranges(&self) -> &[PushConstantRange] {
+        &self.push_constant_ranges
+    }
+
+    /// Returns the layouts of the descriptor sets, starting from set 0.
+    #[inline]
+    pub fn descriptor_set_layouts(&self) -> &[Arc<AbstractDescriptorSetLayout>] {
+        &self.layouts
+    }
 }
 
 unsafe impl<P> VulkanObject for PipelineLayout<P> {
@@ -292,12 +402,21 @@ unsafe impl<P> VulkanObject for PipelineLayout<P> {
     }
 }
 
+unsafe impl<P> DebugName for PipelineLayout<P> {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_PIPELINE_LAYOUT_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl<P> Drop for PipelineLayout<P> {
     #[inline]
     fn drop(&mut self) {
         unsafe {
             let vk = self.device.pointers();
-            vk.DestroyDescriptorSetLayout(self.device.internal_object(), self.layout, ptr::null());
+            vk.DestroyPipelineLayout(self.device.internal_object(), self.layout, ptr::null());
         }
     }
 }
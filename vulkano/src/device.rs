@@ -3,6 +3,7 @@
 //! The `Device` is one of the most important objects of Vulkan. Creating a `Device` is required
 //! before you can create buffers, textures, shaders, etc.
 //!
+use std::ffi::CStr;
 use std::ffi::CString;
 use std::fmt;
 use std::error;
@@ -18,6 +19,7 @@ use instance::QueueFamily;
 
 use Error;
 use OomError;
+use VulkanHandle;
 use VulkanObject;
 use VulkanPointers;
 use check_errors;
@@ -30,6 +32,9 @@ pub struct Device {
     device: vk::Device,
     vk: vk::DevicePointers,
     features: Features,
+
+    // True if the `VK_EXT_debug_marker` extension is enabled.
+    debug_markers: bool,
 }
 
 impl Device {
@@ -47,6 +52,9 @@ impl Device {
     ///   queue with a lower value. Note however that no guarantee can be made on the way the
     ///   priority value is handled by the implementation.
     ///
+    /// The `VK_EXT_debug_marker` extension is enabled if the physical device supports it, which
+    /// is usually the case when the application runs inside a debugging tool like RenderDoc.
+    ///
     /// # Panic
     ///
     /// - Panicks if one of the requested features is not supported by the physical device.
@@ -79,8 +87,32 @@ impl Device {
             layer.as_ptr()
         }).collect::<Vec<_>>();
 
+        // debug markers are only enabled if they are supported, as they are optional, and if the
+        // debug report extension that they depend on is enabled on the instance
+        let debug_markers = phys.instance().debug_report_enabled() && unsafe {
+            let mut num = 0;
+            try!(check_errors(vk_i.EnumerateDeviceExtensionProperties(phys.internal_object(),
+                                                                      ptr::null(), &mut num,
+                                                                      ptr::null_mut())));
+
+            let mut properties: Vec<vk::ExtensionProperties> = Vec::with_capacity(num as usize);
+            try!(check_errors(vk_i.EnumerateDeviceExtensionProperties(phys.internal_object(),
+                                                                      ptr::null(), &mut num,
+                                                                      properties.as_mut_ptr())));
+            properties.set_len(num as usize);
+
+            properties.iter().any(|p| {
+                CStr::from_ptr(p.extensionName.as_ptr()).to_bytes() == b"VK_EXT_debug_marker"
+            })
+        };
+
+        let mut extensions = vec!["VK_KHR_swapchain"];
+        if debug_markers {
+            extensions.push("VK_EXT_debug_marker");
+        }
+
         // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
-        let extensions = ["VK_KHR_swapchain"].iter().map(|&ext| {
+        let extensions = extensions.iter().map(|&ext| {
             // FIXME: check whether each extension is supported
             CString::new(ext).unwrap()
         }).collect::<Vec<_>>();
@@ -156,6 +188,7 @@ impl Device {
             device: device,
             vk: vk,
             features: requested_features.clone(),
+            debug_markers: debug_markers,
         });
 
         // querying the queues
@@ -198,6 +231,38 @@ impl Device {
     pub fn enabled_features(&self) -> &Features {
         &self.features
     }
+
+    /// Returns true if the `VK_EXT_debug_marker` extension is enabled on the device.
+    ///
+    /// If it isn't, giving names to objects and adding labels to command buffers do nothing.
+    #[inline]
+    pub fn debug_markers_enabled(&self) -> bool {
+        self.debug_markers
+    }
+
+    /// Gives a name to an object of this device, which debugging tools and the validation layers
+    /// then display. Does nothing if the debug markers are not enabled.
+    ///
+    /// `ty` must be the `DEBUG_REPORT_OBJECT_TYPE_*` constant that corresponds to the object.
+    #[doc(hidden)]
+    pub unsafe fn set_object_name(&self, ty: u32, object: u64, name: &str) {
+        if !self.debug_markers {
+            return;
+        }
+
+        let name = debug_name(name);
+
+        let infos = vk::DebugMarkerObjectNameInfoEXT {
+            sType: vk::STRUCTURE_TYPE_DEBUG_MARKER_OBJECT_NAME_INFO_EXT,
+            pNext: ptr::null(),
+            objectType: ty,
+            object: object,
+            pObjectName: name.as_ptr(),
+        };
+
+        // failing to name an object isn't worth reporting an error
+        let _ = self.vk.DebugMarkerSetObjectNameEXT(self.device, &infos);
+    }
 }
 
 impl fmt::Debug for Device {
@@ -235,6 +300,40 @@ impl Drop for Device {
     }
 }
 
+/// Objects of a device that can be given a name, which is then displayed by debugging tools and
+/// the validation layers.
+///
+/// Giving a name to an object does nothing if the debug markers aren't enabled on the device.
+///
+/// # Safety
+///
+/// - `OBJECT_TYPE` must be the `DEBUG_REPORT_OBJECT_TYPE_*` value that corresponds to the object.
+/// - `device` must return the device that owns the object.
+///
+pub unsafe trait DebugName: VulkanObject {
+    /// Type of the object, as passed to the debug markers extension.
+    const OBJECT_TYPE: u32;
+
+    /// Returns the device that owns the object.
+    fn device(&self) -> &Arc<Device>;
+
+    /// Gives a name to the object.
+    #[inline]
+    fn set_name(&self, name: &str) where Self::Object: VulkanHandle {
+        unsafe {
+            let object = VulkanObject::internal_object(self).value();
+            DebugName::device(self).set_object_name(Self::OBJECT_TYPE, object, name);
+        }
+    }
+}
+
+/// Turns a name into a string suitable for the debug markers. Interior nul characters are
+/// removed instead of causing an error.
+#[doc(hidden)]
+pub fn debug_name(name: &str) -> CString {
+    CString::new(name.replace('\0', "")).unwrap()
+}
+
 /// Error that can be returned when creating a device.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum DeviceCreationError {
@@ -316,3 +415,12 @@ unsafe impl VulkanObject for Queue {
         self.queue
     }
 }
+
+unsafe impl DebugName for Queue {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_QUEUE_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
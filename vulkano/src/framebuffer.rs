@@ -29,6 +29,7 @@ use std::option::IntoIter as OptionIntoIter;
 use std::ptr;
 use std::sync::Arc;
 
+use device::DebugName;
 use device::Device;
 use formats::Format;
 use formats::FormatMarker;
@@ -655,6 +656,20 @@ impl<L> RenderPass<L> where L: RenderPassLayout {
     pub fn layout(&self) -> &L {
         &self.layout
     }
+
+    /// Returns the granularity of the render area for this renderpass, by calling
+    /// `vkGetRenderAreaGranularity`.
+    ///
+    /// The offset and the dimensions of the render area of a renderpass must be multiples of
+    /// this granularity, except when the render area touches the edges of the framebuffer.
+    pub fn granularity(&self) -> [u32; 2] {
+        unsafe {
+            let vk = self.device.pointers();
+            let mut out = mem::uninitialized();
+            vk.GetRenderAreaGranularity(self.device.internal_object(), self.renderpass, &mut out);
+            [out.width, out.height]
+        }
+    }
 }
 
 unsafe impl<L> VulkanObject for RenderPass<L> {
@@ -666,6 +681,15 @@ unsafe impl<L> VulkanObject for RenderPass<L> {
     }
 }
 
+unsafe impl<L> DebugName for RenderPass<L> {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_RENDER_PASS_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl<L> Drop for RenderPass<L> {
     #[inline]
     fn drop(&mut self) {
@@ -846,6 +870,15 @@ unsafe impl<L> VulkanObject for Framebuffer<L> {
     }
 }
 
+unsafe impl<L> DebugName for Framebuffer<L> {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_FRAMEBUFFER_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl<L> Drop for Framebuffer<L> {
     #[inline]
     fn drop(&mut self) {
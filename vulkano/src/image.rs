@@ -20,6 +20,7 @@ use std::ptr;
 use std::sync::Arc;
 
 use command_buffer::CommandBufferPool;
+use device::DebugName;
 use device::Device;
 use device::Queue;
 use formats::FormatMarker;
@@ -307,6 +308,12 @@ impl<Ty, F, M> Image<Ty, F, M>
         }
     }
 
+    /// Returns the device used to create this image.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
     /// Returns the dimensions of this image.
     #[inline]
     pub fn dimensions(&self) -> Ty::Dimensions {
@@ -393,6 +400,17 @@ unsafe impl<Ty, F, M> VulkanObject for Image<Ty, F, M>
     }
 }
 
+unsafe impl<Ty, F, M> DebugName for Image<Ty, F, M>
+    where Ty: ImageTypeMarker
+{
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_IMAGE_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 unsafe impl<Ty, F, M> Resource for Image<Ty, F, M>
     where Ty: ImageTypeMarker, M: MemorySourceChunk
 {
@@ -721,6 +739,17 @@ unsafe impl<Ty, F, M> VulkanObject for ImageView<Ty, F, M>
     }
 }
 
+unsafe impl<Ty, F, M> DebugName for ImageView<Ty, F, M>
+    where Ty: ImageTypeMarker
+{
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_IMAGE_VIEW_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.image.device
+    }
+}
+
 unsafe impl<Ty, F, M> Resource for ImageView<Ty, F, M>
     where Ty: ImageTypeMarker, M: MemorySourceChunk
 {
@@ -103,8 +103,23 @@ impl Instance {
             layer.as_ptr()
         }).collect::<Vec<_>>();
 
+        // the debug report extension is only enabled if it is supported, either by the
+        // implementation or by one of the layers, as it is optional
+        let debug_report = unsafe {
+            let mut supported = try!(extension_supported(ptr::null(), b"VK_EXT_debug_report"));
+            for &layer in layers.iter() {
+                supported = supported || try!(extension_supported(layer, b"VK_EXT_debug_report"));
+            }
+            supported
+        };
+
+        let mut extensions = vec!["VK_KHR_surface", "VK_KHR_swapchain", "VK_KHR_win32_surface"];
+        if debug_report {
+            extensions.push("VK_EXT_debug_report");
+        }
+
         // TODO: allocate on stack instead (https://github.com/rust-lang/rfcs/issues/618)
-        let extensions = ["VK_KHR_surface", "VK_KHR_swapchain", "VK_KHR_win32_surface", "VK_EXT_debug_report"].iter().map(|&ext| {
+        let extensions = extensions.iter().map(|&ext| {
             // FIXME: check whether each extension is supported
             CString::new(ext).unwrap()
         }).collect::<Vec<_>>();
@@ -141,31 +156,36 @@ impl Instance {
 
         // Creating the debug report callback.
         // TODO: should be optional
-        let debug_report = unsafe {
-            extern "system" fn callback(_: vk::DebugReportFlagsEXT, _: vk::DebugReportObjectTypeEXT,
-                                        _: u64, _: usize, _: i32, layer_prefix: *const c_char,
-                                        message: *const c_char, _: *mut c_void) -> u32
-            {
-                unsafe {
-                    let message = CStr::from_ptr(message).to_str()
-                                                    .expect("debug callback message not utf-8");
-                    println!("Debug callback message: {:?}", message);
-                    vk::DEBUG_REPORT_ERROR_NONE_EXT
+        let debug_report = if debug_report {
+            Some(unsafe {
+                extern "system" fn callback(_: vk::DebugReportFlagsEXT,
+                                            _: vk::DebugReportObjectTypeEXT, _: u64, _: usize,
+                                            _: i32, layer_prefix: *const c_char,
+                                            message: *const c_char, _: *mut c_void) -> u32
+                {
+                    unsafe {
+                        let message = CStr::from_ptr(message).to_str()
+                                                .expect("debug callback message not utf-8");
+                        println!("Debug callback message: {:?}", message);
+                        vk::DEBUG_REPORT_ERROR_NONE_EXT
+                    }
                 }
-            }
 
-            let infos = vk::DebugReportCallbackCreateInfoEXT {
-                sType: vk::STRUCTURE_TYPE_DEBUG_REPORT_CREATE_INFO_EXT,
-                pNext: ptr::null(),
-                flags: 0,   // reserved
-                pfnCallback: callback,
-                pUserData: ptr::null_mut(),
-            };
+                let infos = vk::DebugReportCallbackCreateInfoEXT {
+                    sType: vk::STRUCTURE_TYPE_DEBUG_REPORT_CREATE_INFO_EXT,
+                    pNext: ptr::null(),
+                    flags: 0,   // reserved
+                    pfnCallback: callback,
+                    pUserData: ptr::null_mut(),
+                };
 
-            let mut output = mem::uninitialized();
-            try!(check_errors(vk.CreateDebugReportCallbackEXT(instance, &infos,
-                                                              ptr::null(), &mut output)));
-            output
+                let mut output = mem::uninitialized();
+                try!(check_errors(vk.CreateDebugReportCallbackEXT(instance, &infos,
+                                                                  ptr::null(), &mut output)));
+                output
+            })
+        } else {
+            None
         };
 
         // Enumerating all physical devices.
@@ -227,13 +247,21 @@ impl Instance {
 
         Ok(Arc::new(Instance {
             instance: instance,
-            debug_report: Some(debug_report),
+            debug_report: debug_report,
             //alloc: None,
             physical_devices: physical_devices,
             vk: vk,
         }))
     }
 
+    /// Returns true if the `VK_EXT_debug_report` extension is enabled on the instance.
+    ///
+    /// The debug markers can only be enabled on the devices if this extension is enabled.
+    #[inline]
+    pub fn debug_report_enabled(&self) -> bool {
+        self.debug_report.is_some()
+    }
+
     /*/// Same as `new`, but provides an allocator that will be used by the Vulkan library whenever
     /// it needs to allocate memory on the host.
     ///
@@ -281,6 +309,21 @@ impl Drop for Instance {
     }
 }
 
+// Returns true if the instance extension is supported by the implementation, or by the given
+// layer if `layer` isn't null.
+unsafe fn extension_supported(layer: *const c_char, name: &[u8]) -> Result<bool, Error> {
+    let mut num = 0;
+    try!(check_errors(VK_ENTRY.EnumerateInstanceExtensionProperties(layer, &mut num,
+                                                                    ptr::null_mut())));
+
+    let mut properties: Vec<vk::ExtensionProperties> = Vec::with_capacity(num as usize);
+    try!(check_errors(VK_ENTRY.EnumerateInstanceExtensionProperties(layer, &mut num,
+                                                                    properties.as_mut_ptr())));
+    properties.set_len(num as usize);
+
+    Ok(properties.iter().any(|p| CStr::from_ptr(p.extensionName.as_ptr()).to_bytes() == name))
+}
+
 /// Information that can be given to the Vulkan driver so that it can identify your application.
 pub struct ApplicationInfo<'a> {
     /// Name of the application.
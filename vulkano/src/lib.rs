@@ -57,7 +57,7 @@ pub mod image;
 pub mod instance;
 pub mod memory;
 pub mod pipeline;
-//pub mod query;
+pub mod query;
 pub mod sampler;
 pub mod shader;
 pub mod swapchain;
@@ -121,6 +121,23 @@ unsafe impl<T> VulkanObjectUsize for T where T: VulkanObject<Object = usize> {
     fn internal_object(&self) -> usize { VulkanObject::internal_object(self) }
 }
 
+/// Vulkan handles, which are either pointers or 64-bits integers depending on the object.
+#[doc(hidden)]
+pub unsafe trait VulkanHandle: Copy {
+    /// Returns the value of the handle.
+    fn value(self) -> u64;
+}
+
+unsafe impl VulkanHandle for u64 {
+    #[inline]
+    fn value(self) -> u64 { self }
+}
+
+unsafe impl VulkanHandle for usize {
+    #[inline]
+    fn value(self) -> u64 { self as u64 }
+}
+
 /// Gives access to the Vulkan function pointers stored in this object.
 trait VulkanPointers {
     /// The struct that provides access to the function pointers.
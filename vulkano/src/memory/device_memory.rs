@@ -4,6 +4,7 @@ use std::os::raw::c_void;
 use std::sync::Arc;
 
 use instance::MemoryType;
+use device::DebugName;
 use device::Device;
 use OomError;
 use VulkanObject;
@@ -114,6 +115,15 @@ unsafe impl VulkanObject for DeviceMemory {
     }
 }
 
+unsafe impl DebugName for DeviceMemory {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_DEVICE_MEMORY_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl Drop for DeviceMemory {
     #[inline]
     fn drop(&mut self) {
@@ -10,7 +10,11 @@
 //!   very efficient).
 //! - `MemorySource`, which is the same as `&Arc<Device>` except that it will use the
 //!   already-allocated block.
-//! - ... needs more ...
+//! - `&Arc<MemoryPool>`, which allocates large blocks of memory and sub-allocates the buffers
+//!   and images from them. Use this when you have a lot of resources.
+//! 
+//! For data that is uploaded every frame, like uniforms, `UploadBuffer` returns slices of large
+//! persistently-mapped buffers instead of creating a new buffer each time.
 //! 
 //! # Synchronization
 //! 
@@ -48,6 +52,7 @@ use std::slice;
 use std::sync::Arc;
 
 use sync::Fence;
+use sync::FenceWaitError;
 use sync::Semaphore;
 
 use device::Device;
@@ -61,9 +66,16 @@ pub use self::single::DeviceLocal;
 pub use self::single::DeviceLocalChunk;
 pub use self::single::HostVisible;
 pub use self::single::HostVisibleChunk;
+pub use self::pool::DEFAULT_BLOCK_SIZE;
+pub use self::pool::MemoryPool;
+pub use self::pool::MemoryPoolChunk;
+pub use self::upload::UploadBuffer;
+pub use self::upload::UploadChunk;
 
 mod device_memory;
+mod pool;
 mod single;
+mod upload;
 
 /// Trait for memory objects that can be accessed from the CPU.
 pub unsafe trait CpuAccessible<'a, T: ?Sized> {
@@ -71,9 +83,10 @@ pub unsafe trait CpuAccessible<'a, T: ?Sized> {
 
     /// Gives a read access to the content of the buffer.
     ///
-    /// If the buffer is in use by the GPU, blocks until it is available.
-    // TODO: what happens if timeout is reached? a panic?
-    fn read(&'a self, timeout_ns: u64) -> Self::Read;
+    /// If the buffer is in use by the GPU, blocks until it is available. Returns
+    /// `FenceWaitError::Timeout` if it is still in use after the number of nanoseconds of the
+    /// timeout has elapsed.
+    fn read(&'a self, timeout_ns: u64) -> Result<Self::Read, FenceWaitError>;
 
     /// Tries to give a read access to the content of the buffer.
     ///
@@ -87,9 +100,10 @@ pub unsafe trait CpuWriteAccessible<'a, T: ?Sized>: CpuAccessible<'a, T> {
 
     /// Gives a write access to the content of the buffer.
     ///
-    /// If the buffer is in use by the GPU, blocks until it is available.
-    // TODO: what happens if timeout is reached? a panic?
-    fn write(&'a self, timeout_ns: u64) -> Self::Write;
+    /// If the buffer is in use by the GPU, blocks until it is available. Returns
+    /// `FenceWaitError::Timeout` if it is still in use after the number of nanoseconds of the
+    /// timeout has elapsed.
+    fn write(&'a self, timeout_ns: u64) -> Result<Self::Write, FenceWaitError>;
 
     /// Tries to give a write access to the content of the buffer.
     ///
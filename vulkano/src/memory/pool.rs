@@ -0,0 +1,365 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use memory::ChunkProperties;
+use memory::ChunkRange;
+use memory::DeviceMemory;
+use memory::MemorySource;
+use memory::MemorySourceChunk;
+use sync::Fence;
+use sync::Semaphore;
+
+use device::Device;
+use device::Queue;
+
+use OomError;
+use VulkanObject;
+
+/// Size of the blocks that are allocated by default by a `MemoryPool`.
+pub const DEFAULT_BLOCK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Memory source that allocates large blocks of memory and sub-allocates chunks from them.
+///
+/// Contrary to `DeviceLocal`, this doesn't call `vkAllocateMemory` for each buffer or image,
+/// which would quickly reach the `max_memory_allocation_count` limit of the device.
+///
+/// There is one list of blocks per memory type. Allocations that are larger than the size of a
+/// block get a dedicated block. The blocks are kept alive by the chunks, and are freed when both
+/// the pool and all their chunks are destroyed.
+///
+/// Since the pool doesn't know whether a chunk is going to be used by a buffer or by an image,
+/// all the chunks are aligned and padded to the `buffer_image_granularity` limit of the device.
+/// This guarantees that linear and non-linear resources never share a page.
+///
+/// The memory is not accessible from the CPU, and is locked globally per chunk like with
+/// `DeviceLocal`.
+///
+/// # Example
+///
+/// ```ignore
+/// let pool = MemoryPool::new(&device);
+/// let buffer = Buffer::<[u32], _>::array(&device, 128, &Usage::all(), &pool, &queue).unwrap();
+/// ```
+pub struct MemoryPool {
+    device: Arc<Device>,
+    block_size: usize,
+    granularity: usize,
+    // Blocks of memory, indexed by memory type ID.
+    blocks: Mutex<HashMap<u32, Vec<Arc<Block>>>>,
+}
+
+impl MemoryPool {
+    /// Builds a new pool that allocates blocks of `DEFAULT_BLOCK_SIZE` bytes.
+    #[inline]
+    pub fn new(device: &Arc<Device>) -> Arc<MemoryPool> {
+        MemoryPool::with_block_size(device, DEFAULT_BLOCK_SIZE)
+    }
+
+    /// Builds a new pool that allocates blocks of `block_size` bytes.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `block_size` is 0.
+    ///
+    pub fn with_block_size(device: &Arc<Device>, block_size: usize) -> Arc<MemoryPool> {
+        assert!(block_size != 0);
+
+        let granularity = device.physical_device().limits().buffer_image_granularity() as usize;
+
+        Arc::new(MemoryPool {
+            device: device.clone(),
+            block_size: block_size,
+            granularity: cmp::max(granularity, 1),
+            blocks: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Returns the device used to create this pool.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Returns the size of the blocks that are allocated by this pool.
+    #[inline]
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Returns the number of blocks of memory that are currently allocated by this pool.
+    #[inline]
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.lock().unwrap().values().fold(0, |n, b| n + b.len())
+    }
+}
+
+unsafe impl<'a> MemorySource for &'a Arc<MemoryPool> {
+    type Chunk = MemoryPoolChunk;
+
+    #[inline]
+    fn is_sparse(&self) -> bool {
+        false
+    }
+
+    fn allocate(self, device: &Arc<Device>, size: usize, alignment: usize, memory_type_bits: u32)
+                -> Result<MemoryPoolChunk, OomError>
+    {
+        assert_eq!(device.internal_object(), self.device.internal_object());
+
+        // picking a device-local memory type if possible ; if no memory type is suitable, there
+        // is no memory that we can allocate
+        let mem_ty = {
+            let mut types = device.physical_device().memory_types()
+                                  .filter(|t| (memory_type_bits & (1 << t.id())) != 0);
+            let first = match types.next() {
+                Some(t) => t,
+                None => return Err(OomError::OutOfDeviceMemory),
+            };
+            if first.is_device_local() {
+                first
+            } else {
+                types.find(|t| t.is_device_local()).unwrap_or(first)
+            }
+        };
+
+        let alignment = cmp::max(cmp::max(alignment, 1), self.granularity);
+        let size = align(cmp::max(size, 1), self.granularity);
+
+        let mut blocks = self.blocks.lock().unwrap();
+        let blocks = blocks.entry(mem_ty.id()).or_insert_with(Vec::new);
+
+        for block in blocks.iter() {
+            if let Some(offset) = block.free.lock().unwrap().allocate(size, alignment) {
+                return Ok(MemoryPoolChunk::new(block.clone(), offset, size));
+            }
+        }
+
+        // no block has enough space left ; the memory returned by the implementation is
+        // guaranteed to fulfill any alignment requirement, so the chunk can start at 0
+        let block_size = cmp::max(size, align(self.block_size, self.granularity));
+        let memory = try!(DeviceMemory::alloc(device, &mem_ty, block_size));
+
+        let mut free = FreeList::new(block_size);
+        let offset = free.allocate(size, alignment).unwrap();
+        debug_assert_eq!(offset, 0);
+
+        let block = Arc::new(Block {
+            memory: memory,
+            free: Mutex::new(free),
+        });
+
+        blocks.push(block.clone());
+        Ok(MemoryPoolChunk::new(block, offset, size))
+    }
+}
+
+// A block of memory allocated by a `MemoryPool`.
+struct Block {
+    memory: DeviceMemory,
+    free: Mutex<FreeList>,
+}
+
+/// A chunk allocated from a `MemoryPool`. The range is given back to the pool when the chunk is
+/// destroyed.
+pub struct MemoryPoolChunk {
+    block: Arc<Block>,
+    offset: usize,
+    size: usize,
+    semaphore: Mutex<Option<Arc<Semaphore>>>,
+}
+
+impl MemoryPoolChunk {
+    #[inline]
+    fn new(block: Arc<Block>, offset: usize, size: usize) -> MemoryPoolChunk {
+        MemoryPoolChunk {
+            block: block,
+            offset: offset,
+            size: size,
+            semaphore: Mutex::new(None),
+        }
+    }
+}
+
+unsafe impl MemorySourceChunk for MemoryPoolChunk {
+    #[inline]
+    unsafe fn gpu_access(&self, _write: bool, _range: ChunkRange, _: &mut Queue,
+                         _: Option<Arc<Fence>>, mut semaphore: Option<Arc<Semaphore>>)
+                         -> Option<Arc<Semaphore>>
+    {
+        assert!(semaphore.is_some());
+
+        let mut self_semaphore = self.semaphore.lock().unwrap();
+        mem::swap(&mut *self_semaphore, &mut semaphore);
+
+        semaphore
+    }
+
+    #[inline]
+    fn requires_fence(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn properties(&self) -> ChunkProperties {
+        ChunkProperties::Regular {
+            memory: &self.block.memory,
+            offset: self.offset,
+            size: self.size,
+        }
+    }
+
+    #[inline]
+    fn may_alias(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for MemoryPoolChunk {
+    #[inline]
+    fn drop(&mut self) {
+        self.block.free.lock().unwrap().free(self.offset .. self.offset + self.size);
+    }
+}
+
+// List of the ranges of a block that are not in use, sorted by offset. Adjacent ranges are
+// always merged.
+#[derive(Debug)]
+struct FreeList {
+    ranges: Vec<Range<usize>>,
+}
+
+impl FreeList {
+    #[inline]
+    fn new(size: usize) -> FreeList {
+        FreeList {
+            ranges: vec![0 .. size],
+        }
+    }
+
+    // Finds the first free range that can contain `size` bytes at the given alignment, and
+    // returns the offset of the allocation.
+    fn allocate(&mut self, size: usize, alignment: usize) -> Option<usize> {
+        let found = self.ranges.iter().enumerate().filter_map(|(n, range)| {
+            let start = align(range.start, alignment);
+            if start + size <= range.end { Some((n, start)) } else { None }
+        }).next();
+
+        let (n, start) = match found {
+            Some(f) => f,
+            None => return None,
+        };
+
+        // splitting the range in up to two parts, before and after the allocation
+        let range = self.ranges.remove(n);
+        if start + size != range.end {
+            self.ranges.insert(n, start + size .. range.end);
+        }
+        if range.start != start {
+            self.ranges.insert(n, range.start .. start);
+        }
+
+        Some(start)
+    }
+
+    // Gives back a range that was returned by `allocate`.
+    fn free(&mut self, range: Range<usize>) {
+        let pos = self.ranges.iter().position(|r| r.start >= range.end)
+                                    .unwrap_or(self.ranges.len());
+        debug_assert!(pos == 0 || self.ranges[pos - 1].end <= range.start);
+
+        let merge_prev = pos != 0 && self.ranges[pos - 1].end == range.start;
+        let merge_next = pos != self.ranges.len() && self.ranges[pos].start == range.end;
+
+        match (merge_prev, merge_next) {
+            (true, true) => {
+                let next = self.ranges.remove(pos);
+                self.ranges[pos - 1].end = next.end;
+            },
+            (true, false) => self.ranges[pos - 1].end = range.end,
+            (false, true) => self.ranges[pos].start = range.start,
+            (false, false) => self.ranges.insert(pos, range),
+        }
+    }
+}
+
+// Rounds `value` up to a multiple of `alignment`.
+#[inline]
+fn align(value: usize, alignment: usize) -> usize {
+    (value + alignment - 1) / alignment * alignment
+}
+
+#[cfg(test)]
+mod tests {
+    use memory::ChunkProperties;
+    use memory::MemoryPool;
+    use memory::MemorySource;
+    use memory::MemorySourceChunk;
+    use super::FreeList;
+
+    #[test]
+    fn free_list_reuse() {
+        let mut list = FreeList::new(1024);
+        let a = list.allocate(100, 64).unwrap();
+        let b = list.allocate(100, 64).unwrap();
+        assert_eq!(a, 0);
+        assert_eq!(b, 128);
+
+        list.free(a .. a + 100);
+        assert_eq!(list.allocate(64, 64), Some(0));
+        list.free(0 .. 64);
+        list.free(b .. b + 100);
+        assert_eq!(list.ranges, vec![0 .. 1024]);
+    }
+
+    #[test]
+    fn free_list_full() {
+        let mut list = FreeList::new(256);
+        assert_eq!(list.allocate(256, 1), Some(0));
+        assert_eq!(list.allocate(1, 1), None);
+    }
+
+    #[test]
+    fn sub_allocate() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = MemoryPool::with_block_size(&device, 1024 * 1024);
+
+        let a = (&pool).allocate(&device, 1000, 256, !0).unwrap();
+        let b = (&pool).allocate(&device, 1000, 256, !0).unwrap();
+        assert_eq!(pool.num_blocks(), 1);
+
+        let (oa, ob) = match (a.properties(), b.properties()) {
+            (ChunkProperties::Regular { offset: oa, .. },
+             ChunkProperties::Regular { offset: ob, .. }) => (oa, ob),
+            _ => panic!()
+        };
+
+        assert_eq!(oa % 256, 0);
+        assert_eq!(ob % 256, 0);
+        assert!(oa + 1000 <= ob || ob + 1000 <= oa);
+    }
+
+    #[test]
+    fn large_allocation() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = MemoryPool::with_block_size(&device, 1024);
+
+        let chunk = (&pool).allocate(&device, 4096, 1, !0).unwrap();
+        match chunk.properties() {
+            ChunkProperties::Regular { size, .. } => assert!(size >= 4096),
+            _ => panic!()
+        }
+    }
+
+    #[test]
+    fn blocks_kept_alive() {
+        let (device, _) = gfx_dev_and_queue!();
+        let pool = MemoryPool::new(&device);
+        let chunk = (&pool).allocate(&device, 16, 1, !0).unwrap();
+        drop(pool);
+        drop(chunk);
+    }
+}
@@ -17,6 +17,7 @@ use memory::DeviceMemory;
 use memory::MappedDeviceMemory;
 use memory::ChunkRange;
 use sync::Fence;
+use sync::FenceWaitError;
 use sync::Semaphore;
 
 use device::Device;
@@ -188,7 +189,7 @@ unsafe impl<'a, T: ?Sized + 'a> CpuAccessible<'a, T> for HostVisibleChunk
     type Read = GpuAccess<'a, T>;
 
     #[inline]
-    fn read(&'a self, timeout_ns: u64) -> GpuAccess<'a, T> {
+    fn read(&'a self, timeout_ns: u64) -> Result<GpuAccess<'a, T>, FenceWaitError> {
         self.write(timeout_ns)
     }
 
@@ -204,13 +205,13 @@ unsafe impl<'a, T: ?Sized + 'a> CpuWriteAccessible<'a, T> for HostVisibleChunk
     type Write = GpuAccess<'a, T>;
 
     #[inline]
-    fn write(&'a self, timeout_ns: u64) -> GpuAccess<'a, T> {
+    fn write(&'a self, timeout_ns: u64) -> Result<GpuAccess<'a, T>, FenceWaitError> {
         let vk = self.mem.memory().device().pointers();
         let pointer = T::ref_from_ptr(self.mem.mapping_pointer(), self.mem.memory().size()).unwrap();       // TODO: error
 
         let mut lock = self.lock.lock().unwrap();
         if let Some(ref fence) = lock.1 {
-            fence.wait(timeout_ns).unwrap();        // FIXME: error
+            try!(fence.wait(timeout_ns));
         }
         lock.1 = None;
 
@@ -230,12 +231,12 @@ unsafe impl<'a, T: ?Sized + 'a> CpuWriteAccessible<'a, T> for HostVisibleChunk
             }
         }
 
-        GpuAccess {
+        Ok(GpuAccess {
             mem: &self.mem,
             coherent: self.coherent,
             guard: lock,
             pointer: pointer,
-        }
+        })
     }
 
     #[inline]
@@ -0,0 +1,380 @@
+use std::cmp;
+use std::mem;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use buffer::Buffer;
+use buffer::BufferSlice;
+use buffer::Usage;
+use memory::ChunkProperties;
+use memory::ChunkRange;
+use memory::DeviceMemory;
+use memory::MappedDeviceMemory;
+use memory::MemorySource;
+use memory::MemorySourceChunk;
+use sync::Fence;
+use sync::Semaphore;
+use sync::SharingMode;
+
+use device::Device;
+use device::Queue;
+
+use OomError;
+use VulkanObject;
+use VulkanPointers;
+use check_errors;
+use vk;
+
+/// Allocator for transient data that is uploaded to the GPU every frame, like uniforms or
+/// dynamic vertices.
+///
+/// The data is copied into large persistently-mapped buffers, called blocks, and each upload
+/// returns a `BufferSlice` that points to it. Blocks are filled one after the other. If no block
+/// is available, a new one is allocated, so that the allocator grows until it has enough blocks
+/// for the frames that are in flight.
+///
+/// A block is reused from the start once all the slices that point to it and all the command
+/// buffers that use them have been destroyed, and once all the submissions that used it are
+/// finished. Keeping a slice or a command buffer alive therefore prevents its block from being
+/// reused.
+///
+/// # Example
+///
+/// ```ignore
+/// let uploads = UploadBuffer::new(&device, 1024 * 1024, &Usage::all(), &queue);
+///
+/// // every frame
+/// let uniforms = uploads.upload(matrices).unwrap();
+/// let vertices = uploads.upload_slice(&particles).unwrap();
+/// ```
+pub struct UploadBuffer {
+    device: Arc<Device>,
+    block_size: usize,
+    alignment: usize,
+    usage: Usage,
+    sharing: SharingMode,
+    state: Mutex<State>,
+}
+
+struct State {
+    // Blocks in the order in which they are filled. Blocks are never removed, which allows the
+    // slices to borrow them for as long as the `UploadBuffer` is alive.
+    //
+    // The slices and the command buffers that use them hold a strong reference to their block,
+    // which is how we know whether a block still has live slices.
+    blocks: Vec<Arc<Buffer<[u8], UploadChunk>>>,
+    // Index of the block being filled. Meaningless if `blocks` is empty.
+    current: usize,
+    // Offset of the next upload within the current block.
+    offset: usize,
+}
+
+impl UploadBuffer {
+    /// Builds a new allocator whose blocks contain `block_size` bytes. No memory is allocated
+    /// until the first upload.
+    ///
+    /// `usage` and `sharing` are used to create the blocks, and apply to all the slices that are
+    /// returned.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `block_size` is 0.
+    ///
+    pub fn new<Sh>(device: &Arc<Device>, block_size: usize, usage: &Usage, sharing: Sh)
+                   -> UploadBuffer
+        where Sh: Into<SharingMode>
+    {
+        assert!(block_size != 0);
+
+        // uploads are aligned so that they can be used as any kind of buffer, and so that
+        // flushing a non-coherent upload doesn't touch the previous one
+        let limits = device.physical_device().limits();
+        let alignment = [limits.min_uniform_buffer_offset_alignment(),
+                         limits.min_storage_buffer_offset_alignment(),
+                         limits.min_texel_buffer_offset_alignment(),
+                         limits.non_coherent_atom_size()].iter().fold(1, |a, &b| cmp::max(a, b));
+
+        UploadBuffer {
+            device: device.clone(),
+            block_size: block_size,
+            alignment: alignment as usize,
+            usage: *usage,
+            sharing: sharing.into(),
+            state: Mutex::new(State {
+                blocks: Vec::new(),
+                current: 0,
+                offset: 0,
+            }),
+        }
+    }
+
+    /// Returns the device used to create this allocator.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// Returns the number of blocks that have been allocated so far.
+    #[inline]
+    pub fn num_blocks(&self) -> usize {
+        self.state.lock().unwrap().blocks.len()
+    }
+
+    /// Copies `data` to the GPU and returns a slice that contains it.
+    #[inline]
+    pub fn upload<T>(&self, data: T) -> Result<BufferSlice<T, UploadChunk>, OomError>
+        where T: Copy + 'static
+    {
+        unsafe {
+            self.upload_raw(&data as *const T as *const u8, mem::size_of::<T>(),
+                            mem::align_of::<T>())
+        }
+    }
+
+    /// Copies the elements of `data` to the GPU and returns a slice that contains them.
+    #[inline]
+    pub fn upload_slice<T>(&self, data: &[T]) -> Result<BufferSlice<[T], UploadChunk>, OomError>
+        where T: Copy + 'static
+    {
+        unsafe {
+            self.upload_raw(data.as_ptr() as *const u8, data.len() * mem::size_of::<T>(),
+                            mem::align_of::<T>())
+        }
+    }
+
+    // Copies `size` bytes from `data` to a new allocation.
+    unsafe fn upload_raw<T: ?Sized>(&self, data: *const u8, size: usize, alignment: usize)
+                                    -> Result<BufferSlice<T, UploadChunk>, OomError>
+    {
+        let alignment = cmp::max(alignment, self.alignment);
+
+        let mut state = self.state.lock().unwrap();
+        let offset = try!(self.reserve(&mut state, size, alignment));
+        let block = &state.blocks[state.current];
+        let chunk = block.memory();
+
+        let mapping = chunk.mem.mapping_pointer() as *mut u8;
+        ptr::copy_nonoverlapping(data, mapping.offset(offset as isize), size);
+
+        if !chunk.coherent {
+            // the offset is a multiple of the atom size, but the end must be rounded up too
+            let atom = self.device.physical_device().limits().non_coherent_atom_size() as usize;
+            let flush_size = align(size, cmp::max(atom, 1));
+            let flush_size = if offset + flush_size > chunk.mem.memory().size() {
+                vk::WHOLE_SIZE
+            } else {
+                flush_size as vk::DeviceSize
+            };
+
+            let range = vk::MappedMemoryRange {
+                sType: vk::STRUCTURE_TYPE_MAPPED_MEMORY_RANGE,
+                pNext: ptr::null(),
+                memory: chunk.mem.memory().internal_object(),
+                offset: offset as vk::DeviceSize,
+                size: flush_size,
+            };
+
+            let vk = self.device.pointers();
+            try!(check_errors(vk.FlushMappedMemoryRanges(self.device.internal_object(), 1,
+                                                         &range)));
+        }
+
+        // the block lives on the heap and is never destroyed before `self`, so it can be
+        // borrowed for as long as `self` even though the list of blocks may be reallocated
+        let block: &Arc<Buffer<[u8], UploadChunk>> = &*(block as *const _);
+        Ok(BufferSlice::from_raw_parts(block, offset, size))
+    }
+
+    // Finds space for `size` bytes in the current block, switching to another block or
+    // allocating a new one if necessary. Returns the offset within the new current block.
+    fn reserve(&self, state: &mut State, size: usize, alignment: usize)
+               -> Result<usize, OomError>
+    {
+        if !state.blocks.is_empty() {
+            let offset = align(state.offset, alignment);
+            if offset + size <= state.blocks[state.current].size() {
+                state.offset = offset + size;
+                return Ok(offset);
+            }
+
+            // looking for a block that is no longer in use, in the order in which they were
+            // filled ; a block that is only referenced by `self` has no live slice
+            let num_blocks = state.blocks.len();
+            let next = (1 .. num_blocks).map(|n| (state.current + n) % num_blocks).find(|&n| {
+                let block = &state.blocks[n];
+                block.size() >= size && Arc::strong_count(block) == 1 &&
+                block.memory().is_free()
+            });
+
+            if let Some(next) = next {
+                state.current = next;
+                state.offset = size;
+                return Ok(0);
+            }
+        }
+
+        let block_size = cmp::max(size, self.block_size);
+        let block = unsafe {
+            try!(Buffer::raw(&self.device, block_size, &self.usage, UploadSource,
+                             self.sharing.clone()))
+        };
+
+        // inserting the new block after the current one keeps the order in which the blocks are
+        // filled
+        let pos = if state.blocks.is_empty() { 0 } else { state.current + 1 };
+        state.blocks.insert(pos, block);
+        state.current = pos;
+        state.offset = size;
+        Ok(0)
+    }
+}
+
+// Memory source for the blocks of an `UploadBuffer`.
+struct UploadSource;
+
+unsafe impl MemorySource for UploadSource {
+    type Chunk = UploadChunk;
+
+    #[inline]
+    fn is_sparse(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn allocate(self, device: &Arc<Device>, size: usize, _alignment: usize,
+                memory_type_bits: u32) -> Result<UploadChunk, OomError>
+    {
+        // if no memory type is suitable, there is no memory that we can allocate
+        let mem_ty = match device.physical_device().memory_types()
+                                 .filter(|t| (memory_type_bits & (1 << t.id())) != 0)
+                                 .filter(|t| t.is_host_visible())
+                                 .next()
+        {
+            Some(t) => t,
+            None => return Err(OomError::OutOfDeviceMemory),
+        };
+        let mem = try!(DeviceMemory::alloc_and_map(device, &mem_ty, size));
+
+        // note: alignment doesn't need to be checked because allocating memory is guaranteed to
+        //       fulfill any alignment requirement
+
+        Ok(UploadChunk {
+            mem: mem,
+            coherent: mem_ty.is_host_coherent(),
+            lock: Mutex::new((None, Vec::new())),
+        })
+    }
+}
+
+/// A block of memory of an `UploadBuffer`.
+///
+/// Contrary to `HostVisibleChunk`, the fences of all the submissions that use the memory are
+/// kept, so that the `UploadBuffer` knows when it can be reused.
+pub struct UploadChunk {
+    mem: MappedDeviceMemory,
+    coherent: bool,
+    lock: Mutex<(Option<Arc<Semaphore>>, Vec<Arc<Fence>>)>,
+}
+
+impl UploadChunk {
+    /// Returns true if all the submissions that used this memory are finished.
+    ///
+    /// Note that the memory may still be used by command buffers that haven't been submitted
+    /// yet.
+    pub fn is_free(&self) -> bool {
+        let mut lock = self.lock.lock().unwrap();
+        // the fences whose status can't be queried are kept, so that the memory is never
+        // considered free too early
+        lock.1.retain(|fence| fence.ready() != Ok(true));
+        lock.1.is_empty()
+    }
+}
+
+unsafe impl MemorySourceChunk for UploadChunk {
+    #[inline]
+    unsafe fn gpu_access(&self, _write: bool, _range: ChunkRange, _: &mut Queue,
+                         fence: Option<Arc<Fence>>, mut semaphore: Option<Arc<Semaphore>>)
+                         -> Option<Arc<Semaphore>>
+    {
+        assert!(fence.is_some());
+        assert!(semaphore.is_some());
+
+        let mut self_lock = self.lock.lock().unwrap();
+        mem::swap(&mut self_lock.0, &mut semaphore);
+        self_lock.1.push(fence.unwrap());
+
+        semaphore
+    }
+
+    #[inline]
+    fn properties(&self) -> ChunkProperties {
+        ChunkProperties::Regular {
+            memory: &self.mem.memory(),
+            offset: 0,
+            size: self.mem.memory().size(),
+        }
+    }
+
+    #[inline]
+    fn may_alias(&self) -> bool {
+        false
+    }
+}
+
+// Rounds `value` up to a multiple of `alignment`.
+#[inline]
+fn align(value: usize, alignment: usize) -> usize {
+    (value + alignment - 1) / alignment * alignment
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::Usage;
+    use memory::UploadBuffer;
+
+    #[test]
+    fn upload() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let uploads = UploadBuffer::new(&device, 1024, &Usage::all(), &queue);
+
+        let a = uploads.upload(5u32).unwrap();
+        let b = uploads.upload_slice(&[1u16, 2, 3]).unwrap();
+        assert_eq!(a.size(), 4);
+        assert_eq!(b.len(), 3);
+        assert!(a.offset() + a.size() <= b.offset());
+        assert_eq!(uploads.num_blocks(), 1);
+    }
+
+    #[test]
+    fn grow() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let uploads = UploadBuffer::new(&device, 16, &Usage::all(), &queue);
+
+        let a = uploads.upload([0u8; 16]).unwrap();
+        let b = uploads.upload_slice(&[0u8; 64]).unwrap();
+        assert_eq!(b.offset(), 0);
+        assert_eq!(b.len(), 64);
+        assert_eq!(uploads.num_blocks(), 2);
+        drop(a);
+    }
+
+    #[test]
+    fn live_slices_prevent_reuse() {
+        let (device, queue) = gfx_dev_and_queue!();
+        let uploads = UploadBuffer::new(&device, 16, &Usage::all(), &queue);
+
+        let a = uploads.upload([0u8; 16]).unwrap();
+        let _b = uploads.upload([0u8; 16]).unwrap();
+        let _c = uploads.upload([0u8; 16]).unwrap();
+        assert_eq!(uploads.num_blocks(), 3);
+
+        // every slice is still alive, so none of the blocks can be reused
+        let _d = uploads.upload([0u8; 16]).unwrap();
+        assert_eq!(uploads.num_blocks(), 4);
+
+        drop(a);
+        let _e = uploads.upload([0u8; 16]).unwrap();
+        assert_eq!(uploads.num_blocks(), 4);
+    }
+}
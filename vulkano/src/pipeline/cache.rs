@@ -14,6 +14,7 @@ use std::mem;
 use std::ptr;
 use std::sync::Arc;
 
+use device::DebugName;
 use device::Device;
 
 use OomError;
@@ -124,6 +125,15 @@ unsafe impl VulkanObject for PipelineCache {
     }
 }
 
+unsafe impl DebugName for PipelineCache {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_PIPELINE_CACHE_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl Drop for PipelineCache {
     #[inline]
     fn drop(&mut self) {
@@ -1,84 +1,122 @@
-use std::marker::PhantomData;
 use std::mem;
 use std::ptr;
 use std::sync::Arc;
 
+use descriptor_set::PipelineLayout;
+use descriptor_set::PipelineLayoutDesc;
 use pipeline::GenericPipeline;
-use shader::EntryPoint;
+use shader::ComputeShaderEntryPoint;
+use shader::SpecializationConstants;
 
+use device::DebugName;
 use device::Device;
 use OomError;
-use Success;
 use VulkanObject;
 use VulkanPointers;
 use check_errors;
 use vk;
 
-///
+/// A pipeline object that describes to the Vulkan implementation how it should perform compute
+/// operations.
 ///
 /// The template parameter contains the descriptor set to use with this pipeline.
-pub struct ComputePipeline<D, C> {
+pub struct ComputePipeline<Pl> {
     device: Arc<Device>,
     pipeline: vk::Pipeline,
-    marker: PhantomData<(D, C)>,
+    layout: Arc<PipelineLayout<Pl>>,
 }
 
-impl<D, C> ComputePipeline<D, C> {
-    ///
+impl<Pl> ComputePipeline<Pl> where Pl: PipelineLayoutDesc {
+    /// Builds a new compute pipeline object.
     ///
     /// # Panic
     ///
-    /// Panicks if the pipeline layout and/or shader don't belong to the device.
-    pub fn new<D, S, P>(device: &Arc<Device>, pipeline_layout: &Arc<PipelineLayout<D, C>>,
-                        shader: &ComputeShaderEntryPoint<D, S, P>, specialization: &S)
-                        -> Result<ComputePipeline<D, C>, OomError>
-        where S: SpecializationConstants
+    /// - Panicks if the pipeline layout and/or shader don't belong to the device.
+    ///
+    pub fn new<Css, Csp, Cl>(device: &Arc<Device>, pipeline_layout: &Arc<PipelineLayout<Pl>>,
+                             shader: &ComputeShaderEntryPoint<Cl, Css, Csp>, specialization: &Css)
+                             -> Result<Arc<ComputePipeline<Pl>>, OomError>
+        where Css: SpecializationConstants
     {
         let vk = device.pointers();
 
+        // FIXME: check layout compatibility
+
         let pipeline = unsafe {
-            let spec_descriptors = specialization.descriptors();
+            let spec_descriptors = Css::descriptors();
             let specialization = vk::SpecializationInfo {
-                mapEntryCount: spec_descriptors.len(),
+                mapEntryCount: spec_descriptors.len() as u32,
                 pMapEntries: spec_descriptors.as_ptr() as *const _,
                 dataSize: mem::size_of_val(specialization),
-                pData: specialization as *const S as *const _,
+                pData: specialization as *const Css as *const _,
             };
 
             let stage = vk::PipelineShaderStageCreateInfo {
-                sType: vk::STRUCTURE_TYPE_COMPUTE_PIPELINE_CREATE_INFO,
+                sType: vk::STRUCTURE_TYPE_PIPELINE_SHADER_STAGE_CREATE_INFO,
                 pNext: ptr::null(),
-                flags: 0,
-                shader: shader,
-                pSpecializationInfo: if mem::size_of_val(specialization) == 0 {
+                flags: 0,   // reserved
+                stage: vk::SHADER_STAGE_COMPUTE_BIT,
+                module: shader.module().internal_object(),
+                pName: shader.name().as_ptr(),
+                pSpecializationInfo: if specialization.dataSize == 0 {
                     ptr::null()
                 } else {
                     &specialization
                 },
             };
 
-            let infos = VkComputePipelineCreateInfo {
+            let infos = vk::ComputePipelineCreateInfo {
                 sType: vk::STRUCTURE_TYPE_COMPUTE_PIPELINE_CREATE_INFO,
                 pNext: ptr::null(),
                 flags: 0,
                 stage: stage,
                 layout: pipeline_layout.internal_object(),
-                basePipelineHandle: vk::NULL_HANDLE,
+                basePipelineHandle: 0,
                 basePipelineIndex: 0,
             };
 
             let mut output = mem::uninitialized();
-            try!(check_errors(vk.CreateComputePipelines(device.internal_object(), vk::NULL_HANDLE,
+            try!(check_errors(vk.CreateComputePipelines(device.internal_object(), 0,
                                                         1, &infos, ptr::null(), &mut output)));
             output
         };
+
+        Ok(Arc::new(ComputePipeline {
+            device: device.clone(),
+            pipeline: pipeline,
+            layout: pipeline_layout.clone(),
+        }))
+    }
+
+    /// Returns the pipeline layout used in the constructor.
+    #[inline]
+    pub fn layout(&self) -> &Arc<PipelineLayout<Pl>> {
+        &self.layout
     }
 }
 
-impl GenericPipeline for ComputePipeline {
+impl<Pl> GenericPipeline for ComputePipeline<Pl> {
+}
+
+unsafe impl<Pl> VulkanObject for ComputePipeline<Pl> {
+    type Object = vk::Pipeline;
+
+    #[inline]
+    fn internal_object(&self) -> vk::Pipeline {
+        self.pipeline
+    }
+}
+
+unsafe impl<Pl> DebugName for ComputePipeline<Pl> {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_PIPELINE_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
 }
 
-impl<D, C> Drop for ComputePipeline<D, C> {
+impl<Pl> Drop for ComputePipeline<Pl> {
     #[inline]
     fn drop(&mut self) {
         unsafe {
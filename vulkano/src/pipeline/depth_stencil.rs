@@ -1,31 +1,184 @@
+//! Depth and stencil operations description.
+//!
+//! After the fragment shader has finished running, each fragment goes through the depth
+//! and stencil tests.
+//!
+//! The depth test passes or fails depending on how the depth value of each fragment compares
+//! to the existing depth value in the depth buffer at that fragment's location. Depth values
+//! are always between 0.0 and 1.0.
+//!
+//! The stencil test passes or fails depending on how a reference value compares to the existing
+//! value in the stencil buffer at each fragment's location. Depending on the outcome of the
+//! depth and stencil tests, the value of the stencil buffer at that location can be updated.
+//!
+//! Both tests have no effect if the subpass doesn't have a depth-stencil attachment.
+//!
 
+use std::ops::Range;
+use vk;
+
+/// Configuration of the depth and stencil tests.
+#[derive(Debug, Clone)]
 pub struct DepthStencil {
-    depth_write: bool,
-    depth_compare: Compare,
-    depth_bounds_test: bool,
+    /// If true, then the depth value of each fragment that passes the depth test will be
+    /// written to the depth buffer.
+    pub depth_write: bool,
+
+    /// Comparison to use between the depth value of each fragment and the depth value currently
+    /// in the depth buffer.
+    pub depth_compare: Compare,
+
+    /// Allows you to ask the GPU to exclude fragments that are outside of a certain range.
+    pub depth_bounds_test: DepthBounds,
+
+    /// Stencil operations to use for faces that are facing the viewer.
+    pub stencil_front: Stencil,
+
+    /// Stencil operations to use for faces that are facing away from the viewer.
+    pub stencil_back: Stencil,
 }
 
+impl DepthStencil {
+    /// Creates a `DepthStencil` where both the depth and stencil tests are disabled and have
+    /// no effect.
+    #[inline]
+    pub fn disabled() -> DepthStencil {
+        DepthStencil {
+            depth_write: false,
+            depth_compare: Compare::Always,
+            depth_bounds_test: DepthBounds::Disabled,
+            stencil_front: Default::default(),
+            stencil_back: Default::default(),
+        }
+    }
 
+    /// Creates a `DepthStencil` with a `Less` depth test, `depth_write` set to true, and the
+    /// stencil test disabled.
+    #[inline]
+    pub fn simple_depth_test() -> DepthStencil {
+        DepthStencil {
+            depth_write: true,
+            depth_compare: Compare::Less,
+            depth_bounds_test: DepthBounds::Disabled,
+            stencil_front: Default::default(),
+            stencil_back: Default::default(),
+        }
+    }
+}
 
-    VkBool32                                    depthTestEnable;
-    VkBool32                                    depthWriteEnable;
-    VkCompareOp                                 depthCompareOp;
-    VkBool32                                    depthBoundsTestEnable;
-    VkBool32                                    stencilTestEnable;
-    VkStencilOpState                            front;
-    VkStencilOpState                            back;
-    float                                       minDepthBounds;
-    float                                       maxDepthBounds;
+impl Default for DepthStencil {
+    #[inline]
+    fn default() -> DepthStencil {
+        DepthStencil::disabled()
+    }
+}
 
-typedef struct {
-    VkStencilOp                                 stencilFailOp;
-    VkStencilOp                                 stencilPassOp;
-    VkStencilOp                                 stencilDepthFailOp;
-    VkCompareOp                                 stencilCompareOp;
-    uint32_t                                    stencilCompareMask;
-    uint32_t                                    stencilWriteMask;
-    uint32_t                                    stencilReference;
-} VkStencilOpState;
+/// Configuration of a stencil test.
+///
+/// The `compare_mask`, `write_mask` and `reference` values are dynamic if you pass `None`, in
+/// which case they will need to be set when you build the command buffer. A value must be
+/// dynamic for both faces or for none of them.
+#[derive(Debug, Copy, Clone)]
+pub struct Stencil {
+    /// The comparison to perform between the existing stencil value in the stencil buffer, and
+    /// the reference value (given by `reference`).
+    pub compare: Compare,
+
+    /// The operation to perform when both the depth test and the stencil test passed.
+    pub pass_op: StencilOp,
+
+    /// The operation to perform when the stencil test failed.
+    pub fail_op: StencilOp,
+
+    /// The operation to perform when the stencil test passed but the depth test failed.
+    pub depth_fail_op: StencilOp,
+
+    /// Selects the bits of the stencil values that participate in the test.
+    pub compare_mask: Option<u32>,
+
+    /// Selects the bits of the stencil values that are updated by the stencil test.
+    pub write_mask: Option<u32>,
+
+    /// Reference value that is used in the stencil test.
+    pub reference: Option<u32>,
+}
+
+impl Stencil {
+    /// Returns true if the stencil operation will always result in `Keep`.
+    #[inline]
+    pub fn always_keep(&self) -> bool {
+        match self.compare {
+            Compare::Always => self.pass_op == StencilOp::Keep &&
+                               self.depth_fail_op == StencilOp::Keep,
+            Compare::Never => self.fail_op == StencilOp::Keep,
+            _ => self.pass_op == StencilOp::Keep && self.fail_op == StencilOp::Keep &&
+                 self.depth_fail_op == StencilOp::Keep,
+        }
+    }
+}
+
+impl Default for Stencil {
+    #[inline]
+    fn default() -> Stencil {
+        Stencil {
+            compare: Compare::Always,
+            pass_op: StencilOp::Keep,
+            fail_op: StencilOp::Keep,
+            depth_fail_op: StencilOp::Keep,
+            compare_mask: Some(0xffffffff),
+            write_mask: Some(0xffffffff),
+            reference: Some(0),
+        }
+    }
+}
+
+/// Operation to perform after the depth and stencil tests.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum StencilOp {
+    /// Keeps the current value.
+    Keep = vk::STENCIL_OP_KEEP,
+    /// Sets the value to 0.
+    Zero = vk::STENCIL_OP_ZERO,
+    /// Sets the value to the reference value.
+    Replace = vk::STENCIL_OP_REPLACE,
+    /// Increments the value and clamps it to the maximum representable value.
+    IncrementAndClamp = vk::STENCIL_OP_INCREMENT_AND_CLAMP,
+    /// Decrements the value and clamps it to 0.
+    DecrementAndClamp = vk::STENCIL_OP_DECREMENT_AND_CLAMP,
+    /// Inverts the bits of the value.
+    Invert = vk::STENCIL_OP_INVERT,
+    /// Increments the value and wraps to 0 if it overflows.
+    IncrementAndWrap = vk::STENCIL_OP_INCREMENT_AND_WRAP,
+    /// Decrements the value and wraps to the maximum representable value if it underflows.
+    DecrementAndWrap = vk::STENCIL_OP_DECREMENT_AND_WRAP,
+}
+
+/// Allows you to ask the GPU to exclude fragments that are outside of a certain range.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DepthBounds {
+    /// The test is disabled. All fragments pass the depth bounds test.
+    Disabled,
+
+    /// Fragments that are within the given range do pass the test. Values are depth values
+    /// between 0.0 and 1.0.
+    Fixed(Range<f32>),
+
+    /// The depth bounds test is enabled, but the range will need to be specified when you build
+    /// the command buffer.
+    Dynamic,
+}
+
+impl DepthBounds {
+    /// Returns true if equal to `DepthBounds::Dynamic`.
+    #[inline]
+    pub fn is_dynamic(&self) -> bool {
+        match *self {
+            DepthBounds::Dynamic => true,
+            _ => false
+        }
+    }
+}
 
 /// Specifies how two values should be compared to decide whether a test passes or fails.
 ///
@@ -34,19 +187,19 @@ typedef struct {
 #[repr(u32)]
 pub enum Compare {
     /// The test never passes.
-    Never => vk::COMPARE_OP_NEVER,
+    Never = vk::COMPARE_OP_NEVER,
     /// The test passes if `value < reference_value`.
-    Less => vk::COMPARE_OP_LESS,
+    Less = vk::COMPARE_OP_LESS,
     /// The test passes if `value == reference_value`.
-    Equal => vk::COMPARE_OP_EQUAL,
+    Equal = vk::COMPARE_OP_EQUAL,
     /// The test passes if `value <= reference_value`.
-    LessOrEqual => vk::COMPARE_OP_LESS_OR_EQUAL,
+    LessOrEqual = vk::COMPARE_OP_LESS_OR_EQUAL,
     /// The test passes if `value > reference_value`.
-    Greater => vk::COMPARE_OP_GREATER,
+    Greater = vk::COMPARE_OP_GREATER,
     /// The test passes if `value != reference_value`.
-    NotEqual => vk::COMPARE_OP_NOT_EQUAL,
+    NotEqual = vk::COMPARE_OP_NOT_EQUAL,
     /// The test passes if `value >= reference_value`.
-    GreaterOrEqual => vk::COMPARE_OP_GREATER_OR_EQUAL,
+    GreaterOrEqual = vk::COMPARE_OP_GREATER_OR_EQUAL,
     /// The test always passes.
-    Always => vk::COMPARE_OP_ALWAYS,
+    Always = vk::COMPARE_OP_ALWAYS,
 }
@@ -3,6 +3,7 @@ use std::mem;
 use std::ptr;
 use std::sync::Arc;
 
+use device::DebugName;
 use device::Device;
 use descriptor_set::PipelineLayout;
 use descriptor_set::PipelineLayoutDesc;
@@ -17,11 +18,14 @@ use vk;
 
 use pipeline::GenericPipeline;
 use pipeline::blend::Blend;
+use pipeline::depth_stencil::Compare;
+use pipeline::depth_stencil::DepthBounds;
+use pipeline::depth_stencil::DepthStencil;
 use pipeline::input_assembly::InputAssembly;
 use pipeline::multisample::Multisample;
 use pipeline::raster::DepthBiasControl;
 use pipeline::raster::Rasterization;
-use pipeline::vertex::MultiVertex;
+use pipeline::vertex::VertexDefinition;
 use pipeline::vertex::Vertex;
 use pipeline::viewport::ViewportsState;
 
@@ -38,6 +42,11 @@ pub struct GraphicsPipeline<MultiVertex, Layout> {
     dynamic_viewport: bool,
     dynamic_scissor: bool,
     dynamic_depth_bias: bool,
+    dynamic_blend_constants: bool,
+    dynamic_depth_bounds: bool,
+    dynamic_stencil_compare_mask: bool,
+    dynamic_stencil_write_mask: bool,
+    dynamic_stencil_reference: bool,
 
     num_viewports: u32,
 
@@ -45,7 +54,7 @@ pub struct GraphicsPipeline<MultiVertex, Layout> {
 }
 
 impl<MV, L> GraphicsPipeline<MV, L>
-    where MV: MultiVertex, L: PipelineLayoutDesc
+    where MV: VertexDefinition, L: PipelineLayoutDesc
 {
     /// Builds a new graphics pipeline object.
     ///
@@ -55,12 +64,18 @@ impl<MV, L> GraphicsPipeline<MV, L>
     /// - Panicks if the `rasterization_samples` parameter of `multisample` is not >= 1.
     /// - Panicks if the `sample_shading` parameter of `multisample` is not between 0.0 and 1.0.
     /// - Panicks if the line width is different from 1.0 and the `wide_lines` feature is not enabled.
+    /// - Panicks if the depth bounds test is enabled and the `depth_bounds` feature is not
+    ///   enabled.
+    /// - Panicks if the depth bounds are fixed and not between 0.0 and 1.0.
+    /// - Panicks if the stencil compare mask, write mask or reference is dynamic for one face
+    ///   and not for the other.
     ///
     // TODO: check all the device's limits
     pub fn new<Vi, Fo, R, Vl, Fl>
               (device: &Arc<Device>, vertex_shader: &VertexShaderEntryPoint<Vi, Vl>,
                input_assembly: &InputAssembly, viewport: &ViewportsState,
-               raster: &Rasterization, multisample: &Multisample, blend: &Blend,
+               raster: &Rasterization, multisample: &Multisample, depth_stencil: &DepthStencil,
+               blend: &Blend,
                fragment_shader: &FragmentShaderEntryPoint<Fo, Fl>,
                layout: &Arc<PipelineLayout<L>>, render_pass: &Subpass<R>)
                -> Result<Arc<GraphicsPipeline<MV, L>>, OomError>
@@ -156,7 +171,11 @@ impl<MV, L> GraphicsPipeline<MV, L>
                     dynamic_states.push(vk::DYNAMIC_STATE_SCISSOR);
                     (viewports, vec![], num)
                 },
-                ViewportsState::Dynamic { num } => (vec![], vec![], num),
+                ViewportsState::Dynamic { num } => {
+                    dynamic_states.push(vk::DYNAMIC_STATE_VIEWPORT);
+                    dynamic_states.push(vk::DYNAMIC_STATE_SCISSOR);
+                    (vec![], vec![], num)
+                },
             };
 
             let viewport_info = vk::PipelineViewportStateCreateInfo {
@@ -222,35 +241,78 @@ impl<MV, L> GraphicsPipeline<MV, L>
                 alphaToOneEnable: if multisample.alpha_to_one { vk::TRUE } else { vk::FALSE },
             };
 
-            let depth_stencil = vk::PipelineDepthStencilStateCreateInfo {
+            let (depth_bounds_enable, depth_bounds) = match depth_stencil.depth_bounds_test {
+                DepthBounds::Disabled => (vk::FALSE, 0.0 .. 1.0),
+                DepthBounds::Fixed(ref range) => {
+                    assert!(range.start >= 0.0 && range.start <= 1.0);
+                    assert!(range.end >= 0.0 && range.end <= 1.0);
+                    (vk::TRUE, range.clone())
+                },
+                DepthBounds::Dynamic => {
+                    dynamic_states.push(vk::DYNAMIC_STATE_DEPTH_BOUNDS);
+                    (vk::TRUE, 0.0 .. 1.0)
+                },
+            };
+
+            if depth_bounds_enable == vk::TRUE {
+                assert!(device.enabled_features().depth_bounds);
+            }
+
+            let front = &depth_stencil.stencil_front;
+            let back = &depth_stencil.stencil_back;
+
+            assert_eq!(front.compare_mask.is_none(), back.compare_mask.is_none());
+            assert_eq!(front.write_mask.is_none(), back.write_mask.is_none());
+            assert_eq!(front.reference.is_none(), back.reference.is_none());
+
+            if front.compare_mask.is_none() {
+                dynamic_states.push(vk::DYNAMIC_STATE_STENCIL_COMPARE_MASK);
+            }
+            if front.write_mask.is_none() {
+                dynamic_states.push(vk::DYNAMIC_STATE_STENCIL_WRITE_MASK);
+            }
+            if front.reference.is_none() {
+                dynamic_states.push(vk::DYNAMIC_STATE_STENCIL_REFERENCE);
+            }
+
+            // Depth writes are disabled by Vulkan when the depth test is disabled, so the test
+            // is only disabled when it can't have any effect.
+            let depth_test_enable = depth_stencil.depth_compare != Compare::Always ||
+                                    depth_stencil.depth_write;
+
+            let depth_stencil_infos = vk::PipelineDepthStencilStateCreateInfo {
                 sType: vk::STRUCTURE_TYPE_PIPELINE_DEPTH_STENCIL_STATE_CREATE_INFO,
                 pNext: ptr::null(),
                 flags: 0,   // reserved
-                depthTestEnable: vk::TRUE,          // FIXME:
-                depthWriteEnable: vk::TRUE,         // FIXME:
-                depthCompareOp: vk::COMPARE_OP_LESS,           // FIXME:
-                depthBoundsTestEnable: vk::FALSE,            // FIXME:
-                stencilTestEnable: vk::FALSE,            // FIXME:
+                depthTestEnable: if depth_test_enable { vk::TRUE } else { vk::FALSE },
+                depthWriteEnable: if depth_stencil.depth_write { vk::TRUE } else { vk::FALSE },
+                depthCompareOp: depth_stencil.depth_compare as u32,
+                depthBoundsTestEnable: depth_bounds_enable,
+                stencilTestEnable: if front.always_keep() && back.always_keep() {
+                    vk::FALSE
+                } else {
+                    vk::TRUE
+                },
                 front: vk::StencilOpState {
-                    failOp: vk::STENCIL_OP_KEEP,           // FIXME:
-                    passOp: vk::STENCIL_OP_KEEP,           // FIXME:
-                    depthFailOp: vk::STENCIL_OP_KEEP,          // FIXME:
-                    compareOp: 0,            // FIXME:
-                    compareMask: 0,          // FIXME:
-                    writeMask: 0,            // FIXME:
-                    reference: 0,            // FIXME:
+                    failOp: front.fail_op as u32,
+                    passOp: front.pass_op as u32,
+                    depthFailOp: front.depth_fail_op as u32,
+                    compareOp: front.compare as u32,
+                    compareMask: front.compare_mask.unwrap_or(0xffffffff),
+                    writeMask: front.write_mask.unwrap_or(0xffffffff),
+                    reference: front.reference.unwrap_or(0),
                 },
                 back: vk::StencilOpState {
-                    failOp: vk::STENCIL_OP_KEEP,           // FIXME:
-                    passOp: vk::STENCIL_OP_KEEP,           // FIXME:
-                    depthFailOp: vk::STENCIL_OP_KEEP,          // FIXME:
-                    compareOp: 0,            // FIXME:
-                    compareMask: 0,          // FIXME:
-                    writeMask: 0,            // FIXME:
-                    reference: 0,            // FIXME:
+                    failOp: back.fail_op as u32,
+                    passOp: back.pass_op as u32,
+                    depthFailOp: back.depth_fail_op as u32,
+                    compareOp: back.compare as u32,
+                    compareMask: back.compare_mask.unwrap_or(0xffffffff),
+                    writeMask: back.write_mask.unwrap_or(0xffffffff),
+                    reference: back.reference.unwrap_or(0),
                 },
-                minDepthBounds: 0.0,           // FIXME:
-                maxDepthBounds: 1.0,           // FIXME:
+                minDepthBounds: depth_bounds.start,
+                maxDepthBounds: depth_bounds.end,
             };
 
             let atch = vk::PipelineColorBlendAttachmentState {
@@ -263,6 +325,10 @@ impl<MV, L> GraphicsPipeline<MV, L>
                 alphaBlendOp: 0,
                 colorWriteMask: 0xf,
             };
+            if blend.blend_constants.is_none() {
+                dynamic_states.push(vk::DYNAMIC_STATE_BLEND_CONSTANTS);
+            }
+
             let blend = vk::PipelineColorBlendStateCreateInfo {
                 sType: vk::STRUCTURE_TYPE_PIPELINE_COLOR_BLEND_STATE_CREATE_INFO,
                 pNext: ptr::null(),
@@ -294,7 +360,7 @@ impl<MV, L> GraphicsPipeline<MV, L>
                 pViewportState: &viewport_info,
                 pRasterizationState: &rasterization,
                 pMultisampleState: &multisample,
-                pDepthStencilState: &depth_stencil,
+                pDepthStencilState: &depth_stencil_infos,
                 pColorBlendState: &blend,
                 pDynamicState: &dynamic_states,
                 layout: layout.internal_object(),
@@ -319,6 +385,11 @@ impl<MV, L> GraphicsPipeline<MV, L>
             dynamic_viewport: viewport.dynamic_viewports(),
             dynamic_scissor: viewport.dynamic_scissors(),
             dynamic_depth_bias: raster.depth_bias.is_dynamic(),
+            dynamic_blend_constants: blend.blend_constants.is_none(),
+            dynamic_depth_bounds: depth_stencil.depth_bounds_test.is_dynamic(),
+            dynamic_stencil_compare_mask: depth_stencil.stencil_front.compare_mask.is_none(),
+            dynamic_stencil_write_mask: depth_stencil.stencil_front.write_mask.is_none(),
+            dynamic_stencil_reference: depth_stencil.stencil_front.reference.is_none(),
 
             num_viewports: viewport.num_viewports(),
 
@@ -328,7 +399,7 @@ impl<MV, L> GraphicsPipeline<MV, L>
 }
 
 impl<MV, L> GraphicsPipeline<MV, L>
-    where MV: MultiVertex, L: PipelineLayoutDesc
+    where MV: VertexDefinition, L: PipelineLayoutDesc
 {
     /// Returns the pipeline layout used in the constructor.
     #[inline]
@@ -343,6 +414,60 @@ impl<MultiVertex, Layout> GraphicsPipeline<MultiVertex, Layout> {
     pub fn has_dynamic_line_width(&self) -> bool {
         self.dynamic_line_width
     }
+
+    /// Returns the number of viewports and scissors of this pipeline.
+    #[inline]
+    pub fn num_viewports(&self) -> u32 {
+        self.num_viewports
+    }
+
+    /// Returns true if the viewports used by this pipeline are dynamic.
+    #[inline]
+    pub fn has_dynamic_viewports(&self) -> bool {
+        self.dynamic_viewport
+    }
+
+    /// Returns true if the scissors used by this pipeline are dynamic.
+    #[inline]
+    pub fn has_dynamic_scissors(&self) -> bool {
+        self.dynamic_scissor
+    }
+
+    /// Returns true if the depth bias used by this pipeline is dynamic.
+    #[inline]
+    pub fn has_dynamic_depth_bias(&self) -> bool {
+        self.dynamic_depth_bias
+    }
+
+    /// Returns true if the blend constants used by this pipeline are dynamic.
+    #[inline]
+    pub fn has_dynamic_blend_constants(&self) -> bool {
+        self.dynamic_blend_constants
+    }
+
+    /// Returns true if the depth bounds used by this pipeline are dynamic.
+    #[inline]
+    pub fn has_dynamic_depth_bounds(&self) -> bool {
+        self.dynamic_depth_bounds
+    }
+
+    /// Returns true if the stencil compare masks used by this pipeline are dynamic.
+    #[inline]
+    pub fn has_dynamic_stencil_compare_mask(&self) -> bool {
+        self.dynamic_stencil_compare_mask
+    }
+
+    /// Returns true if the stencil write masks used by this pipeline are dynamic.
+    #[inline]
+    pub fn has_dynamic_stencil_write_mask(&self) -> bool {
+        self.dynamic_stencil_write_mask
+    }
+
+    /// Returns true if the stencil references used by this pipeline are dynamic.
+    #[inline]
+    pub fn has_dynamic_stencil_reference(&self) -> bool {
+        self.dynamic_stencil_reference
+    }
 }
 
 impl<MultiVertex, Layout> GenericPipeline for GraphicsPipeline<MultiVertex, Layout> {
@@ -357,6 +482,15 @@ unsafe impl<MultiVertex, Layout> VulkanObject for GraphicsPipeline<MultiVertex,
     }
 }
 
+unsafe impl<MultiVertex, Layout> DebugName for GraphicsPipeline<MultiVertex, Layout> {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_PIPELINE_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl<MultiVertex, Layout> Drop for GraphicsPipeline<MultiVertex, Layout> {
     #[inline]
     fn drop(&mut self) {
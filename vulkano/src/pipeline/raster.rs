@@ -67,7 +67,7 @@ impl DepthBiasControl {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct DepthBias {
     pub constant_factor: f32,
     /// Requires the `depth_bias_clamp` feature to be enabled.
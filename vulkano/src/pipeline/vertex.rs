@@ -1,15 +1,32 @@
+//! Vertex buffers and description of their content.
+//!
+//! When creating a graphics pipeline, you have to describe the vertex buffers that are going to
+//! be read by the vertex shader. This description is the first template parameter of
+//! `GraphicsPipeline` and must implement the `VertexDefinition` trait. It is either a type that
+//! implements `Vertex` for a single vertex buffer, a `PerInstance` wrapper around such a type, or
+//! a tuple of up to eight of these.
+//!
+//! When adding a draw command to a command buffer, you then have to pass the vertex buffers
+//! themselves. They must be an object that implements `MultiVertex` and whose `Definition`
+//! matches the pipeline. This is either a buffer or a buffer slice of vertices, a `PerInstance`
+//! wrapper around one of them, or a tuple of up to eight of these.
+//!
+//! ```ignore
+//! let pipeline: GraphicsPipeline<(Vertex, PerInstance<InstanceData>), _> = ...;
+//! cb.draw(&pipeline, (vertex_buffer.clone(), PerInstance(instances.clone())), ...)
+//! ```
 use std::mem;
-use std::option::IntoIter as OptionIntoIter;
 use std::sync::Arc;
 use std::vec::IntoIter as VecIntoIter;
 
 use buffer::Buffer;
 use buffer::BufferResource;
+use buffer::BufferSlice;
 use formats::Format;
 use memory::MemorySourceChunk;
 use vk;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u32)]
 pub enum VertexInputRate {
     Vertex = vk::VERTEX_INPUT_RATE_VERTEX,
@@ -28,25 +45,80 @@ pub struct VertexAttribute {
     pub format: Format,
 }
 
-/// Trait for types that contain the layout of a collection of vertex buffers.
-pub unsafe trait MultiVertex {
-    type BuffersIter: ExactSizeIterator<Item = Arc<BufferResource>>;
+/// Marks a vertex buffer as containing one element per instance instead of one element per
+/// vertex.
+///
+/// Can be used both around the type of the element when describing the vertex buffers of a
+/// pipeline, and around the buffer itself when adding a draw command.
+#[derive(Debug, Copy, Clone)]
+pub struct PerInstance<T>(pub T);
+
+/// Describes the content of a single vertex buffer.
+pub unsafe trait VertexBufferDefinition: 'static {
+    /// Returns the characteristics of an attribute of the elements of the buffer.
+    fn attrib(name: &str) -> Option<VertexAttribute>;
+
+    /// Returns the size in bytes of each element of the buffer.
+    fn stride() -> u32;
+
+    /// Returns whether the buffer contains one element per vertex or one element per instance.
+    fn input_rate() -> VertexInputRate;
+}
+
+unsafe impl<T> VertexBufferDefinition for T where T: 'static + Vertex {
+    #[inline]
+    fn attrib(name: &str) -> Option<VertexAttribute> {
+        T::attrib(name)
+    }
+
+    #[inline]
+    fn stride() -> u32 {
+        mem::size_of::<T>() as u32
+    }
+
+    #[inline]
+    fn input_rate() -> VertexInputRate {
+        VertexInputRate::Vertex
+    }
+}
+
+unsafe impl<T> VertexBufferDefinition for PerInstance<T> where T: 'static + Vertex {
+    #[inline]
+    fn attrib(name: &str) -> Option<VertexAttribute> {
+        T::attrib(name)
+    }
+
+    #[inline]
+    fn stride() -> u32 {
+        mem::size_of::<T>() as u32
+    }
+
+    #[inline]
+    fn input_rate() -> VertexInputRate {
+        VertexInputRate::Instance
+    }
+}
 
+/// Describes the layout of a collection of vertex buffers. Used when creating a graphics
+/// pipeline.
+pub unsafe trait VertexDefinition: 'static {
+    /// Returns the index of the buffer that contains the given attribute, and the
+    /// characteristics of the attribute.
     fn attrib(name: &str) -> Option<(u32, VertexAttribute)>;
 
     /// Returns the number of buffers in this collection.
     fn num_buffers() -> u32;
 
+    /// Returns the stride and the input rate of a buffer.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if `buffer_id` is out of range.
+    ///
     fn buffer_info(buffer_id: u32) -> (u32, VertexInputRate);
-
-    fn buffers(&self) -> Self::BuffersIter;
 }
 
-unsafe impl<T, M> MultiVertex for Arc<Buffer<T, M>>
-    where T: 'static + Vertex, M: 'static + MemorySourceChunk
-{
-    type BuffersIter = OptionIntoIter<Arc<BufferResource>>;
-
+unsafe impl<T> VertexDefinition for T where T: VertexBufferDefinition {
     #[inline]
     fn attrib(name: &str) -> Option<(u32, VertexAttribute)> {
         T::attrib(name).map(|attr| (0, attr))
@@ -60,138 +132,270 @@ unsafe impl<T, M> MultiVertex for Arc<Buffer<T, M>>
     #[inline]
     fn buffer_info(buffer_id: u32) -> (u32, VertexInputRate) {
         assert_eq!(buffer_id, 0);
-        (mem::size_of::<T>() as u32, VertexInputRate::Vertex)
+        (T::stride(), T::input_rate())
+    }
+}
+
+/// A single vertex buffer, or a slice of a vertex buffer.
+pub unsafe trait VertexBuffer {
+    /// Description of the content of the buffer.
+    type Definition: VertexBufferDefinition;
+
+    /// Returns the buffer and the offset in bytes of the first element.
+    fn buffer(&self) -> (Arc<BufferResource>, usize);
+
+    /// Returns the number of elements.
+    fn len(&self) -> usize;
+}
+
+unsafe impl<T, M> VertexBuffer for Arc<Buffer<T, M>>
+    where T: 'static + Vertex, M: 'static + MemorySourceChunk
+{
+    type Definition = T;
+
+    #[inline]
+    fn buffer(&self) -> (Arc<BufferResource>, usize) {
+        (self.clone() as Arc<_>, 0)
     }
 
     #[inline]
-    fn buffers(&self) -> OptionIntoIter<Arc<BufferResource>> {
-        Some(self.clone() as Arc<_>).into_iter()
+    fn len(&self) -> usize {
+        1
     }
 }
 
-unsafe impl<T, M> MultiVertex for Arc<Buffer<[T], M>>
+unsafe impl<T, M> VertexBuffer for Arc<Buffer<[T], M>>
     where T: 'static + Vertex, M: 'static + MemorySourceChunk
 {
-    type BuffersIter = OptionIntoIter<Arc<BufferResource>>;
+    type Definition = T;
 
     #[inline]
-    fn attrib(name: &str) -> Option<(u32, VertexAttribute)> {
-        T::attrib(name).map(|attr| (0, attr))
+    fn buffer(&self) -> (Arc<BufferResource>, usize) {
+        (self.clone() as Arc<_>, 0)
     }
 
     #[inline]
-    fn num_buffers() -> u32 {
-        1
+    fn len(&self) -> usize {
+        Buffer::len(self)
     }
+}
+
+unsafe impl<'a, T, M> VertexBuffer for BufferSlice<'a, [T], M>
+    where T: 'static + Vertex, M: 'a
+{
+    type Definition = T;
 
     #[inline]
-    fn buffer_info(buffer_id: u32) -> (u32, VertexInputRate) {
-        assert_eq!(buffer_id, 0);
-        (mem::size_of::<T>() as u32, VertexInputRate::Vertex)
+    fn buffer(&self) -> (Arc<BufferResource>, usize) {
+        (BufferSlice::buffer(self).clone(), self.offset())
     }
 
     #[inline]
-    fn buffers(&self) -> OptionIntoIter<Arc<BufferResource>> {
-        Some(self.clone() as Arc<_>).into_iter()
+    fn len(&self) -> usize {
+        BufferSlice::len(self)
     }
 }
 
-macro_rules! impl_mv {
+unsafe impl<B, T> VertexBuffer for PerInstance<B>
+    where B: VertexBuffer<Definition = T>, T: 'static + Vertex
+{
+    type Definition = PerInstance<T>;
+
+    #[inline]
+    fn buffer(&self) -> (Arc<BufferResource>, usize) {
+        self.0.buffer()
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+macro_rules! impl_vb {
     ($t1:ident, $t2:ty) => (
-        unsafe impl<$t1, M> MultiVertex for Arc<Buffer<$t2, M>>
+        unsafe impl<$t1, M> VertexBuffer for Arc<Buffer<$t2, M>>
             where T: 'static + Vertex, M: 'static + MemorySourceChunk
         {
-            type BuffersIter = OptionIntoIter<Arc<BufferResource>>;
+            type Definition = T;
 
             #[inline]
-            fn attrib(name: &str) -> Option<(u32, VertexAttribute)> {
-                T::attrib(name).map(|attr| (0, attr))
+            fn buffer(&self) -> (Arc<BufferResource>, usize) {
+                (self.clone() as Arc<_>, 0)
             }
 
             #[inline]
-            fn num_buffers() -> u32 {
-                1
-            }
-
-            #[inline]
-            fn buffer_info(buffer_id: u32) -> (u32, VertexInputRate) {
-                assert_eq!(buffer_id, 0);
-                (mem::size_of::<T>() as u32, VertexInputRate::Vertex)
-            }
-
-            #[inline]
-            fn buffers(&self) -> OptionIntoIter<Arc<BufferResource>> {
-                Some(self.clone() as Arc<_>).into_iter()
+            fn len(&self) -> usize {
+                mem::size_of::<$t2>() / mem::size_of::<T>()
             }
         }
     );
 }
 
-impl_mv!(T, [T; 1]);
-impl_mv!(T, [T; 2]);
-impl_mv!(T, [T; 3]);
-impl_mv!(T, [T; 4]);
-impl_mv!(T, [T; 5]);
-impl_mv!(T, [T; 6]);
-impl_mv!(T, [T; 7]);
-impl_mv!(T, [T; 8]);
-impl_mv!(T, [T; 9]);
-impl_mv!(T, [T; 10]);
-impl_mv!(T, [T; 11]);
-impl_mv!(T, [T; 12]);
-impl_mv!(T, [T; 13]);
-impl_mv!(T, [T; 14]);
-impl_mv!(T, [T; 15]);
-impl_mv!(T, [T; 16]);
-impl_mv!(T, [T; 32]);
-impl_mv!(T, [T; 64]);
-impl_mv!(T, [T; 128]);
-impl_mv!(T, [T; 256]);
-impl_mv!(T, [T; 512]);
-impl_mv!(T, [T; 1024]);
-impl_mv!(T, [T; 2048]);
-impl_mv!(T, [T; 4096]);
-
-
-unsafe impl<A, B, Ma, Mb> MultiVertex for (Arc<Buffer<[A], Ma>>, Arc<Buffer<[B], Mb>>)
-    where A: 'static + Vertex, B: 'static + Vertex, Ma: 'static + MemorySourceChunk,
-          Mb: 'static + MemorySourceChunk
-{
-    type BuffersIter = VecIntoIter<Arc<BufferResource>>;
+impl_vb!(T, [T; 1]);
+impl_vb!(T, [T; 2]);
+impl_vb!(T, [T; 3]);
+impl_vb!(T, [T; 4]);
+impl_vb!(T, [T; 5]);
+impl_vb!(T, [T; 6]);
+impl_vb!(T, [T; 7]);
+impl_vb!(T, [T; 8]);
+impl_vb!(T, [T; 9]);
+impl_vb!(T, [T; 10]);
+impl_vb!(T, [T; 11]);
+impl_vb!(T, [T; 12]);
+impl_vb!(T, [T; 13]);
+impl_vb!(T, [T; 14]);
+impl_vb!(T, [T; 15]);
+impl_vb!(T, [T; 16]);
+impl_vb!(T, [T; 32]);
+impl_vb!(T, [T; 64]);
+impl_vb!(T, [T; 128]);
+impl_vb!(T, [T; 256]);
+impl_vb!(T, [T; 512]);
+impl_vb!(T, [T; 1024]);
+impl_vb!(T, [T; 2048]);
+impl_vb!(T, [T; 4096]);
+
+/// Trait for types that contain a collection of vertex buffers that can be passed to a draw
+/// command.
+pub unsafe trait MultiVertex {
+    /// Description of the vertex buffers. The graphics pipeline must have been created with the
+    /// same definition.
+    type Definition: VertexDefinition;
+
+    /// An iterator that produces the buffers and the offset in bytes of their first element.
+    type BuffersIter: ExactSizeIterator<Item = (Arc<BufferResource>, usize)>;
+
+    /// Returns the buffers of this collection, in the order of their bindings.
+    fn buffers(&self) -> Self::BuffersIter;
+
+    /// Returns the number of vertices that can be read from the per-vertex buffers of this
+    /// collection, or `None` if there isn't any.
+    ///
+    /// This is the number of elements of the smallest buffer.
+    fn num_vertices(&self) -> Option<usize>;
+
+    /// Same as `num_vertices`, but for the per-instance buffers.
+    fn num_instances(&self) -> Option<usize>;
+}
+
+unsafe impl<B> MultiVertex for B where B: VertexBuffer {
+    type Definition = B::Definition;
+    type BuffersIter = VecIntoIter<(Arc<BufferResource>, usize)>;
 
     #[inline]
-    fn attrib(name: &str) -> Option<(u32, VertexAttribute)> {
-        if let Some(attr) = A::attrib(name) {
-            Some((0, attr))
-        } else if let Some(attr) = B::attrib(name) {
-            Some((1, attr))
-        } else {
-            None
-        }
+    fn buffers(&self) -> VecIntoIter<(Arc<BufferResource>, usize)> {
+        vec![self.buffer()].into_iter()
     }
 
     #[inline]
-    fn num_buffers() -> u32 {
-        2
+    fn num_vertices(&self) -> Option<usize> {
+        match B::Definition::input_rate() {
+            VertexInputRate::Vertex => Some(self.len()),
+            VertexInputRate::Instance => None,
+        }
     }
 
     #[inline]
-    fn buffer_info(buffer_id: u32) -> (u32, VertexInputRate) {
-        if buffer_id == 0 {
-            (mem::size_of::<A>() as u32, VertexInputRate::Vertex)
-        } else if buffer_id == 1 {
-            (mem::size_of::<B>() as u32, VertexInputRate::Vertex)
-        } else {
-            panic!()
+    fn num_instances(&self) -> Option<usize> {
+        match B::Definition::input_rate() {
+            VertexInputRate::Vertex => None,
+            VertexInputRate::Instance => Some(self.len()),
         }
     }
+}
 
-    #[inline]
-    fn buffers(&self) -> VecIntoIter<Arc<BufferResource>> {
-        vec![self.0.clone() as Arc<_>, self.1.clone() as Arc<_>].into_iter()
+// Returns the smallest of `current` and `len`, if the input rate of the buffer is `rate`.
+#[inline]
+fn min_len(current: Option<usize>, len: usize, buffer_rate: VertexInputRate,
+           rate: VertexInputRate) -> Option<usize>
+{
+    if buffer_rate != rate {
+        return current;
     }
+
+    match current {
+        Some(current) if current <= len => Some(current),
+        _ => Some(len),
+    }
+}
+
+macro_rules! impl_mv_tuple {
+    ($($buf:ident: $num:tt),+) => (
+        unsafe impl<$($buf),+> VertexDefinition for ($($buf,)+)
+            where $($buf: VertexBufferDefinition),+
+        {
+            #[inline]
+            fn attrib(name: &str) -> Option<(u32, VertexAttribute)> {
+                $(
+                    if let Some(attr) = $buf::attrib(name) {
+                        return Some(($num, attr));
+                    }
+                )+
+
+                None
+            }
+
+            #[inline]
+            fn num_buffers() -> u32 {
+                [$($num),+].len() as u32
+            }
+
+            #[inline]
+            fn buffer_info(buffer_id: u32) -> (u32, VertexInputRate) {
+                $(
+                    if buffer_id == $num {
+                        return ($buf::stride(), $buf::input_rate());
+                    }
+                )+
+
+                panic!("Out of range buffer id")
+            }
+        }
+
+        unsafe impl<$($buf),+> MultiVertex for ($($buf,)+)
+            where $($buf: VertexBuffer),+
+        {
+            type Definition = ($($buf::Definition,)+);
+            type BuffersIter = VecIntoIter<(Arc<BufferResource>, usize)>;
+
+            #[inline]
+            fn buffers(&self) -> VecIntoIter<(Arc<BufferResource>, usize)> {
+                vec![$(self.$num.buffer()),+].into_iter()
+            }
+
+            #[inline]
+            fn num_vertices(&self) -> Option<usize> {
+                let mut num = None;
+                $(
+                    num = min_len(num, self.$num.len(), $buf::Definition::input_rate(),
+                                  VertexInputRate::Vertex);
+                )+
+                num
+            }
+
+            #[inline]
+            fn num_instances(&self) -> Option<usize> {
+                let mut num = None;
+                $(
+                    num = min_len(num, self.$num.len(), $buf::Definition::input_rate(),
+                                  VertexInputRate::Instance);
+                )+
+                num
+            }
+        }
+    );
 }
 
+impl_mv_tuple!(A: 0);
+impl_mv_tuple!(A: 0, B: 1);
+impl_mv_tuple!(A: 0, B: 1, C: 2);
+impl_mv_tuple!(A: 0, B: 1, C: 2, D: 3);
+impl_mv_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_mv_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+impl_mv_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+impl_mv_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
 
 #[macro_export]
 macro_rules! impl_vertex {
@@ -49,7 +49,7 @@ impl ViewportsState {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Viewport {
     pub origin: [f32; 2],
     pub dimensions: [f32; 2],
@@ -71,7 +71,7 @@ impl Into<vk::Viewport> for Viewport {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Scissor {
     pub origin: [i32; 2],
     pub dimensions: [u32; 2],
@@ -1,38 +1,199 @@
 //! This module provides support for query pools.
-//! 
+//!
 //! In Vulkan, queries are not created individually. Instead you manipulate **query pools**, which
 //! represent a collection of queries. Whenever you use a query, you have to specify both the query
 //! pool and the slot id within that query pool.
+//!
+//! There are three kinds of query pools:
+//!
+//! - `OcclusionQueriesPool`, whose queries count the number of samples that pass the depth and
+//!   stencil tests between `begin_query` and `end_query`.
+//! - `PipelineStatisticsQueriesPool`, whose queries count the number of invocations of various
+//!   stages of the pipeline between `begin_query` and `end_query`.
+//! - `TimestampQueriesPool`, whose queries are written with `write_timestamp`.
+//!
+//! The results of the queries can be read from the host with `get_results`, or copied to a
+//! buffer with `copy_query_pool_results`. Before a query can be used, it must have been reset
+//! with `reset_query_pool` in a command buffer.
+use std::error;
+use std::fmt;
 use std::mem;
+use std::ops::Range;
 use std::ptr;
 use std::sync::Arc;
 
+use device::DebugName;
+use device::Device;
+
+use Error;
+use OomError;
+use Success;
+use VulkanObject;
+use VulkanPointers;
+use check_errors;
 use vk;
 
-use device::Device;
+/// Trait for all the query pools.
+pub trait QueryPool {
+    /// Returns the device that was used to create the pool.
+    fn device(&self) -> &Arc<Device>;
+
+    /// Returns the number of slots of the pool.
+    fn num_slots(&self) -> u32;
+
+    /// Returns the kind of queries of the pool.
+    fn query_type(&self) -> QueryType;
+
+    /// Returns the number of values that each query of the pool produces.
+    fn num_values_per_query(&self) -> usize;
+}
+
+/// Kind of queries of a query pool.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum QueryType {
+    Occlusion,
+    PipelineStatistics,
+    Timestamp,
+}
+
+/// Describes the statistics that the queries of a `PipelineStatisticsQueriesPool` collect.
+///
+/// The results of a query contain one value for each statistic that is enabled, in the order of
+/// the fields of this struct.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct QueryPipelineStatistics {
+    pub input_assembly_vertices: bool,
+    pub input_assembly_primitives: bool,
+    pub vertex_shader_invocations: bool,
+    pub geometry_shader_invocations: bool,
+    pub geometry_shader_primitives: bool,
+    pub clipping_invocations: bool,
+    pub clipping_primitives: bool,
+    pub fragment_shader_invocations: bool,
+    pub tessellation_control_shader_patches: bool,
+    pub tessellation_evaluation_shader_invocations: bool,
+    pub compute_shader_invocations: bool,
+}
+
+impl QueryPipelineStatistics {
+    /// Builds a `QueryPipelineStatistics` with all statistics set to `false`.
+    #[inline]
+    pub fn none() -> QueryPipelineStatistics {
+        QueryPipelineStatistics::default()
+    }
+
+    /// Returns the number of statistics that are enabled.
+    #[inline]
+    pub fn count(&self) -> usize {
+        let flags: vk::QueryPipelineStatisticFlags = (*self).into();
+        flags.count_ones() as usize
+    }
+}
+
+#[doc(hidden)]
+impl Into<vk::QueryPipelineStatisticFlags> for QueryPipelineStatistics {
+    #[inline]
+    fn into(self) -> vk::QueryPipelineStatisticFlags {
+        let mut result = 0;
+        if self.input_assembly_vertices { result |= vk::QUERY_PIPELINE_STATISTIC_INPUT_ASSEMBLY_VERTICES_BIT; }
+        if self.input_assembly_primitives { result |= vk::QUERY_PIPELINE_STATISTIC_INPUT_ASSEMBLY_PRIMITIVES_BIT; }
+        if self.vertex_shader_invocations { result |= vk::QUERY_PIPELINE_STATISTIC_VERTEX_SHADER_INVOCATIONS_BIT; }
+        if self.geometry_shader_invocations { result |= vk::QUERY_PIPELINE_STATISTIC_GEOMETRY_SHADER_INVOCATIONS_BIT; }
+        if self.geometry_shader_primitives { result |= vk::QUERY_PIPELINE_STATISTIC_GEOMETRY_SHADER_PRIMITIVES_BIT; }
+        if self.clipping_invocations { result |= vk::QUERY_PIPELINE_STATISTIC_CLIPPING_INVOCATIONS_BIT; }
+        if self.clipping_primitives { result |= vk::QUERY_PIPELINE_STATISTIC_CLIPPING_PRIMITIVES_BIT; }
+        if self.fragment_shader_invocations { result |= vk::QUERY_PIPELINE_STATISTIC_FRAGMENT_SHADER_INVOCATIONS_BIT; }
+        if self.tessellation_control_shader_patches { result |= vk::QUERY_PIPELINE_STATISTIC_TESSELLATION_CONTROL_SHADER_PATCHES_BIT; }
+        if self.tessellation_evaluation_shader_invocations { result |= vk::QUERY_PIPELINE_STATISTIC_TESSELLATION_EVALUATION_SHADER_INVOCATIONS_BIT; }
+        if self.compute_shader_invocations { result |= vk::QUERY_PIPELINE_STATISTIC_COMPUTE_SHADER_INVOCATIONS_BIT; }
+        result
+    }
+}
+
+/// How the results of queries should be retrieved.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct QueryResultFlags {
+    /// Wait until the results of all the queries are available.
+    pub wait: bool,
+    /// Return the intermediate results of the queries that are not available yet. Can't be used
+    /// with timestamp queries.
+    pub partial: bool,
+}
+
+#[doc(hidden)]
+impl Into<vk::QueryResultFlags> for QueryResultFlags {
+    #[inline]
+    fn into(self) -> vk::QueryResultFlags {
+        let mut result = 0;
+        if self.wait { result |= vk::QUERY_RESULT_WAIT_BIT; }
+        if self.partial { result |= vk::QUERY_RESULT_PARTIAL_BIT; }
+        result
+    }
+}
+
+/// Type that can receive the results of queries. Implemented on `u32` and `u64`.
+///
+/// Using `u64` corresponds to `VK_QUERY_RESULT_64_BIT`. Results that don't fit in a `u32` are
+/// truncated.
+pub unsafe trait QueryResultElement: Copy {
+    #[doc(hidden)]
+    fn flags() -> vk::QueryResultFlags;
+}
+
+unsafe impl QueryResultElement for u32 {
+    #[inline]
+    fn flags() -> vk::QueryResultFlags {
+        0
+    }
+}
+
+unsafe impl QueryResultElement for u64 {
+    #[inline]
+    fn flags() -> vk::QueryResultFlags {
+        vk::QUERY_RESULT_64_BIT
+    }
+}
 
 macro_rules! query_pool {
-    ($name:ident, $query_type:expr) => {
+    ($name:ident, $query_type:expr, $ty:expr) => {
         pub struct $name {
             device: Arc<Device>,
-            pool: VkQueryPool,
+            pool: vk::QueryPool,
             num_slots: u32,
+            // Number of values that each query produces.
+            num_values: usize,
         }
 
         impl $name {
-            /// Builds a new query pool.
-            pub fn new(device: Arc<Device>, num_slots: u32) -> Arc<$name> {
-                let create_infos = VkQueryPoolCreateInfo {
-                    sType: vk::QUERY_POOL_CREATE_INFO,
-                    pNext: ptr::null(),
-                    flags: 0,
-                    queryType: $query_type,
-                    num_slots: num_slots,
-                    pipelineStatistics: 0,      // TODO: 
+            // Builds a new query pool.
+            fn raw(device: &Arc<Device>, num_slots: u32,
+                   statistics: vk::QueryPipelineStatisticFlags)
+                   -> Result<Arc<$name>, OomError>
+            {
+                let vk = device.pointers();
+
+                let pool = unsafe {
+                    let infos = vk::QueryPoolCreateInfo {
+                        sType: vk::STRUCTURE_TYPE_QUERY_POOL_CREATE_INFO,
+                        pNext: ptr::null(),
+                        flags: 0,   // reserved
+                        queryType: $query_type,
+                        queryCount: num_slots,
+                        pipelineStatistics: statistics,
+                    };
+
+                    let mut output = mem::uninitialized();
+                    try!(check_errors(vk.CreateQueryPool(device.internal_object(), &infos,
+                                                         ptr::null(), &mut output)));
+                    output
                 };
 
-                let mut output = mem::uninitialized();
-                vkCreateQueryPool(device.internal_object(), &create_infos, ptr::null(), &mut output);
+                Ok(Arc::new($name {
+                    device: device.clone(),
+                    pool: pool,
+                    num_slots: num_slots,
+                    num_values: if statistics != 0 { statistics.count_ones() as usize } else { 1 },
+                }))
             }
 
             /// Returns the number of slots of that query pool.
@@ -40,16 +201,239 @@ macro_rules! query_pool {
             pub fn num_slots(&self) -> u32 {
                 self.num_slots
             }
+
+            /// Copies the results of the queries within `range` to `destination`, and returns
+            /// `true` if all the results were available.
+            ///
+            /// Each query produces `num_values_per_query()` values. If a result isn't available
+            /// and `flags.partial` is false, then the corresponding values are left untouched.
+            ///
+            /// # Panic
+            ///
+            /// - Panicks if the range is out of range of the pool.
+            /// - Panicks if `destination` doesn't have the right length.
+            /// - Panicks if `flags.partial` is true and this is a timestamp pool.
+            ///
+            pub fn get_results<T>(&self, range: Range<u32>, flags: QueryResultFlags,
+                                  destination: &mut [T]) -> Result<bool, GetResultsError>
+                where T: QueryResultElement
+            {
+                assert!(!flags.partial || $ty != QueryType::Timestamp);
+
+                unsafe {
+                    get_results(&self.device, self.pool, self.num_slots, self.num_values, range,
+                                flags, destination)
+                }
+            }
+        }
+
+        impl QueryPool for $name {
+            #[inline]
+            fn device(&self) -> &Arc<Device> {
+                &self.device
+            }
+
+            #[inline]
+            fn num_slots(&self) -> u32 {
+                self.num_slots
+            }
+
+            #[inline]
+            fn query_type(&self) -> QueryType {
+                $ty
+            }
+
+            #[inline]
+            fn num_values_per_query(&self) -> usize {
+                self.num_values
+            }
+        }
+
+        unsafe impl VulkanObject for $name {
+            type Object = vk::QueryPool;
+
+            #[inline]
+            fn internal_object(&self) -> vk::QueryPool {
+                self.pool
+            }
+        }
+
+        unsafe impl DebugName for $name {
+            const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_QUERY_POOL_EXT;
+
+            #[inline]
+            fn device(&self) -> &Arc<Device> {
+                &self.device
+            }
         }
 
         impl Drop for $name {
+            #[inline]
             fn drop(&mut self) {
                 unsafe {
-                    vkDestroyQueryPool
+                    let vk = self.device.pointers();
+                    vk.DestroyQueryPool(self.device.internal_object(), self.pool, ptr::null());
                 }
             }
         }
     };
 }
 
-query_pool!(OcclusionQueriesPool, vk::QUERY_TYPE_OCCLUSION);
+query_pool!(OcclusionQueriesPool, vk::QUERY_TYPE_OCCLUSION, QueryType::Occlusion);
+
+impl OcclusionQueriesPool {
+    /// Builds a new pool of occlusion queries.
+    ///
+    /// Each query produces the number of samples that passed the depth and stencil tests.
+    #[inline]
+    pub fn new(device: &Arc<Device>, num_slots: u32)
+               -> Result<Arc<OcclusionQueriesPool>, OomError>
+    {
+        OcclusionQueriesPool::raw(device, num_slots, 0)
+    }
+}
+
+query_pool!(PipelineStatisticsQueriesPool, vk::QUERY_TYPE_PIPELINE_STATISTICS,
+            QueryType::PipelineStatistics);
+
+impl PipelineStatisticsQueriesPool {
+    /// Builds a new pool of pipeline statistics queries.
+    ///
+    /// Each query produces one value for each statistic that is enabled.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the `pipeline_statistics_query` feature is not enabled on the device.
+    /// - Panicks if no statistic is enabled.
+    ///
+    #[inline]
+    pub fn new(device: &Arc<Device>, num_slots: u32, statistics: QueryPipelineStatistics)
+               -> Result<Arc<PipelineStatisticsQueriesPool>, OomError>
+    {
+        assert!(device.enabled_features().pipeline_statistics_query);
+        assert!(statistics.count() != 0);
+        PipelineStatisticsQueriesPool::raw(device, num_slots, statistics.into())
+    }
+}
+
+query_pool!(TimestampQueriesPool, vk::QUERY_TYPE_TIMESTAMP, QueryType::Timestamp);
+
+impl TimestampQueriesPool {
+    /// Builds a new pool of timestamp queries.
+    ///
+    /// Each query produces a timestamp in an implementation-defined unit. Use
+    /// `get_results_ns` to obtain nanoseconds.
+    #[inline]
+    pub fn new(device: &Arc<Device>, num_slots: u32)
+               -> Result<Arc<TimestampQueriesPool>, OomError>
+    {
+        TimestampQueriesPool::raw(device, num_slots, 0)
+    }
+
+    /// Same as `get_results`, but converts the timestamps to nanoseconds by using the
+    /// `timestamp_period` limit of the physical device.
+    ///
+    /// If `false` is returned, the content of `destination` is unspecified.
+    ///
+    /// # Panic
+    ///
+    /// - Panicks if the range is out of range of the pool.
+    /// - Panicks if `destination` doesn't have the same length as the range.
+    /// - Panicks if `flags.partial` is true.
+    ///
+    pub fn get_results_ns(&self, range: Range<u32>, flags: QueryResultFlags,
+                          destination: &mut [u64]) -> Result<bool, GetResultsError>
+    {
+        let available = try!(self.get_results(range, flags, destination));
+
+        let period = self.device.physical_device().limits().timestamp_period() as f64;
+        for value in destination.iter_mut() {
+            *value = (*value as f64 * period) as u64;
+        }
+
+        Ok(available)
+    }
+}
+
+// Common implementation of the `get_results` methods.
+unsafe fn get_results<T>(device: &Arc<Device>, pool: vk::QueryPool, num_slots: u32,
+                         num_values: usize, range: Range<u32>, flags: QueryResultFlags,
+                         destination: &mut [T]) -> Result<bool, GetResultsError>
+    where T: QueryResultElement
+{
+    assert!(range.start <= range.end);
+    assert!(range.end <= num_slots);
+    assert_eq!(destination.len(), (range.end - range.start) as usize * num_values);
+
+    let vk = device.pointers();
+    let flags: vk::QueryResultFlags = flags.into();
+    let stride = mem::size_of::<T>() * num_values;
+
+    let result = try!(check_errors(vk.GetQueryPoolResults(device.internal_object(), pool,
+                                                          range.start, range.end - range.start,
+                                                          mem::size_of_val(destination),
+                                                          destination.as_mut_ptr() as *mut _,
+                                                          stride as vk::DeviceSize,
+                                                          flags | T::flags())));
+
+    match result {
+        Success::Success => Ok(true),
+        Success::NotReady => Ok(false),
+        _ => unreachable!()
+    }
+}
+
+/// Error that can be returned when reading the results of queries.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GetResultsError {
+    /// Not enough memory.
+    OomError(OomError),
+
+    /// The connection to the device has been lost.
+    DeviceLost,
+}
+
+impl From<OomError> for GetResultsError {
+    #[inline]
+    fn from(err: OomError) -> GetResultsError {
+        GetResultsError::OomError(err)
+    }
+}
+
+impl From<Error> for GetResultsError {
+    #[inline]
+    fn from(err: Error) -> GetResultsError {
+        match err {
+            err @ Error::OutOfHostMemory => GetResultsError::OomError(OomError::from(err)),
+            err @ Error::OutOfDeviceMemory => GetResultsError::OomError(OomError::from(err)),
+            // no other error is allowed by the specifications ; if the implementation returns
+            // one anyway, the state of the device can't be trusted anymore
+            _ => GetResultsError::DeviceLost,
+        }
+    }
+}
+
+impl error::Error for GetResultsError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            GetResultsError::OomError(_) => "no memory available",
+            GetResultsError::DeviceLost => "the connection to the device has been lost",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            GetResultsError::OomError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GetResultsError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
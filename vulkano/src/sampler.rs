@@ -7,6 +7,7 @@ use std::mem;
 use std::ptr;
 use std::sync::Arc;
 
+use device::DebugName;
 use device::Device;
 use OomError;
 use VulkanObject;
@@ -73,6 +74,24 @@ impl Sampler {
     }
 }
 
+unsafe impl VulkanObject for Sampler {
+    type Object = vk::Sampler;
+
+    #[inline]
+    fn internal_object(&self) -> vk::Sampler {
+        self.sampler
+    }
+}
+
+unsafe impl DebugName for Sampler {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_SAMPLER_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl Drop for Sampler {
     #[inline]
     fn drop(&mut self) {
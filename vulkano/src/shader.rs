@@ -5,6 +5,7 @@ use std::ptr;
 use std::sync::Arc;
 use std::ffi::CStr;
 
+use device::DebugName;
 use device::Device;
 use OomError;
 use VulkanObject;
@@ -91,6 +92,28 @@ impl ShaderModule {
             marker: PhantomData,
         }
     }
+
+    /// Gets access to a compute shader entry point contained in this module.
+    ///
+    /// This is purely a *logical* operation. It returns a struct that *represents* the entry
+    /// point but doesn't actually do anything.
+    ///
+    /// # Safety
+    ///
+    /// - The user must check that the entry point exists in the module, as this is not checked
+    ///   by Vulkan.
+    /// - Calling this function also determines the template parameters associated to the
+    ///   `EntryPoint` struct. Therefore care must be taken that the values there are correct.
+    ///
+    pub unsafe fn compute_shader_entry_point<'a, D, S, P>(&'a self, name: &'a CStr)
+                                                          -> ComputeShaderEntryPoint<'a, D, S, P>
+    {
+        ComputeShaderEntryPoint {
+            module: self,
+            name: name,
+            marker: PhantomData,
+        }
+    }
 }
 
 unsafe impl VulkanObject for ShaderModule {
@@ -102,6 +125,15 @@ unsafe impl VulkanObject for ShaderModule {
     }
 }
 
+unsafe impl DebugName for ShaderModule {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_SHADER_MODULE_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl Drop for ShaderModule {
     #[inline]
     fn drop(&mut self) {
@@ -143,6 +175,18 @@ pub struct ComputeShaderEntryPoint<'a, D, S, P> {
     marker: PhantomData<(D, S, P)>
 }
 
+impl<'a, D, S, P> ComputeShaderEntryPoint<'a, D, S, P> {
+    #[inline]
+    pub fn module(&self) -> &'a ShaderModule {
+        self.module
+    }
+
+    #[inline]
+    pub fn name(&self) -> &'a CStr {
+        self.name
+    }
+}
+
 pub struct FragmentShaderEntryPoint<'a, F, L> {
     module: &'a ShaderModule,
     name: &'a CStr,
@@ -9,13 +9,17 @@
 //! But don't worry ; this is automatically enforced by this library (as long as you don't use
 //! any unsafe function). See the `memory` module for more info.
 //!
+use std::error;
+use std::fmt;
 use std::mem;
 use std::ptr;
 use std::sync::Arc;
 use std::sync::Mutex;
 
+use device::DebugName;
 use device::Device;
 use device::Queue;
+use Error;
 use OomError;
 use Success;
 use VulkanObject;
@@ -72,6 +76,138 @@ impl<'a> From<&'a [&'a Arc<Mutex<Queue>>]> for SharingMode {
     }
 }
 
+/// Describes a set of stages of the pipeline.
+///
+/// Used when building pipeline barriers, in order to describe which stages must be finished
+/// before which other stages can start.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct PipelineStages {
+    pub top_of_pipe: bool,
+    pub draw_indirect: bool,
+    pub vertex_input: bool,
+    pub vertex_shader: bool,
+    pub tessellation_control_shader: bool,
+    pub tessellation_evaluation_shader: bool,
+    pub geometry_shader: bool,
+    pub fragment_shader: bool,
+    pub early_fragment_tests: bool,
+    pub late_fragment_tests: bool,
+    pub color_attachment_output: bool,
+    pub compute_shader: bool,
+    pub transfer: bool,
+    pub bottom_of_pipe: bool,
+    pub host: bool,
+    /// Equivalent to all the graphics stages at once.
+    pub all_graphics: bool,
+    /// Equivalent to all the stages at once.
+    pub all_commands: bool,
+}
+
+impl PipelineStages {
+    /// Builds a `PipelineStages` with all stages set to `false`.
+    #[inline]
+    pub fn none() -> PipelineStages {
+        PipelineStages::default()
+    }
+
+    /// Builds a `PipelineStages` with only `all_commands` set to `true`.
+    #[inline]
+    pub fn all_commands() -> PipelineStages {
+        PipelineStages { all_commands: true, .. PipelineStages::default() }
+    }
+}
+
+#[doc(hidden)]
+impl Into<vk::PipelineStageFlagBits> for PipelineStages {
+    #[inline]
+    fn into(self) -> vk::PipelineStageFlagBits {
+        let mut result = 0;
+        if self.top_of_pipe { result |= vk::PIPELINE_STAGE_TOP_OF_PIPE_BIT; }
+        if self.draw_indirect { result |= vk::PIPELINE_STAGE_DRAW_INDIRECT_BIT; }
+        if self.vertex_input { result |= vk::PIPELINE_STAGE_VERTEX_INPUT_BIT; }
+        if self.vertex_shader { result |= vk::PIPELINE_STAGE_VERTEX_SHADER_BIT; }
+        if self.tessellation_control_shader { result |= vk::PIPELINE_STAGE_TESSELLATION_CONTROL_SHADER_BIT; }
+        if self.tessellation_evaluation_shader { result |= vk::PIPELINE_STAGE_TESSELLATION_EVALUATION_SHADER_BIT; }
+        if self.geometry_shader { result |= vk::PIPELINE_STAGE_GEOMETRY_SHADER_BIT; }
+        if self.fragment_shader { result |= vk::PIPELINE_STAGE_FRAGMENT_SHADER_BIT; }
+        if self.early_fragment_tests { result |= vk::PIPELINE_STAGE_EARLY_FRAGMENT_TESTS_BIT; }
+        if self.late_fragment_tests { result |= vk::PIPELINE_STAGE_LATE_FRAGMENT_TESTS_BIT; }
+        if self.color_attachment_output { result |= vk::PIPELINE_STAGE_COLOR_ATTACHMENT_OUTPUT_BIT; }
+        if self.compute_shader { result |= vk::PIPELINE_STAGE_COMPUTE_SHADER_BIT; }
+        if self.transfer { result |= vk::PIPELINE_STAGE_TRANSFER_BIT; }
+        if self.bottom_of_pipe { result |= vk::PIPELINE_STAGE_BOTTOM_OF_PIPE_BIT; }
+        if self.host { result |= vk::PIPELINE_STAGE_HOST_BIT; }
+        if self.all_graphics { result |= vk::PIPELINE_STAGE_ALL_GRAPHICS_BIT; }
+        if self.all_commands { result |= vk::PIPELINE_STAGE_ALL_COMMANDS_BIT; }
+        result
+    }
+}
+
+/// Describes a set of memory accesses.
+///
+/// Used when building memory barriers, in order to describe which writes must be made visible
+/// to which kind of accesses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct AccessFlagBits {
+    pub indirect_command_read: bool,
+    pub index_read: bool,
+    pub vertex_attribute_read: bool,
+    pub uniform_read: bool,
+    pub input_attachment_read: bool,
+    pub shader_read: bool,
+    pub shader_write: bool,
+    pub color_attachment_read: bool,
+    pub color_attachment_write: bool,
+    pub depth_stencil_attachment_read: bool,
+    pub depth_stencil_attachment_write: bool,
+    pub transfer_read: bool,
+    pub transfer_write: bool,
+    pub host_read: bool,
+    pub host_write: bool,
+    pub memory_read: bool,
+    pub memory_write: bool,
+}
+
+impl AccessFlagBits {
+    /// Builds an `AccessFlagBits` with all accesses set to `false`.
+    #[inline]
+    pub fn none() -> AccessFlagBits {
+        AccessFlagBits::default()
+    }
+
+    /// Builds an `AccessFlagBits` with `memory_read` and `memory_write` set to `true`.
+    #[inline]
+    pub fn all() -> AccessFlagBits {
+        AccessFlagBits { memory_read: true, memory_write: true, .. AccessFlagBits::default() }
+    }
+}
+
+#[doc(hidden)]
+impl Into<vk::AccessFlagBits> for AccessFlagBits {
+    #[inline]
+    fn into(self) -> vk::AccessFlagBits {
+        let mut result = 0;
+        if self.indirect_command_read { result |= vk::ACCESS_INDIRECT_COMMAND_READ_BIT; }
+        if self.index_read { result |= vk::ACCESS_INDEX_READ_BIT; }
+        if self.vertex_attribute_read { result |= vk::ACCESS_VERTEX_ATTRIBUTE_READ_BIT; }
+        if self.uniform_read { result |= vk::ACCESS_UNIFORM_READ_BIT; }
+        if self.input_attachment_read { result |= vk::ACCESS_INPUT_ATTACHMENT_READ_BIT; }
+        if self.shader_read { result |= vk::ACCESS_SHADER_READ_BIT; }
+        if self.shader_write { result |= vk::ACCESS_SHADER_WRITE_BIT; }
+        if self.color_attachment_read { result |= vk::ACCESS_COLOR_ATTACHMENT_READ_BIT; }
+        if self.color_attachment_write { result |= vk::ACCESS_COLOR_ATTACHMENT_WRITE_BIT; }
+        if self.depth_stencil_attachment_read { result |= vk::ACCESS_DEPTH_STENCIL_ATTACHMENT_READ_BIT; }
+        if self.depth_stencil_attachment_write { result |= vk::ACCESS_DEPTH_STENCIL_ATTACHMENT_WRITE_BIT; }
+        if self.transfer_read { result |= vk::ACCESS_TRANSFER_READ_BIT; }
+        if self.transfer_write { result |= vk::ACCESS_TRANSFER_WRITE_BIT; }
+        if self.host_read { result |= vk::ACCESS_HOST_READ_BIT; }
+        if self.host_write { result |= vk::ACCESS_HOST_WRITE_BIT; }
+        if self.memory_read { result |= vk::ACCESS_MEMORY_READ_BIT; }
+        if self.memory_write { result |= vk::ACCESS_MEMORY_WRITE_BIT; }
+        result
+    }
+}
+
 /// A fence is used to know when a command buffer submission has finished its execution.
 ///
 /// When a command buffer accesses a ressource, you have to ensure that the CPU doesn't access
@@ -86,16 +222,19 @@ impl Fence {
     /// Builds a new fence.
     #[inline]
     pub fn new(device: &Arc<Device>) -> Result<Arc<Fence>, OomError> {
-        Fence::new_impl(device, false)
+        Ok(try!(Fence::raw(device, false)))
     }
 
     /// Builds a new fence already in the "signaled" state.
     #[inline]
     pub fn signaled(device: &Arc<Device>) -> Result<Arc<Fence>, OomError> {
-        Fence::new_impl(device, true)
+        Ok(try!(Fence::raw(device, true)))
     }
 
-    fn new_impl(device: &Arc<Device>, signaled: bool) -> Result<Arc<Fence>, OomError> {
+    /// Same as `new` or `signaled`, but returns the raw Vulkan error. Used by the functions that
+    /// can handle a lost device.
+    #[doc(hidden)]
+    pub fn raw(device: &Arc<Device>, signaled: bool) -> Result<Arc<Fence>, Error> {
         let vk = device.pointers();
 
         let fence = unsafe {
@@ -116,9 +255,24 @@ impl Fence {
         }))
     }
 
+    /// Returns the device that owns the fence.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
     /// Returns true if the fence is signaled.
+    ///
+    /// Never returns `FenceWaitError::Timeout`.
     #[inline]
-    pub fn ready(&self) -> Result<bool, OomError> {
+    pub fn ready(&self) -> Result<bool, FenceWaitError> {
+        Ok(try!(self.raw_ready()))
+    }
+
+    /// Same as `ready`, but returns the raw Vulkan error. Used by the functions that can handle
+    /// a lost device.
+    #[doc(hidden)]
+    pub fn raw_ready(&self) -> Result<bool, Error> {
         unsafe {
             let vk = self.device.pointers();
             let result = try!(check_errors(vk.GetFenceStatus(self.device.internal_object(),
@@ -135,7 +289,7 @@ impl Fence {
     /// timeout has elapsed.
     ///
     /// Returns `Ok` if the fence is now signaled. Returns `Err` if the timeout was reached instead.
-    pub fn wait(&self, timeout_ns: u64) -> Result<(), OomError> {       // FIXME: wrong error
+    pub fn wait(&self, timeout_ns: u64) -> Result<(), FenceWaitError> {
         unsafe {
             let vk = self.device.pointers();
             let r = try!(check_errors(vk.WaitForFences(self.device.internal_object(), 1,
@@ -143,7 +297,7 @@ impl Fence {
 
             match r {
                 Success::Success => Ok(()),
-                Success::Timeout => panic!(),        // FIXME:
+                Success::Timeout => Err(FenceWaitError::Timeout),
                 _ => unreachable!()
             }
         }
@@ -197,6 +351,15 @@ unsafe impl VulkanObject for Fence {
     }
 }
 
+unsafe impl DebugName for Fence {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_FENCE_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl Drop for Fence {
     #[inline]
     fn drop(&mut self) {
@@ -207,6 +370,65 @@ impl Drop for Fence {
     }
 }
 
+/// Error that can be returned when waiting on a fence.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FenceWaitError {
+    /// Not enough memory to complete the wait.
+    OomError(OomError),
+
+    /// The timeout has been reached before the fence was signaled.
+    Timeout,
+
+    /// The connection to the device has been lost. The fence will never be signaled.
+    DeviceLost,
+}
+
+impl From<OomError> for FenceWaitError {
+    #[inline]
+    fn from(err: OomError) -> FenceWaitError {
+        FenceWaitError::OomError(err)
+    }
+}
+
+impl error::Error for FenceWaitError {
+    #[inline]
+    fn description(&self) -> &str {
+        match *self {
+            FenceWaitError::OomError(_) => "no memory available",
+            FenceWaitError::Timeout => "the timeout has been reached",
+            FenceWaitError::DeviceLost => "the connection to the device has been lost",
+        }
+    }
+
+    #[inline]
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            FenceWaitError::OomError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FenceWaitError {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", error::Error::description(self))
+    }
+}
+
+impl From<Error> for FenceWaitError {
+    #[inline]
+    fn from(err: Error) -> FenceWaitError {
+        match err {
+            err @ Error::OutOfHostMemory => FenceWaitError::OomError(OomError::from(err)),
+            err @ Error::OutOfDeviceMemory => FenceWaitError::OomError(OomError::from(err)),
+            // no other error is allowed by the specifications ; if the implementation returns
+            // one anyway, the state of the device can't be trusted anymore
+            _ => FenceWaitError::DeviceLost,
+        }
+    }
+}
+
 /// Used to provide synchronization between command buffers during their execution.
 /// 
 /// It is similar to a fence, except that it is purely on the GPU side. The CPU can't query a
@@ -220,6 +442,13 @@ impl Semaphore {
     /// Builds a new semaphore.
     #[inline]
     pub fn new(device: &Arc<Device>) -> Result<Arc<Semaphore>, OomError> {
+        Ok(try!(Semaphore::raw(device)))
+    }
+
+    /// Same as `new`, but returns the raw Vulkan error. Used by the functions that can handle a
+    /// lost device.
+    #[doc(hidden)]
+    pub fn raw(device: &Arc<Device>) -> Result<Arc<Semaphore>, Error> {
         let vk = device.pointers();
 
         let semaphore = unsafe {
@@ -241,6 +470,12 @@ impl Semaphore {
             semaphore: semaphore,
         }))
     }
+
+    /// Returns the device that owns the semaphore.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
 }
 
 unsafe impl VulkanObject for Semaphore {
@@ -252,6 +487,15 @@ unsafe impl VulkanObject for Semaphore {
     }
 }
 
+unsafe impl DebugName for Semaphore {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_SEMAPHORE_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl Drop for Semaphore {
     #[inline]
     fn drop(&mut self) {
@@ -299,6 +543,12 @@ impl Event {
         }))
     }
 
+    /// Returns the device that owns the event.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
     /// Returns true if the event is signaled.
     #[inline]
     pub fn signaled(&self) -> Result<bool, OomError> {
@@ -346,6 +596,15 @@ unsafe impl VulkanObject for Event {
     }
 }
 
+unsafe impl DebugName for Event {
+    const OBJECT_TYPE: u32 = vk::DEBUG_REPORT_OBJECT_TYPE_EVENT_EXT;
+
+    #[inline]
+    fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+}
+
 impl Drop for Event {
     #[inline]
     fn drop(&mut self) {